@@ -7,7 +7,6 @@ use prometheus_client::metrics::family::Family;
 use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
 use std::io::Write;
-use std::sync::atomic::AtomicU64;
 
 pub fn text(c: &mut Criterion) {
     c.bench_function("encode", |b| {
@@ -23,7 +22,7 @@ pub fn text(c: &mut Criterion) {
             Get,
             #[allow(dead_code)]
             Put,
-        };
+        }
 
         #[derive(Clone, Hash, PartialEq, Eq)]
         enum Status {
@@ -32,7 +31,7 @@ pub fn text(c: &mut Criterion) {
             Four,
             #[allow(dead_code)]
             Five,
-        };
+        }
 
         impl Encode for Status {
             fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
@@ -90,6 +89,26 @@ pub fn text(c: &mut Criterion) {
             black_box(&mut buffer);
         })
     });
+
+    c.bench_function("encode_10k_series", |b| {
+        let mut registry = Registry::<Box<dyn EncodeMetric>>::default();
+        let counter_family = Family::<Vec<(&'static str, String)>, Counter>::default();
+        registry.register("my_counter", "My counter", Box::new(counter_family.clone()));
+
+        for i in 0..10_000 {
+            counter_family
+                .get_or_create(&vec![("id", i.to_string())])
+                .inc();
+        }
+
+        let mut buffer = vec![];
+
+        b.iter(|| {
+            buffer.clear();
+            encode(&mut buffer, &registry).unwrap();
+            black_box(&mut buffer);
+        })
+    });
 }
 
 criterion_group!(benches, text);
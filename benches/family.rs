@@ -1,6 +1,28 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 use prometheus_client::metrics::counter::Counter;
-use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::family::{Family, Interner};
+use std::hash::{BuildHasherDefault, Hasher};
+use std::sync::Arc;
+
+/// Minimal port of the non-cryptographic `FxHasher` used within rustc, to
+/// compare against the default SipHash.
+#[derive(Default)]
+struct FxHasher {
+    hash: u64,
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash = (self.hash.rotate_left(5) ^ u64::from(*byte))
+                .wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
 
 pub fn family(c: &mut Criterion) {
     c.bench_function("counter family with Vec<(String, String)> label set", |b| {
@@ -28,7 +50,7 @@ pub fn family(c: &mut Criterion) {
             Get,
             #[allow(dead_code)]
             Put,
-        };
+        }
 
         #[derive(Clone, Hash, PartialEq, Eq)]
         enum Status {
@@ -37,7 +59,7 @@ pub fn family(c: &mut Criterion) {
             Four,
             #[allow(dead_code)]
             Five,
-        };
+        }
         let family = Family::<Labels, Counter>::default();
 
         b.iter(|| {
@@ -49,7 +71,44 @@ pub fn family(c: &mut Criterion) {
                 .inc();
         })
     });
+
+    c.bench_function(
+        "counter family with Vec<(String, String)> label set and FxHasher",
+        |b| {
+            let family = Family::<Vec<(String, String)>, Counter, _, _>::new_with_hasher(
+                Counter::default,
+                1,
+                BuildHasherDefault::<FxHasher>::default(),
+            );
+
+            b.iter(|| {
+                family
+                    .get_or_create(&vec![
+                        ("method".to_owned(), "GET".to_owned()),
+                        ("status".to_owned(), "200".to_owned()),
+                    ])
+                    .inc();
+            })
+        },
+    );
+}
+
+pub fn family_interned(c: &mut Criterion) {
+    c.bench_function("counter family with interned label set", |b| {
+        let interner = Interner::default();
+        let family = Family::<Vec<(Arc<str>, Arc<str>)>, Counter>::default();
+
+        b.iter(|| {
+            family
+                .get_or_create(&vec![
+                    (interner.intern("method"), interner.intern("GET")),
+                    (interner.intern("status"), interner.intern("200")),
+                    (interner.intern("instance"), interner.intern("instance-0")),
+                ])
+                .inc();
+        })
+    });
 }
 
-criterion_group!(benches, family);
+criterion_group!(benches, family, family_interned);
 criterion_main!(benches);
@@ -5,37 +5,120 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::DeriveInput;
 
-#[proc_macro_derive(Encode)]
+/// Derives `Encode` for a struct of labels or an enum of label values.
+///
+/// Fields of a struct are encoded as labels in declaration order. A field of
+/// type `Option<T>` is omitted when `None`. Note that a label set lacking an
+/// optional label is a distinct series from one with the label present.
+///
+/// With `#[prometheus(rename = "...")]` a field is encoded as a label with the
+/// given name instead of the field name. The name has to be a valid label name
+/// that is not reserved, failing compilation otherwise:
+///
+/// ```compile_fail
+/// # use prometheus_client::encoding::text::Encode;
+/// #
+/// #[derive(Encode)]
+/// struct Labels {
+///     #[prometheus(rename = "http-method")]
+///     method: String,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use prometheus_client::encoding::text::Encode;
+/// #
+/// #[derive(Encode)]
+/// struct Labels {
+///     #[prometheus(rename = "le")]
+///     bucket: String,
+/// }
+/// ```
+///
+/// With `#[prometheus(name = "...")]` an enum of unit variants or a newtype
+/// struct is encoded as a label set of a single label with the given name,
+/// e.g. `method="Get"`, usable directly as the label set of a `Family`. The
+/// field of a newtype struct has to implement `EncodeLabelValue`.
+#[proc_macro_derive(Encode, attributes(prometheus))]
 pub fn derive_encode(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).unwrap();
     let name = &ast.ident;
 
+    if let Some(label_name) = attribute_value(&ast.attrs, "name") {
+        return derive_encode_single_label(name, &label_name, ast.data).into();
+    }
+
     let body = match ast.data {
         syn::Data::Struct(s) => match s.fields {
-            syn::Fields::Named(syn::FieldsNamed { named, .. }) => named
-                .into_iter()
-                .enumerate()
-                .map(|(i, f)| {
-                    let ident = f.ident.unwrap();
-                    let ident_string = KEYWORD_IDENTIFIERS
-                        .iter()
-                        .find(|pair| ident == pair.1)
-                        .map(|pair| pair.0.to_string())
-                        .unwrap_or_else(|| ident.to_string());
-
-                    let maybe_comma = if i == 0 {
-                        TokenStream2::default()
-                    } else {
-                        quote! { writer.write_all(b",")?; }
-                    };
+            syn::Fields::Named(syn::FieldsNamed { named, .. }) => {
+                let has_optional_field = named.iter().any(|f| is_option(&f.ty));
+
+                let fields: TokenStream2 = named
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, f)| {
+                        let rename = attribute_value(&f.attrs, "rename");
+                        if let Some(rename) = &rename {
+                            if !is_valid_label_name(rename) {
+                                panic!("Invalid label name {:?}.", rename);
+                            }
+                        }
+                        let optional = is_option(&f.ty);
+                        let ident = f.ident.unwrap();
+                        let ident_string = rename.unwrap_or_else(|| {
+                            KEYWORD_IDENTIFIERS
+                                .iter()
+                                .find(|pair| ident == pair.1)
+                                .map(|pair| pair.0.to_string())
+                                .unwrap_or_else(|| ident.to_string())
+                        });
+
+                        // With optional fields present, whether a comma is
+                        // needed is only known at runtime.
+                        let maybe_comma = if has_optional_field {
+                            quote! {
+                                writer.write_all(separator)?;
+                                separator = b",";
+                            }
+                        } else if i == 0 {
+                            TokenStream2::default()
+                        } else {
+                            quote! { writer.write_all(b",")?; }
+                        };
+                        let encode_label = |value: TokenStream2| {
+                            quote! {
+                                #maybe_comma
+                                writer.write_all(concat!(#ident_string, "=\"").as_bytes())?;
+                                prometheus_client::encoding::text::Encode::encode(#value, writer)?;
+                                writer.write_all(b"\"")?;
+                            }
+                        };
+
+                        if optional {
+                            let encode_label = encode_label(quote! { value });
+                            quote! {
+                                if let Some(value) = &self.#ident {
+                                    #encode_label
+                                }
+                            }
+                        } else {
+                            encode_label(quote! { &self.#ident })
+                        }
+                    })
+                    .collect();
+
+                if has_optional_field {
                     quote! {
-                        #maybe_comma
-                        writer.write_all(concat!(#ident_string, "=\"").as_bytes())?;
-                        prometheus_client::encoding::text::Encode::encode(&self.#ident, writer)?;
-                        writer.write_all(b"\"")?;
+                        #[allow(unused_assignments)]
+                        {
+                            let mut separator: &[u8] = b"";
+                            #fields
+                        }
                     }
-                })
-                .collect(),
+                } else {
+                    fields
+                }
+            }
             syn::Fields::Unnamed(_) => {
                 panic!("Can not derive Encode for struct with unnamed fields.")
             }
@@ -73,6 +156,186 @@ pub fn derive_encode(input: TokenStream) -> TokenStream {
     gen.into()
 }
 
+/// Derives `Encode` for an enum or newtype struct annotated with
+/// `#[prometheus(name = "...")]`, encoding a single label.
+fn derive_encode_single_label(
+    name: &syn::Ident,
+    label_name: &str,
+    data: syn::Data,
+) -> TokenStream2 {
+    if !is_valid_label_name(label_name) {
+        panic!("Invalid label name {:?}.", label_name);
+    }
+
+    let value = match data {
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => {
+            let match_arms: TokenStream2 = variants
+                .into_iter()
+                .map(|v| {
+                    if !matches!(v.fields, syn::Fields::Unit) {
+                        panic!(
+                            "Can not derive Encode with a label name for enum variant with fields."
+                        )
+                    }
+
+                    let ident = v.ident;
+                    quote! {
+                        #name::#ident => writer.write_all(stringify!(#ident).as_bytes())?,
+                    }
+                })
+                .collect();
+
+            quote! {
+                match self {
+                    #match_arms
+                }
+            }
+        }
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. }),
+            ..
+        }) if unnamed.len() == 1 => quote! {
+            prometheus_client::encoding::text::EncodeLabelValue::encode_label_value(&self.0, writer)?;
+        },
+        _ => panic!("Can only derive Encode with a label name for enums and newtype structs."),
+    };
+
+    quote! {
+        impl prometheus_client::encoding::text::Encode for #name {
+            fn encode(&self, writer: &mut dyn std::io::Write) -> std::result::Result<(), std::io::Error> {
+                writer.write_all(concat!(#label_name, "=\"").as_bytes())?;
+                #value
+                writer.write_all(b"\"")?;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[proc_macro_derive(EncodeLabelValue)]
+pub fn derive_encode_label_value(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    let name = &ast.ident;
+
+    let match_arms: TokenStream2 = match ast.data {
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => variants
+            .into_iter()
+            .map(|v| {
+                if !matches!(v.fields, syn::Fields::Unit) {
+                    panic!("Can not derive EncodeLabelValue for enum variant with fields.")
+                }
+
+                let ident = v.ident;
+                quote! {
+                    #name::#ident => writer.write_all(stringify!(#ident).as_bytes())?,
+                }
+            })
+            .collect(),
+        _ => panic!("Can only derive EncodeLabelValue for enums."),
+    };
+
+    let gen = quote! {
+        impl prometheus_client::encoding::text::EncodeLabelValue for #name {
+            fn encode_label_value(&self, writer: &mut dyn std::io::Write) -> std::result::Result<(), std::io::Error> {
+                match self {
+                    #match_arms
+                }
+
+                Ok(())
+            }
+        }
+    };
+    gen.into()
+}
+
+#[proc_macro_derive(EnumLike)]
+pub fn derive_enum_like(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    let name = &ast.ident;
+
+    let idents: Vec<syn::Ident> = match ast.data {
+        syn::Data::Enum(syn::DataEnum { variants, .. }) => variants
+            .into_iter()
+            .map(|v| {
+                if !matches!(v.fields, syn::Fields::Unit) {
+                    panic!("Can not derive EnumLike for enum variant with fields.")
+                }
+
+                v.ident
+            })
+            .collect(),
+        _ => panic!("Can only derive EnumLike for enums."),
+    };
+
+    let gen = quote! {
+        impl prometheus_client::metrics::state_set::EnumLike for #name {
+            fn variants() -> &'static [Self] {
+                &[#(#name::#idents),*]
+            }
+
+            fn name(&self) -> &'static str {
+                match self {
+                    #(#name::#idents => stringify!(#idents),)*
+                }
+            }
+        }
+    };
+    gen.into()
+}
+
+/// Whether the given type is an `Option`, judged by the last path segment.
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .map(|segment| {
+                segment.ident == "Option"
+                    && matches!(segment.arguments, syn::PathArguments::AngleBracketed(_))
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Extracts the value set via `#[prometheus(<key> = "...")]`, if any, e.g.
+/// the `rename` of a field or the label `name` of a single label enum.
+fn attribute_value(attrs: &[syn::Attribute], key: &str) -> Option<String> {
+    let mut value = None;
+
+    for attr in attrs.iter().filter(|a| a.path.is_ident("prometheus")) {
+        let nested = match attr.parse_meta() {
+            Ok(syn::Meta::List(syn::MetaList { nested, .. })) => nested,
+            _ => panic!("Expected `#[prometheus({} = \"...\")]`.", key),
+        };
+
+        for meta in nested {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) if path.is_ident(key) => value = Some(lit.value()),
+                _ => panic!("Expected `#[prometheus({} = \"...\")]`.", key),
+            }
+        }
+    }
+
+    value
+}
+
+/// Whether the given label name is valid in the OpenMetrics text format and
+/// not reserved, checked at compile time.
+fn is_valid_label_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .enumerate()
+            .all(|(i, c)| c.is_ascii_alphabetic() || c == '_' || (i > 0 && c.is_ascii_digit()))
+        && !["__name__", "le", "quantile"].contains(&name)
+}
+
 // Copied from https://github.com/djc/askama (MIT and APACHE licensed) and
 // modified.
 static KEYWORD_IDENTIFIERS: [(&str, &str); 48] = [
@@ -1,6 +1,7 @@
-use prometheus_client::encoding::text::{encode, Encode};
+use prometheus_client::encoding::text::{encode, Encode, EncodeLabelValue};
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::state_set::{EnumLike, StateSet};
 use prometheus_client::registry::Registry;
 
 #[test]
@@ -10,14 +11,15 @@ fn basic_flow() {
     struct Labels {
         method: Method,
         path: String,
-    };
+    }
 
+    #[allow(clippy::upper_case_acronyms)]
     #[derive(Clone, Hash, PartialEq, Eq, Encode)]
     enum Method {
         GET,
         #[allow(dead_code)]
         PUT,
-    };
+    }
 
     let family = Family::<Labels, Counter>::default();
     registry.register("my_counter", "This is my counter", family.clone());
@@ -52,16 +54,198 @@ fn remap_keyword_identifiers() {
         // Test makes sure `r#type` is replaced by `type` in the OpenMetrics
         // output.
         r#type: u64,
-    };
+    }
 
     let labels = Labels { r#type: 42 };
 
     let mut buffer = vec![];
 
-    labels.encode(&mut buffer);
+    labels.encode(&mut buffer).unwrap();
 
     assert_eq!(
         "type=\"42\"".to_string(),
         String::from_utf8(buffer).unwrap()
     );
 }
+
+#[test]
+fn rename_field() {
+    #[derive(Encode, Hash, Clone, Eq, PartialEq)]
+    struct Labels {
+        #[prometheus(rename = "http_method")]
+        method: String,
+        path: String,
+    }
+
+    let labels = Labels {
+        method: "GET".to_string(),
+        path: "/metrics".to_string(),
+    };
+
+    let mut buffer = vec![];
+
+    labels.encode(&mut buffer).unwrap();
+
+    assert_eq!(
+        "http_method=\"GET\",path=\"/metrics\"".to_string(),
+        String::from_utf8(buffer).unwrap()
+    );
+}
+
+#[test]
+fn single_label() {
+    #[derive(Clone, Hash, PartialEq, Eq, Encode)]
+    #[prometheus(name = "method")]
+    enum Method {
+        Get,
+        Put,
+    }
+
+    #[derive(Clone, Hash, PartialEq, Eq, Encode)]
+    #[prometheus(name = "status")]
+    struct Status(u64);
+
+    let mut registry = <Registry>::default();
+    let methods = Family::<Method, Counter>::default();
+    registry.register("requests", "Number of requests", Box::new(methods.clone()));
+    let statuses = Family::<Status, Counter>::default();
+    registry.register(
+        "responses",
+        "Number of responses",
+        Box::new(statuses.clone()),
+    );
+
+    methods.get_or_create(&Method::Get).inc();
+    methods.get_or_create(&Method::Put).inc_by(2);
+    statuses.get_or_create(&Status(200)).inc();
+
+    let mut buffer = vec![];
+    encode(&mut buffer, &registry).unwrap();
+
+    let expected = "# HELP requests Number of requests.\n".to_owned()
+        + "# TYPE requests counter\n"
+        + "requests_total{method=\"Get\"} 1\n"
+        + "requests_total{method=\"Put\"} 2\n"
+        + "# HELP responses Number of responses.\n"
+        + "# TYPE responses counter\n"
+        + "responses_total{status=\"200\"} 1\n"
+        + "# EOF\n";
+    assert_eq!(expected, String::from_utf8(buffer).unwrap());
+}
+
+#[test]
+fn optional_fields() {
+    #[derive(Encode, Hash, Clone, Eq, PartialEq)]
+    struct Labels {
+        method: Option<String>,
+        path: String,
+        status: Option<u64>,
+    }
+
+    let mut registry = Registry::default();
+    let family = Family::<Labels, Counter>::default();
+    registry.register("my_counter", "This is my counter", family.clone());
+
+    for (method, status) in [
+        (Some("GET"), Some(200)),
+        (None, Some(200)),
+        (Some("GET"), None),
+        (None, None),
+    ] {
+        family
+            .get_or_create(&Labels {
+                method: method.map(ToString::to_string),
+                path: "/metrics".to_string(),
+                status,
+            })
+            .inc();
+    }
+
+    let mut buffer = vec![];
+    encode(&mut buffer, &registry).unwrap();
+
+    let expected = "# HELP my_counter This is my counter.\n".to_owned()
+        + "# TYPE my_counter counter\n"
+        + "my_counter_total{method=\"GET\",path=\"/metrics\"} 1\n"
+        + "my_counter_total{method=\"GET\",path=\"/metrics\",status=\"200\"} 1\n"
+        + "my_counter_total{path=\"/metrics\"} 1\n"
+        + "my_counter_total{path=\"/metrics\",status=\"200\"} 1\n"
+        + "# EOF\n";
+    assert_eq!(expected, String::from_utf8(buffer).unwrap());
+}
+
+#[test]
+fn only_optional_fields() {
+    #[derive(Encode, Hash, Clone, Eq, PartialEq)]
+    struct Labels {
+        method: Option<String>,
+    }
+
+    let mut registry = Registry::default();
+    let family = Family::<Labels, Counter>::default();
+    registry.register("my_counter", "This is my counter", family.clone());
+    family.get_or_create(&Labels { method: None }).inc();
+
+    let mut buffer = vec![];
+    encode(&mut buffer, &registry).unwrap();
+
+    let expected = "# HELP my_counter This is my counter.\n".to_owned()
+        + "# TYPE my_counter counter\n"
+        + "my_counter_total 1\n"
+        + "# EOF\n";
+    assert_eq!(expected, String::from_utf8(buffer).unwrap());
+}
+
+#[test]
+fn label_value_enum() {
+    #[derive(Clone, Hash, PartialEq, Eq, EncodeLabelValue)]
+    enum Method {
+        Get,
+        #[allow(dead_code)]
+        Put,
+    }
+
+    let mut registry = Registry::default();
+    let family = Family::<Vec<(String, Method)>, Counter>::default();
+    registry.register("my_counter", "This is my counter", family.clone());
+
+    family
+        .get_or_create(&vec![("method".to_string(), Method::Get)])
+        .inc();
+
+    let mut buffer = vec![];
+    encode(&mut buffer, &registry).unwrap();
+
+    let expected = "# HELP my_counter This is my counter.\n".to_owned()
+        + "# TYPE my_counter counter\n"
+        + "my_counter_total{method=\"Get\"} 1\n"
+        + "# EOF\n";
+    assert_eq!(expected, String::from_utf8(buffer).unwrap());
+}
+
+#[test]
+fn enum_like() {
+    #[derive(Clone, Copy, Debug, PartialEq, EnumLike)]
+    enum Connection {
+        Down,
+        Up,
+    }
+
+    assert_eq!(&[Connection::Down, Connection::Up], Connection::variants());
+    assert_eq!("Up", Connection::Up.name());
+
+    let mut registry = Registry::default();
+    let state_set = StateSet::<Connection>::default();
+    registry.register("connection", "Connection state", state_set.clone());
+    state_set.set(Connection::Up);
+
+    let mut buffer = vec![];
+    encode(&mut buffer, &registry).unwrap();
+
+    let expected = "# HELP connection Connection state.\n".to_owned()
+        + "# TYPE connection stateset\n"
+        + "connection{connection=\"Down\"} 0\n"
+        + "connection{connection=\"Up\"} 1\n"
+        + "# EOF\n";
+    assert_eq!(expected, String::from_utf8(buffer).unwrap());
+}
@@ -1,4 +1,5 @@
 use prometheus_client::encoding::text::{encode, Encode};
+use prometheus_client::encoding::{content_type, Format};
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
 use prometheus_client::registry::Registry;
@@ -34,7 +35,7 @@ async fn main() -> std::result::Result<(), std::io::Error> {
             encode(&mut encoded, &req.state().registry.lock().unwrap()).unwrap();
             let response = tide::Response::builder(200)
                 .body(encoded)
-                .content_type("application/openmetrics-text; version=1.0.0; charset=utf-8")
+                .content_type(content_type(Format::OpenMetricsText))
                 .build();
             Ok(response)
         });
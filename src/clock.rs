@@ -0,0 +1,45 @@
+//! Time source for the timestamps recorded by metrics.
+//!
+//! See [`Clock`] for details.
+
+use std::time::SystemTime;
+
+/// A source of the current time, used wherever a metric records a timestamp,
+/// e.g. [`CounterWithCreated::new_with_clock`] or
+/// [`GaugeWithTimestamp::set_with_clock`].
+///
+/// Production code uses the [`SystemClock`]. Tests can instead supply a fixed
+/// or otherwise controlled time, without sleeping or asserting on time
+/// ranges. Any `Fn() -> SystemTime` is a [`Clock`].
+///
+/// ```
+/// # use prometheus_client::metrics::counter::CounterWithCreated;
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// let clock = || UNIX_EPOCH + Duration::from_secs(42);
+///
+/// let counter = CounterWithCreated::<u64>::new_with_clock(&clock);
+/// assert_eq!(UNIX_EPOCH + Duration::from_secs(42), counter.created());
+/// ```
+///
+/// [`CounterWithCreated::new_with_clock`]: crate::metrics::counter::CounterWithCreated::new_with_clock
+/// [`GaugeWithTimestamp::set_with_clock`]: crate::metrics::gauge::GaugeWithTimestamp::set_with_clock
+pub trait Clock: Send + Sync {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// [`Clock`] returning the system time, see [`SystemTime::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl<F: Fn() -> SystemTime + Send + Sync> Clock for F {
+    fn now(&self) -> SystemTime {
+        self()
+    }
+}
@@ -0,0 +1,65 @@
+//! Metric collector implementation.
+//!
+//! See [`Collector`] for details.
+
+use crate::encoding::text::EncodeMetric;
+use crate::registry::Descriptor;
+
+pub mod process;
+
+/// A collector producing metrics at encode time.
+///
+/// In contrast to metrics registered via
+/// [`Registry::register`](crate::registry::Registry::register), which live for
+/// the lifetime of the [`Registry`](crate::registry::Registry), a [`Collector`]
+/// is asked for a fresh set of metrics each time the
+/// [`Registry`](crate::registry::Registry) is encoded. This is useful for values
+/// that are cheaper to compute on demand, e.g. the depth of a queue or the size
+/// of a cache.
+///
+/// A [`Collector`] is registered via
+/// [`Registry::register_collector`](crate::registry::Registry::register_collector).
+///
+/// ```
+/// # use prometheus_client::collector::Collector;
+/// # use prometheus_client::encoding::text::{encode, EncodeMetric};
+/// # use prometheus_client::metrics::gauge::{Atomic as _, Gauge};
+/// # use prometheus_client::registry::{Descriptor, Registry};
+/// # use std::sync::{Arc, Mutex};
+/// #
+/// struct QueueCollector {
+///     queue: Arc<Mutex<Vec<u64>>>,
+/// }
+///
+/// impl Collector for QueueCollector {
+///     fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+///         let gauge: Gauge = Gauge::default();
+///         gauge.set(self.queue.lock().unwrap().len() as u64);
+///
+///         vec![(
+///             Descriptor::new("queue_depth", "Number of queued items", None, vec![]),
+///             Box::new(gauge),
+///         )]
+///     }
+/// }
+///
+/// let mut registry = <Registry>::default();
+/// let queue = Arc::new(Mutex::new(vec![1, 2, 3]));
+/// registry.register_collector(Box::new(QueueCollector { queue }));
+///
+/// let mut buffer = vec![];
+/// encode(&mut buffer, &registry).unwrap();
+///
+/// let expected = "# HELP queue_depth Number of queued items.\n".to_owned() +
+///                "# TYPE queue_depth gauge\n" +
+///                "queue_depth 3\n" +
+///                "# EOF\n";
+/// assert_eq!(expected, String::from_utf8(buffer).unwrap());
+/// ```
+pub trait Collector: Send {
+    /// Collect the current set of metrics.
+    ///
+    /// The prefix and labels of the (sub-)registry the [`Collector`] is
+    /// registered with are applied to each returned [`Descriptor`].
+    fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)>;
+}
@@ -0,0 +1,293 @@
+//! Module implementing a collector of the standard process metrics.
+//!
+//! See [`ProcessCollector`] for details.
+
+use super::Collector;
+use crate::encoding::text::EncodeMetric;
+use crate::registry::Descriptor;
+
+/// Collector of the standard process metrics, e.g. `process_cpu_seconds_total`,
+/// `process_resident_memory_bytes` and `process_open_fds`.
+///
+/// The metrics are read from `/proc/self` each time the
+/// [`Registry`](crate::registry::Registry) is encoded. On platforms other than
+/// Linux no metrics are collected.
+///
+/// ```
+/// # use prometheus_client::collector::process::ProcessCollector;
+/// # use prometheus_client::encoding::text::encode;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = <Registry>::default();
+/// registry.register_collector(Box::new(ProcessCollector::new()));
+///
+/// let mut buffer = vec![];
+/// encode(&mut buffer, &registry).unwrap();
+/// # #[cfg(target_os = "linux")]
+/// # assert!(String::from_utf8(buffer).unwrap().contains("process_open_fds "));
+/// ```
+#[derive(Debug)]
+pub struct ProcessCollector {
+    namespace: String,
+}
+
+impl ProcessCollector {
+    /// Create a [`ProcessCollector`] using the default `process` namespace.
+    pub fn new() -> Self {
+        Self::with_namespace("process")
+    }
+
+    /// Create a [`ProcessCollector`] prefixing each metric with the given
+    /// namespace instead of the default `process`.
+    ///
+    /// ```
+    /// # use prometheus_client::collector::process::ProcessCollector;
+    /// #
+    /// let collector = ProcessCollector::with_namespace("my_service");
+    /// assert_eq!("my_service", collector.namespace());
+    /// ```
+    pub fn with_namespace<N: Into<String>>(namespace: N) -> Self {
+        Self {
+            namespace: namespace.into(),
+        }
+    }
+
+    /// The namespace each metric name is prefixed with.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+}
+
+impl Default for ProcessCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector for ProcessCollector {
+    #[cfg(target_os = "linux")]
+    fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+        linux::collect(&self.namespace)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+        vec![]
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{Descriptor, EncodeMetric};
+    use crate::encoding::text::Encoder;
+    use crate::metrics::MetricType;
+    use crate::registry::Unit;
+    use std::fs;
+
+    pub(super) fn collect(namespace: &str) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+        let stat = Stat::read();
+        // The CPU and start times in `/proc/self/stat` are given in clock
+        // ticks (`USER_HZ`), as reported by `sysconf(_SC_CLK_TCK)`.
+        let ticks_per_second = rustix::param::clock_ticks_per_second() as f64;
+        let name = |n: &str| format!("{}_{}", namespace, n);
+        let mut metrics: Vec<(Descriptor, Box<dyn EncodeMetric>)> = vec![];
+        let mut push = |desc: Descriptor, metric_type: MetricType, value: Option<f64>| {
+            if let Some(value) = value {
+                metrics.push((desc, Box::new(Sample { metric_type, value })));
+            }
+        };
+
+        push(
+            Descriptor::new(
+                name("cpu"),
+                "Total user and system CPU time spent",
+                Some(Unit::Seconds),
+                vec![],
+            ),
+            MetricType::Counter,
+            stat.as_ref()
+                .map(|s| (s.utime + s.stime) as f64 / ticks_per_second),
+        );
+        push(
+            Descriptor::new(
+                name("resident_memory"),
+                "Resident memory size",
+                Some(Unit::Bytes),
+                vec![],
+            ),
+            MetricType::Gauge,
+            resident_memory(),
+        );
+        push(
+            Descriptor::new(
+                name("virtual_memory"),
+                "Virtual memory size",
+                Some(Unit::Bytes),
+                vec![],
+            ),
+            MetricType::Gauge,
+            stat.as_ref().map(|s| s.vsize as f64),
+        );
+        push(
+            Descriptor::new(
+                name("open_fds"),
+                "Number of open file descriptors",
+                None,
+                vec![],
+            ),
+            MetricType::Gauge,
+            open_fds(),
+        );
+        push(
+            Descriptor::new(
+                name("threads"),
+                "Number of OS threads in the process",
+                None,
+                vec![],
+            ),
+            MetricType::Gauge,
+            stat.as_ref().map(|s| s.num_threads as f64),
+        );
+        push(
+            Descriptor::new(
+                name("start_time"),
+                "Start time of the process since unix epoch",
+                Some(Unit::Seconds),
+                vec![],
+            ),
+            MetricType::Gauge,
+            stat.as_ref()
+                .and_then(|s| Some(boot_time()? + s.start_time as f64 / ticks_per_second)),
+        );
+
+        metrics
+    }
+
+    /// A single sample of a process metric, read at collect time.
+    struct Sample {
+        metric_type: MetricType,
+        value: f64,
+    }
+
+    impl EncodeMetric for Sample {
+        fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+            let mut bucket_encoder = match self.metric_type {
+                MetricType::Counter => encoder.encode_suffix("total")?,
+                _ => encoder.no_suffix()?,
+            };
+            bucket_encoder
+                .no_bucket()?
+                .encode_value(self.value)?
+                .no_exemplar()?;
+
+            Ok(())
+        }
+
+        fn metric_type(&self) -> MetricType {
+            self.metric_type
+        }
+    }
+
+    fn resident_memory() -> Option<f64> {
+        // `/proc/self/stat` reports the resident set size in pages. Use
+        // `/proc/self/status`, reporting it in kB, instead to not depend on the
+        // page size.
+        let kb = fs::read_to_string("/proc/self/status")
+            .ok()?
+            .lines()
+            .find_map(|l| l.strip_prefix("VmRSS:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some((kb * 1024) as f64)
+    }
+
+    fn open_fds() -> Option<f64> {
+        Some(fs::read_dir("/proc/self/fd").ok()?.count() as f64)
+    }
+
+    fn boot_time() -> Option<f64> {
+        fs::read_to_string("/proc/stat")
+            .ok()?
+            .lines()
+            .find_map(|l| l.strip_prefix("btime "))?
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(|t| t as f64)
+    }
+
+    /// The subset of `/proc/self/stat` used by [`collect`].
+    struct Stat {
+        utime: u64,
+        stime: u64,
+        num_threads: u64,
+        start_time: u64,
+        vsize: u64,
+    }
+
+    impl Stat {
+        fn read() -> Option<Self> {
+            Self::parse(&fs::read_to_string("/proc/self/stat").ok()?)
+        }
+
+        fn parse(stat: &str) -> Option<Self> {
+            // The second field is the executable name in parentheses, which may
+            // itself contain spaces and parentheses. Skip past the last closing
+            // parenthesis. The remaining fields start with field 3 (`state`).
+            let fields: Vec<&str> = stat
+                .get(stat.rfind(')')? + 1..)?
+                .split_whitespace()
+                .collect();
+            let field = |n: usize| -> Option<u64> { fields.get(n - 3)?.parse().ok() };
+
+            Some(Stat {
+                utime: field(14)?,
+                stime: field(15)?,
+                num_threads: field(20)?,
+                start_time: field(22)?,
+                vsize: field(23)?,
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parse_stat() {
+            let stat = "1234 (my (weird) exe) S 1 1234 1234 0 -1 4194560 1829 0 0 0 \
+                        250 50 0 0 20 0 4 0 8000 123456789 1500 18446744073709551615";
+            let stat = Stat::parse(stat).unwrap();
+
+            assert_eq!(250, stat.utime);
+            assert_eq!(50, stat.stime);
+            assert_eq!(4, stat.num_threads);
+            assert_eq!(8000, stat.start_time);
+            assert_eq!(123456789, stat.vsize);
+        }
+
+        #[test]
+        fn collect_self() {
+            let names: Vec<_> = collect("process")
+                .into_iter()
+                .map(|(desc, _)| desc.name().to_string())
+                .collect();
+
+            assert_eq!(
+                vec![
+                    "process_cpu",
+                    "process_resident_memory",
+                    "process_virtual_memory",
+                    "process_open_fds",
+                    "process_threads",
+                    "process_start_time",
+                ],
+                names
+            );
+        }
+    }
+}
@@ -1,3 +1,246 @@
 //! Exposition format implementations.
 
+pub mod snapshot;
 pub mod text;
+
+/// An exposition format supported by this crate.
+///
+/// Use [`content_type`] for the `Content-Type` header of a scrape response.
+/// Only the OpenMetrics text format is supported for now, thus there is no
+/// `Accept` header to negotiate yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// The OpenMetrics text format, see [`text::encode`].
+    OpenMetricsText,
+}
+
+/// The value of the `Content-Type` header for a response encoded in the given
+/// [`Format`].
+///
+/// ```
+/// # use prometheus_client::encoding::{content_type, Format};
+/// #
+/// assert_eq!(
+///     "application/openmetrics-text; version=1.0.0; charset=utf-8",
+///     content_type(Format::OpenMetricsText),
+/// );
+/// ```
+pub fn content_type(format: Format) -> &'static str {
+    match format {
+        Format::OpenMetricsText => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+    }
+}
+
+/// Error returned when encoding the metrics of a
+/// [`Registry`](crate::registry::Registry), e.g. by [`text::encode`].
+///
+/// Implementations of [`text::Encode`] and [`text::EncodeMetric`] return a
+/// [`std::io::Error`]. An [`EncodeError`] wrapped in a [`std::io::Error`] of
+/// kind [`std::io::ErrorKind::InvalidData`] is unwrapped again on conversion,
+/// any other [`std::io::Error`] becomes [`EncodeError::Io`]. Conversely an
+/// [`EncodeError`] converts into a [`std::io::Error`], allowing the use of `?`
+/// in functions returning the latter.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// The units or label names are not valid in the exposition format.
+    InvalidName(Vec<String>),
+    /// A metric of a [`Collector`](crate::collector::Collector) collides with
+    /// another metric of the same name.
+    DuplicateName(String),
+    /// The label names are reserved, see
+    /// [`RESERVED_LABEL_NAMES`](crate::registry::RESERVED_LABEL_NAMES).
+    ReservedLabel(Vec<String>),
+    /// Writing the output failed.
+    Io(std::io::Error),
+    /// Formatting a value failed.
+    Fmt(std::fmt::Error),
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::InvalidName(names) => {
+                write!(f, "Invalid units or label names {:?}.", names)
+            }
+            EncodeError::DuplicateName(name) => write!(
+                f,
+                "Collected metric {:?} collides with another metric of the same name.",
+                name
+            ),
+            EncodeError::ReservedLabel(names) => write!(f, "Reserved label names {:?}.", names),
+            EncodeError::Io(e) => write!(f, "Failed to write encoded metrics: {}", e),
+            EncodeError::Fmt(e) => write!(f, "Failed to format encoded metrics: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Io(e) => Some(e),
+            EncodeError::Fmt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for EncodeError {
+    fn from(e: std::io::Error) -> Self {
+        if !e.get_ref().is_some_and(|inner| inner.is::<EncodeError>()) {
+            return EncodeError::Io(e);
+        }
+
+        e.into_inner()
+            .and_then(|inner| inner.downcast::<EncodeError>().ok())
+            .map(|e| *e)
+            .expect("Inner error to be an EncodeError.")
+    }
+}
+
+impl From<std::fmt::Error> for EncodeError {
+    fn from(e: std::fmt::Error) -> Self {
+        EncodeError::Fmt(e)
+    }
+}
+
+impl From<EncodeError> for std::io::Error {
+    fn from(e: EncodeError) -> Self {
+        match e {
+            EncodeError::Io(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Encode the metrics registered with the provided [`Registry`] in the given
+/// [`Format`] into an owned, complete response body, including the `# EOF`
+/// terminator of the text format.
+///
+/// Use this to set the `Content-Length` header of a scrape response, or to
+/// cache the body between scrapes.
+///
+/// ```
+/// # use prometheus_client::encoding::{content_type, encode_to_vec, Format};
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = Registry::default();
+/// let counter: Counter = Counter::default();
+/// registry.register("my_counter", "This is my counter", counter);
+///
+/// let format = Format::OpenMetricsText;
+/// let body = encode_to_vec(&registry, format).unwrap();
+/// let headers = [
+///     ("Content-Type", content_type(format).to_string()),
+///     ("Content-Length", body.len().to_string()),
+/// ];
+/// assert!(body.ends_with(b"# EOF\n"));
+/// ```
+///
+/// [`Registry`]: crate::registry::Registry
+pub fn encode_to_vec<M: text::EncodeMetric>(
+    registry: &crate::registry::Registry<M>,
+    format: Format,
+) -> Result<Vec<u8>, EncodeError> {
+    let mut body = Vec::new();
+
+    match format {
+        Format::OpenMetricsText => text::encode(&mut body, registry)?,
+    }
+
+    Ok(body)
+}
+
+/// Encode the metrics registered with the provided [`Registry`] in the given
+/// [`Format`], compressing the output with gzip.
+///
+/// Use this to respond to scrape requests with an `Accept-Encoding: gzip`
+/// header, setting the `Content-Encoding` header of the response to `gzip`.
+///
+/// ```
+/// # use prometheus_client::encoding::{encode_gzip, Format};
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = Registry::default();
+/// let counter: Counter = Counter::default();
+/// registry.register("my_counter", "This is my counter", counter);
+///
+/// let compressed = encode_gzip(&registry, Format::OpenMetricsText).unwrap();
+/// ```
+///
+/// [`Registry`]: crate::registry::Registry
+#[cfg(feature = "gzip")]
+pub fn encode_gzip<M: text::EncodeMetric>(
+    registry: &crate::registry::Registry<M>,
+    format: Format,
+) -> Result<Vec<u8>, EncodeError> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+
+    match format {
+        Format::OpenMetricsText => text::encode(&mut encoder, registry)?,
+    }
+
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_error_io_round_trip() {
+        let error: std::io::Error = EncodeError::DuplicateName("my_counter".to_string()).into();
+        assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+        assert!(matches!(
+            EncodeError::from(error),
+            EncodeError::DuplicateName(name) if name == "my_counter"
+        ));
+
+        let error = std::io::Error::new(std::io::ErrorKind::InvalidData, "other");
+        assert!(matches!(EncodeError::from(error), EncodeError::Io(_)));
+    }
+
+    #[test]
+    fn encode_to_vec_matches_encode() {
+        use crate::metrics::counter::Counter;
+        use crate::registry::Registry;
+
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", counter.clone());
+        counter.inc();
+
+        let body = encode_to_vec(&registry, Format::OpenMetricsText).unwrap();
+
+        let mut expected = Vec::new();
+        text::encode(&mut expected, &registry).unwrap();
+        assert_eq!(expected, body);
+        assert!(body.ends_with(b"# EOF\n"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn encode_gzip_round_trip() {
+        use crate::metrics::counter::Counter;
+        use crate::registry::Registry;
+        use std::io::Read;
+
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", counter.clone());
+        counter.inc();
+
+        let compressed = encode_gzip(&registry, Format::OpenMetricsText).unwrap();
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+
+        let mut expected = Vec::new();
+        text::encode(&mut expected, &registry).unwrap();
+        assert_eq!(String::from_utf8(expected).unwrap(), decompressed);
+    }
+}
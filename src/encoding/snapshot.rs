@@ -0,0 +1,406 @@
+//! Owned snapshot of the metrics registered with a [`Registry`].
+//!
+//! See [`Registry::snapshot`](crate::registry::Registry::snapshot) for
+//! details.
+
+use super::text::{record, Encode, EncodeMetric};
+use crate::metrics::MetricType;
+use crate::registry::Registry;
+use std::borrow::Cow;
+use std::time::SystemTime;
+
+/// Owned, immutable copy of the current values of all metrics of a
+/// [`Registry`], e.g. to assert on values in tests without parsing the text
+/// format.
+///
+/// The [`Snapshot`] is recorded while traversing the metrics the same way
+/// [`encode`](super::text::encode) does and thus exposes exactly what a scrape
+/// would expose, with metric families sorted by name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    families: Vec<MetricFamily>,
+}
+
+impl Snapshot {
+    /// All metric families in the order they are encoded.
+    pub fn families(&self) -> &[MetricFamily] {
+        &self.families
+    }
+
+    /// The metric family with the given name, including the unit if any.
+    pub fn family(&self, name: &str) -> Option<&MetricFamily> {
+        self.families.iter().find(|family| family.name == name)
+    }
+}
+
+/// A single metric family of a [`Snapshot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricFamily {
+    name: String,
+    help: String,
+    metric_type: MetricType,
+    unit: Option<String>,
+    samples: Vec<Sample>,
+}
+
+impl MetricFamily {
+    /// The name of the metric family, including the unit if any.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+
+    pub fn metric_type(&self) -> MetricType {
+        self.metric_type
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref()
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// The value of the sample with the given name and label set, the latter
+    /// compared irrespective of the order of the labels.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<Value> {
+        self.samples
+            .iter()
+            .find(|sample| {
+                sample.name == name
+                    && sample.labels.len() == labels.len()
+                    && labels.iter().all(|(k, v)| {
+                        sample
+                            .labels
+                            .iter()
+                            .any(|(sample_k, sample_v)| sample_k == k && sample_v == v)
+                    })
+            })
+            .map(|sample| sample.value)
+    }
+}
+
+/// A single sample of a [`MetricFamily`], e.g. the `_count` of a histogram.
+///
+/// Exemplars are not part of a [`Sample`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: Value,
+    timestamp: Option<SystemTime>,
+}
+
+impl Sample {
+    /// The name of the sample, including suffixes like `_total`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The labels of the sample in the order they are encoded, including
+    /// constant labels and e.g. the `le` label of histogram buckets.
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.labels
+    }
+
+    pub fn value(&self) -> Value {
+        self.value
+    }
+
+    /// The timestamp of the sample, if any.
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+}
+
+/// The value of a [`Sample`].
+///
+/// Integer values, e.g. of a [`Counter`](crate::metrics::counter::Counter)
+/// with the default `u64`, are kept as integers and thus retain their full
+/// precision.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+}
+
+impl Value {
+    /// The value as an [`f64`], losing precision for integers beyond 2^53.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Value::Unsigned(v) => v as f64,
+            Value::Signed(v) => v as f64,
+            Value::Float(v) => v,
+        }
+    }
+}
+
+/// Records the samples of a single metric family while it is encoded, see
+/// [`record`].
+#[derive(Default)]
+pub(crate) struct Recorder {
+    samples: Vec<Sample>,
+    /// The name and labels of the sample whose value is yet to be recorded.
+    next: Option<(String, Vec<(String, String)>)>,
+}
+
+impl Recorder {
+    pub(crate) fn start_sample(&mut self, name: String) {
+        self.next = Some((name, Vec::new()));
+    }
+
+    /// Records the constant labels and the label set of the next sample.
+    ///
+    /// Label sets only implement [`Encode`], i.e. can only be written in the
+    /// text format. They are thus encoded and split into their labels.
+    pub(crate) fn push_labels(
+        &mut self,
+        const_labels: &[(Cow<'static, str>, Cow<'static, str>)],
+        label_set: Option<&dyn Encode>,
+    ) -> Result<(), std::io::Error> {
+        let labels = match self.next.as_mut() {
+            Some((_, labels)) => labels,
+            None => return Ok(()),
+        };
+        labels.extend(
+            const_labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string())),
+        );
+
+        let label_set = match label_set {
+            Some(label_set) => label_set,
+            None => return Ok(()),
+        };
+        let mut buffer = Vec::new();
+        label_set.encode(&mut buffer)?;
+        let encoded = String::from_utf8(buffer).map_err(invalid)?;
+        let mut rest = encoded.as_str();
+        while !rest.is_empty() {
+            let (key, value) = rest
+                .split_once("=\"")
+                .ok_or_else(|| invalid(format!("Failed to parse label set {:?}.", encoded)))?;
+            let mut escaped = false;
+            let value_end = value
+                .find(|c| {
+                    let end = !escaped && c == '"';
+                    escaped = !escaped && c == '\\';
+                    end
+                })
+                .ok_or_else(|| invalid(format!("Failed to parse label set {:?}.", encoded)))?;
+            labels.push((key.to_owned(), unescape(&value[..value_end])));
+            rest = &value[value_end + 1..];
+            rest = rest.strip_prefix(',').unwrap_or(rest);
+        }
+
+        Ok(())
+    }
+
+    /// Records an additional label of the next sample, e.g. the `le` label of
+    /// a histogram bucket.
+    pub(crate) fn push_label(
+        &mut self,
+        name: &str,
+        value: &dyn Encode,
+    ) -> Result<(), std::io::Error> {
+        if let Some((_, labels)) = self.next.as_mut() {
+            let mut buffer = Vec::new();
+            value.encode(&mut buffer)?;
+            let value = String::from_utf8(buffer).map_err(invalid)?;
+            labels.push((name.to_owned(), unescape(&value)));
+        }
+        Ok(())
+    }
+
+    /// Records the value of the next sample, completing the sample.
+    ///
+    /// Like label sets, values only implement [`Encode`]. Integers are parsed
+    /// as such, everything else as an [`f64`].
+    pub(crate) fn push_value(&mut self, value: &dyn Encode) -> Result<(), std::io::Error> {
+        let (name, labels) = match self.next.take() {
+            Some(next) => next,
+            None => return Ok(()),
+        };
+        let mut buffer = Vec::new();
+        value.encode(&mut buffer)?;
+        let encoded = String::from_utf8(buffer).map_err(invalid)?;
+        let value = if let Ok(v) = encoded.parse() {
+            Value::Unsigned(v)
+        } else if let Ok(v) = encoded.parse() {
+            Value::Signed(v)
+        } else {
+            encoded
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| invalid(format!("Failed to parse value {:?}.", encoded)))?
+        };
+
+        self.samples.push(Sample {
+            name,
+            labels,
+            value,
+            timestamp: None,
+        });
+        Ok(())
+    }
+
+    /// Records the timestamp of the last sample.
+    pub(crate) fn set_timestamp(&mut self, timestamp: SystemTime) {
+        if let Some(sample) = self.samples.last_mut() {
+            sample.timestamp = Some(timestamp);
+        }
+    }
+}
+
+pub(crate) fn snapshot<M: EncodeMetric>(
+    registry: &Registry<M>,
+) -> Result<Snapshot, std::io::Error> {
+    let mut families = Vec::new();
+    record(registry, |name, desc, metric_type, recorder| {
+        families.push(MetricFamily {
+            name: name.to_owned(),
+            help: desc.help().to_owned(),
+            metric_type,
+            unit: desc.unit().as_ref().map(|unit| unit.as_str().to_owned()),
+            samples: recorder.samples,
+        })
+    })?;
+
+    Ok(Snapshot { families })
+}
+
+fn invalid<E>(e: E) -> std::io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+/// Reverts the escaping of backslashes, newlines and double quotes.
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::counter::Counter;
+    use crate::metrics::family::Family;
+    use crate::metrics::gauge::{Gauge, GaugeWithTimestamp};
+    use crate::metrics::histogram::Histogram;
+    use crate::registry::Unit;
+
+    #[test]
+    fn snapshot_matches_encoding() {
+        let mut registry = <Registry>::default();
+
+        let requests = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register("requests", "Number of requests", Box::new(requests.clone()));
+        requests
+            .get_or_create(&vec![("path".to_owned(), "/a \"b\"\n".to_owned())])
+            .inc_by(3);
+
+        let latency = Histogram::new(vec![1.0].into_iter());
+        registry.register_with_unit(
+            "latency",
+            "Request latency",
+            Unit::Seconds,
+            Box::new(latency.clone()),
+        );
+        latency.observe(0.5);
+
+        let gauge: Gauge = Gauge::default();
+        registry
+            .sub_registry_with_label(("shard".into(), "1".into()))
+            .register("queue", "Queue length\nin items", Box::new(gauge.clone()));
+        gauge.set(7);
+
+        let snapshot = registry.snapshot().unwrap();
+        assert_eq!(
+            vec!["latency_seconds", "queue", "requests"],
+            snapshot
+                .families()
+                .iter()
+                .map(|f| f.name())
+                .collect::<Vec<_>>()
+        );
+
+        let latency = snapshot.family("latency_seconds").unwrap();
+        assert_eq!(MetricType::Histogram, latency.metric_type());
+        assert_eq!(Some("seconds"), latency.unit());
+        assert_eq!(
+            Some(Value::Unsigned(1)),
+            latency.value("latency_seconds_count", &[])
+        );
+        assert_eq!(
+            Some(Value::Unsigned(1)),
+            latency.value("latency_seconds_bucket", &[("le", "+Inf")])
+        );
+        assert_eq!(
+            Some(Value::Float(0.5)),
+            latency.value("latency_seconds_sum", &[])
+        );
+
+        let queue = snapshot.family("queue").unwrap();
+        assert_eq!("Queue length\nin items.", queue.help());
+        assert_eq!(
+            Some(Value::Unsigned(7)),
+            queue.value("queue", &[("shard", "1")])
+        );
+
+        let requests = snapshot.family("requests").unwrap();
+        assert_eq!(MetricType::Counter, requests.metric_type());
+        assert_eq!(
+            Some(Value::Unsigned(3)),
+            requests.value("requests_total", &[("path", "/a \"b\"\n")])
+        );
+    }
+
+    #[test]
+    fn snapshot_keeps_integer_precision() {
+        let mut registry = <Registry>::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", Box::new(counter.clone()));
+        counter.inc_by(u64::MAX - 1);
+
+        let snapshot = registry.snapshot().unwrap();
+        assert_eq!(
+            Some(Value::Unsigned(u64::MAX - 1)),
+            snapshot
+                .family("my_counter")
+                .unwrap()
+                .value("my_counter_total", &[])
+        );
+    }
+
+    #[test]
+    fn snapshot_with_timestamp() {
+        let mut registry = <Registry>::default();
+        let gauge: GaugeWithTimestamp = GaugeWithTimestamp::default();
+        registry.register("my_gauge", "My gauge", Box::new(gauge.clone()));
+        let timestamp = std::time::UNIX_EPOCH + std::time::Duration::from_secs(42);
+        gauge.set_with_timestamp(3, timestamp);
+
+        let snapshot = registry.snapshot().unwrap();
+        let sample = &snapshot.family("my_gauge").unwrap().samples()[0];
+        assert_eq!(Value::Unsigned(3), sample.value());
+        assert_eq!(Some(timestamp), sample.timestamp());
+    }
+}
@@ -24,70 +24,509 @@
 //! assert_eq!(expected, String::from_utf8(buffer).unwrap());
 //! ```
 
-use crate::metrics::counter::{self, Counter};
+use super::snapshot::Recorder;
+use super::EncodeError;
+use crate::metrics::counter::{self, Counter, CounterWithCreated};
+use crate::metrics::counter_vec::CounterVec;
 use crate::metrics::exemplar::{CounterWithExemplar, Exemplar, HistogramWithExemplars};
-use crate::metrics::family::{Family, MetricConstructor};
-use crate::metrics::gauge::{self, Gauge};
+use crate::metrics::family::{ConstrainedLabel, Family, MetricConstructor};
+use crate::metrics::gauge::{self, Gauge, GaugeFn, GaugeWithTimestamp};
+use crate::metrics::gauge_histogram::GaugeHistogram;
 use crate::metrics::histogram::Histogram;
 use crate::metrics::info::Info;
+use crate::metrics::native_histogram::NativeHistogram;
+use crate::metrics::state_set::{self, StateSet};
+use crate::metrics::summary::Summary;
+use crate::metrics::t_digest_summary::TDigestSummary;
+use crate::metrics::unknown::Unknown;
 use crate::metrics::{MetricType, TypedMetric};
-use crate::registry::{Registry, Unit};
+use crate::registry::{with_unit, Descriptor, Registry, Unit, RESERVED_LABEL_NAMES};
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::ops::Deref;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 
 pub use prometheus_client_derive_text_encode::*;
 
-pub fn encode<W, M>(writer: &mut W, registry: &Registry<M>) -> Result<(), std::io::Error>
+/// Encode the metrics registered with the provided [`Registry`] into the
+/// provided [`Write`]r using the OpenMetrics text format.
+///
+/// Each metric is preceded by its `# HELP`, `# TYPE` and, if set, `# UNIT`
+/// line. Label sets of a [`Family`] are expanded inline, e.g.
+/// `my_metric_total{method="GET"} 3`. The output is terminated with `# EOF`.
+///
+/// Each metric type renders itself through its [`EncodeMetric`]
+/// implementation. Metrics of any [`Collector`](crate::collector::Collector)
+/// registered with the [`Registry`] are collected and encoded alongside the
+/// metrics registered directly.
+///
+/// To produce deterministic output, metrics are sorted by the name of their
+/// metric family as written, e.g. `my_counter` for a counter registered as
+/// `my_counter_total`, and the members of a [`Family`] by their encoded
+/// label set.
+///
+/// Returns an [`EncodeError::InvalidName`] if a [`Unit::Other`] or a label
+/// name is not valid in the OpenMetrics text format, or an
+/// [`EncodeError::ReservedLabel`] if a label name is reserved, see
+/// [`RESERVED_LABEL_NAMES`], instead of producing output a scraper can not
+/// parse. Invalid units
+/// and constant label names, the latter only possible in release builds, see
+/// [`Registry::register`], are detected before anything is written, listing
+/// all offenders. Label names of [`Family`] label sets are validated as they
+/// are written.
+///
+/// With the `tracing` feature enabled, each call emits a trace event with the
+/// number of metric families and samples encoded.
+///
+/// See [`encode_with_metadata`] to omit the `# HELP`, `# UNIT` and `# TYPE`
+/// lines.
+pub fn encode<W, M>(writer: &mut W, registry: &Registry<M>) -> Result<(), EncodeError>
 where
     W: Write,
     M: EncodeMetric,
 {
-    for (desc, metric) in registry.iter() {
-        writer.write_all(b"# HELP ")?;
-        writer.write_all(desc.name().as_bytes())?;
-        if let Some(unit) = desc.unit() {
-            writer.write_all(b"_")?;
-            unit.encode(writer)?;
+    encode_with_metadata(writer, registry, Metadata::Full)
+}
+
+/// The metadata lines preceding the samples of each metric, see
+/// [`encode_with_metadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Metadata {
+    /// The `# HELP`, `# TYPE` and, if set, `# UNIT` lines, as written by
+    /// [`encode`].
+    Full,
+    /// The `# TYPE` lines only.
+    TypeOnly,
+    /// No metadata lines at all.
+    Omit,
+}
+
+/// Like [`encode`], writing only the given [`Metadata`] lines, e.g. to reduce
+/// the size of the output of huge registries.
+///
+/// The metadata lines are optional in the OpenMetrics text format, yet
+/// without a `# TYPE` line a parser treats a metric as `unknown`, e.g. turning
+/// the `_total`, `_bucket` and `_count` samples of counters and histograms
+/// into independent metrics, and without a `# UNIT` line the unit is lost.
+/// [`Metadata::TypeOnly`] thus retains the type of each metric while
+/// dropping the descriptive help texts and units. [`Metadata::Omit`] is meant
+/// for consumers which don't rely on metadata, e.g. parsers of the legacy
+/// Prometheus text format treating all samples as untyped.
+///
+/// ```
+/// # use prometheus_client::encoding::text::{encode_with_metadata, Metadata};
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = Registry::default();
+/// let counter: Counter = Counter::default();
+/// registry.register("my_counter", "This is my counter", counter.clone());
+/// counter.inc();
+///
+/// let mut encoded = Vec::new();
+/// encode_with_metadata(&mut encoded, &registry, Metadata::TypeOnly).unwrap();
+/// assert_eq!(
+///     "# TYPE my_counter counter\nmy_counter_total 1\n# EOF\n",
+///     String::from_utf8(encoded).unwrap(),
+/// );
+/// ```
+pub fn encode_with_metadata<W, M>(
+    writer: &mut W,
+    registry: &Registry<M>,
+    metadata: Metadata,
+) -> Result<(), EncodeError>
+where
+    W: Write,
+    M: EncodeMetric,
+{
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("encode").entered();
+    #[cfg(feature = "tracing")]
+    let writer = &mut SampleCounter {
+        writer,
+        samples: 0,
+        line_start: true,
+    };
+
+    let start = registry
+        .self_metrics()
+        .map(|self_metrics| self_metrics.start());
+    let families = Families::new(registry)?;
+
+    #[cfg(feature = "tracing")]
+    let families_len = families.order.len();
+
+    for source in families.order.iter() {
+        let (desc, metric) = families.get(*source);
+        encode_metric(writer, desc, metric, metadata)?;
+    }
+
+    writer.write_all(b"# EOF\n")?;
+
+    if let (Some(self_metrics), Some(start)) = (registry.self_metrics(), start) {
+        self_metrics.record(start);
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::trace!(
+        families = families_len,
+        samples = writer.samples,
+        "Encoded registry."
+    );
+
+    Ok(())
+}
+
+/// Iterator over the encoded metric families of a [`Registry`], see
+/// [`Registry::encode_families`].
+///
+/// Yields one chunk per metric family, followed by a final chunk holding the
+/// `# EOF` marker. Concatenating all chunks yields the output of [`encode`].
+/// An error, e.g. a collision of collected metrics, is yielded in place of
+/// the next chunk and ends the iteration.
+pub struct EncodeFamilies<'a, M> {
+    families: Option<Result<Families<'a, M>, EncodeError>>,
+    next: usize,
+}
+
+impl<'a, M: EncodeMetric> EncodeFamilies<'a, M> {
+    pub(crate) fn new(registry: &'a Registry<M>) -> Self {
+        Self {
+            families: Some(Families::new(registry)),
+            next: 0,
         }
-        writer.write_all(b" ")?;
-        writer.write_all(desc.help().as_bytes())?;
-        writer.write_all(b"\n")?;
+    }
+}
 
-        writer.write_all(b"# TYPE ")?;
-        writer.write_all(desc.name().as_bytes())?;
-        if let Some(unit) = desc.unit() {
-            writer.write_all(b"_")?;
-            unit.encode(writer)?;
+impl<'a, M: EncodeMetric> Iterator for EncodeFamilies<'a, M> {
+    type Item = Result<Vec<u8>, EncodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let families = match self.families.as_ref()? {
+            Ok(families) => families,
+            Err(_) => {
+                return self
+                    .families
+                    .take()
+                    .and_then(|families| families.err())
+                    .map(Err);
+            }
+        };
+
+        let mut chunk = Vec::new();
+        match families.order.get(self.next) {
+            Some(source) => {
+                self.next += 1;
+                let (desc, metric) = families.get(*source);
+                if let Err(e) = encode_metric(&mut chunk, desc, metric, Metadata::Full) {
+                    self.families = None;
+                    return Some(Err(e.into()));
+                }
+            }
+            None => {
+                self.families = None;
+                chunk.extend_from_slice(b"# EOF\n");
+            }
         }
-        writer.write_all(b" ")?;
-        metric.metric_type().encode(writer)?;
-        writer.write_all(b"\n")?;
 
-        if let Some(unit) = desc.unit() {
-            writer.write_all(b"# UNIT ")?;
-            writer.write_all(desc.name().as_bytes())?;
-            writer.write_all(b"_")?;
-            unit.encode(writer)?;
-            writer.write_all(b" ")?;
-            unit.encode(writer)?;
-            writer.write_all(b"\n")?;
+        Some(Ok(chunk))
+    }
+}
+
+/// The metrics of a [`Registry`] and its collectors, validated and sorted for
+/// encoding.
+struct Families<'a, M> {
+    registered: Vec<(&'a Descriptor, &'a M)>,
+    collected: Vec<(Descriptor, Box<dyn EncodeMetric>)>,
+    order: Vec<Source>,
+}
+
+/// Position of a metric in [`Families`].
+#[derive(Clone, Copy)]
+enum Source {
+    Registered(usize),
+    Collected(usize),
+}
+
+impl<'a, M: EncodeMetric> Families<'a, M> {
+    fn new(registry: &'a Registry<M>) -> Result<Self, EncodeError> {
+        let registered: Vec<(&Descriptor, &M)> = registry
+            .iter()
+            .map(|(desc, metric)| (desc, metric))
+            .collect();
+        let collected = registry.collect();
+
+        // Make sure no two metrics, registered or collected, expose the same
+        // metric family, e.g. a counter `my_counter` and a counter
+        // `my_counter_total`.
+        let mut names: HashSet<String> = HashSet::new();
+        for desc in registered
+            .iter()
+            .map(|(desc, _)| *desc)
+            .chain(collected.iter().map(|(desc, _)| desc))
+        {
+            if !names.insert(desc.family_name()) {
+                return Err(EncodeError::DuplicateName(desc.full_name()));
+            }
         }
 
-        let encoder = Encoder {
+        let mut families = Self {
+            registered,
+            collected,
+            order: Vec::new(),
+        };
+        let mut order: Vec<(String, Source)> = (0..families.registered.len())
+            .map(Source::Registered)
+            .chain((0..families.collected.len()).map(Source::Collected))
+            .filter(|source| !families.get(*source).1.is_empty())
+            .map(|source| {
+                let (desc, metric) = families.get(source);
+                (family_name(desc, metric).into_owned(), source)
+            })
+            .collect();
+        // Sort by the name as written, i.e. without a `_total` or `_info`
+        // suffix and including the unit. Stable sort, keeping metrics with
+        // the same name in registration order.
+        order.sort_by(|(a, _), (b, _)| a.cmp(b));
+        families.order = order.into_iter().map(|(_, source)| source).collect();
+
+        let mut invalid: Vec<String> = Vec::new();
+        let mut reserved: Vec<String> = Vec::new();
+        for source in families.order.iter() {
+            let desc = families.get(*source).0;
+            if let Some(unit) = desc.unit().as_ref().map(Unit::as_str) {
+                if !is_valid_unit(unit) {
+                    invalid.push(unit.to_owned());
+                }
+            }
+            for (name, _) in desc.labels() {
+                if !is_valid_label_name(name) {
+                    invalid.push(name.to_string());
+                } else if RESERVED_LABEL_NAMES.contains(&name.as_ref()) {
+                    reserved.push(name.to_string());
+                }
+            }
+        }
+        if !invalid.is_empty() {
+            return Err(EncodeError::InvalidName(invalid));
+        }
+        if !reserved.is_empty() {
+            return Err(EncodeError::ReservedLabel(reserved));
+        }
+
+        Ok(families)
+    }
+
+    fn get(&self, source: Source) -> (&Descriptor, &dyn EncodeMetric) {
+        match source {
+            Source::Registered(i) => {
+                let (desc, metric) = self.registered[i];
+                (desc, metric)
+            }
+            Source::Collected(i) => {
+                let (desc, metric) = &self.collected[i];
+                (desc, metric)
+            }
+        }
+    }
+}
+
+/// Whether the given unit only consists of the characters allowed in metric
+/// names, i.e. `[a-zA-Z0-9_:]`.
+fn is_valid_unit(unit: &str) -> bool {
+    !unit.is_empty()
+        && unit
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b':')
+}
+
+/// Whether the given label name matches `[a-zA-Z_][a-zA-Z0-9_]*` and is not
+/// one of the [`RESERVED_LABEL_NAMES`].
+fn is_valid_label_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .enumerate()
+            .all(|(i, b)| is_label_name_byte(b, i == 0))
+}
+
+fn is_label_name_byte(byte: u8, first: bool) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || (!first && byte.is_ascii_digit())
+}
+
+/// Length of the longest of the [`RESERVED_LABEL_NAMES`].
+const MAX_RESERVED_LABEL_NAME_LEN: usize = 8;
+
+/// [`Write`]r validating that the bytes written to the wrapped [`Write`]r
+/// form a valid label name, only retaining enough of them to detect the
+/// [`RESERVED_LABEL_NAMES`].
+struct LabelNameValidator<'a> {
+    writer: &'a mut dyn Write,
+    len: usize,
+    valid: bool,
+    prefix: [u8; MAX_RESERVED_LABEL_NAME_LEN],
+}
+
+impl<'a> LabelNameValidator<'a> {
+    fn new(writer: &'a mut dyn Write) -> Self {
+        Self {
             writer,
-            name: desc.name(),
-            unit: desc.unit(),
+            len: 0,
+            valid: true,
+            prefix: [0; MAX_RESERVED_LABEL_NAME_LEN],
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.valid && self.len > 0
+    }
+
+    fn is_reserved(&self) -> bool {
+        self.len <= MAX_RESERVED_LABEL_NAME_LEN
+            && RESERVED_LABEL_NAMES
+                .iter()
+                .any(|name| name.as_bytes() == &self.prefix[..self.len])
+    }
+}
+
+impl<'a> Write for LabelNameValidator<'a> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        for (i, byte) in buf.iter().enumerate() {
+            self.valid &= is_label_name_byte(*byte, self.len + i == 0);
+            if let Some(b) = self.prefix.get_mut(self.len + i) {
+                *b = *byte;
+            }
+        }
+        self.len += buf.len();
+        self.writer.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+}
+
+/// [`Write`]r counting the samples, i.e. the lines not starting with `#`,
+/// written to the wrapped [`Write`]r.
+#[cfg(feature = "tracing")]
+struct SampleCounter<W> {
+    writer: W,
+    samples: usize,
+    line_start: bool,
+}
+
+#[cfg(feature = "tracing")]
+impl<W: Write> Write for SampleCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        let written = self.writer.write(buf)?;
+        for byte in &buf[..written] {
+            if self.line_start && *byte != b'#' {
+                self.samples += 1;
+            }
+            self.line_start = *byte == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+}
+
+/// The name of the metric family the given metric is exposed as.
+fn family_name<'a, M: EncodeMetric + ?Sized>(desc: &'a Descriptor, metric: &M) -> Cow<'a, str> {
+    // The `_total` and `_info` suffixes are part of the sample names, not the
+    // metric family name, and are appended by the [`Counter`] and [`Info`]
+    // encodings. Strip them in case the metric was registered including the
+    // suffix, avoiding e.g. `my_counter_total_total`.
+    let name = match metric.metric_type() {
+        MetricType::Counter => desc.name().strip_suffix("_total").unwrap_or(desc.name()),
+        MetricType::Info => desc.name().strip_suffix("_info").unwrap_or(desc.name()),
+        _ => desc.name(),
+    };
+    // Likewise the unit is only appended if the name does not already end
+    // with it.
+    with_unit(name, desc.unit().as_ref())
+}
+
+/// Traverses the metric families of the given [`Registry`] in the order
+/// [`encode`] writes them, passing the family name, the [`Descriptor`] and the
+/// samples of each to the given closure. Used by [`Registry::snapshot`].
+pub(crate) fn record<M, F>(registry: &Registry<M>, mut f: F) -> Result<(), EncodeError>
+where
+    M: EncodeMetric,
+    F: FnMut(&str, &Descriptor, MetricType, Recorder),
+{
+    let families = Families::new(registry)?;
+
+    for source in families.order.iter() {
+        let (desc, metric) = families.get(*source);
+        let name = family_name(desc, metric);
+        let mut recorder = Recorder::default();
+        let encoder = Encoder {
+            writer: &mut std::io::sink(),
+            name: &name,
             const_labels: desc.labels(),
             labels: None,
+            recorder: Some(&mut recorder),
         };
-
         metric.encode(encoder)?;
+        f(&name, desc, metric.metric_type(), recorder);
     }
 
-    writer.write_all(b"# EOF\n")?;
+    Ok(())
+}
+
+fn encode_metric<W, M>(
+    writer: &mut W,
+    desc: &Descriptor,
+    metric: &M,
+    metadata: Metadata,
+) -> Result<(), std::io::Error>
+where
+    W: Write,
+    M: EncodeMetric + ?Sized,
+{
+    let name = family_name(desc, metric);
+    let name = name.as_ref();
+
+    if metadata == Metadata::Full {
+        writer.write_all(b"# HELP ")?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(b" ")?;
+        escape_help(desc.help(), writer)?;
+        writer.write_all(b"\n")?;
+    }
+
+    if metadata != Metadata::Omit {
+        writer.write_all(b"# TYPE ")?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(b" ")?;
+        metric.metric_type().encode(writer)?;
+        writer.write_all(b"\n")?;
+    }
+
+    if let (Metadata::Full, Some(unit)) = (metadata, desc.unit()) {
+        writer.write_all(b"# UNIT ")?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(b" ")?;
+        unit.encode(writer)?;
+        writer.write_all(b"\n")?;
+    }
+
+    let encoder = Encoder {
+        writer,
+        name,
+        const_labels: desc.labels(),
+        labels: None,
+        recorder: None,
+    };
+
+    metric.encode(encoder)?;
 
     Ok(())
 }
@@ -110,6 +549,13 @@ impl Encode for u64 {
     }
 }
 
+impl Encode for i64 {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        writer.write_all(itoa::Buffer::new().format(*self).as_bytes())?;
+        Ok(())
+    }
+}
+
 impl Encode for u32 {
     fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
         writer.write_all(itoa::Buffer::new().format(*self).as_bytes())?;
@@ -142,26 +588,212 @@ impl<T: Encode> Encode for Vec<T> {
     }
 }
 
-impl<K: Encode, V: Encode> Encode for (K, V) {
+/// Allows fixed label sets, e.g. `[("method", "GET"), ("status", "200")]`,
+/// without allocating a [`Vec`]. Labels are encoded in declaration order.
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.as_slice().encode(writer)
+    }
+}
+
+impl<K: Encode, V: EncodeLabelValue> Encode for (K, V) {
     fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
         let (key, value) = self;
 
-        key.encode(writer)?;
+        let mut validator = LabelNameValidator::new(writer);
+        key.encode(&mut validator)?;
+        if !validator.is_valid() || validator.is_reserved() {
+            // Only allocate in the error case, to name the offender.
+            let mut name = Vec::new();
+            key.encode(&mut name)?;
+            let name = vec![String::from_utf8_lossy(&name).into_owned()];
+            let error = if validator.is_valid() {
+                EncodeError::ReservedLabel(name)
+            } else {
+                EncodeError::InvalidName(name)
+            };
+            return Err(error.into());
+        }
         writer.write_all(b"=\"")?;
 
-        value.encode(writer)?;
+        value.encode_label_value(writer)?;
         writer.write_all(b"\"")?;
 
         Ok(())
     }
 }
 
+/// A type that can be used as the value of a label, e.g. the `V` of a `(K, V)`
+/// label pair.
+///
+/// Implement or derive [`EncodeLabelValue`] for an enum to restrict the values
+/// of a label to a fixed set, catching typos at compile time. The derive
+/// encodes each variant as its name. To use such an enum as the label set of a
+/// [`Family`] with a single label, derive [`Encode`] with
+/// `#[prometheus(name = "method")]` instead.
+///
+/// ```
+/// # use prometheus_client::encoding::text::{encode, EncodeLabelValue};
+/// # use prometheus_client::metrics::counter::{Atomic, Counter};
+/// # use prometheus_client::metrics::family::Family;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// #[derive(Clone, Hash, PartialEq, Eq, EncodeLabelValue)]
+/// enum Method {
+///   Get,
+///   Put,
+/// }
+///
+/// let mut registry = Registry::default();
+/// let family = Family::<Vec<(&'static str, Method)>, Counter>::default();
+/// registry.register("requests", "Number of requests", family.clone());
+///
+/// family.get_or_create(&vec![("method", Method::Get)]).inc();
+/// #
+/// # let mut buffer = vec![];
+/// # encode(&mut buffer, &registry).unwrap();
+/// #
+/// # let expected = "# HELP requests Number of requests.\n".to_owned() +
+/// #                "# TYPE requests counter\n" +
+/// #                "requests_total{method=\"Get\"} 1\n" +
+/// #                "# EOF\n";
+/// # assert_eq!(expected, String::from_utf8(buffer).unwrap());
+/// ```
+pub trait EncodeLabelValue {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error>;
+}
+
+impl EncodeLabelValue for str {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        escape_label_value(self, writer)
+    }
+}
+
+impl<T: EncodeLabelValue + ?Sized> EncodeLabelValue for &T {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        (**self).encode_label_value(writer)
+    }
+}
+
+impl EncodeLabelValue for String {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        escape_label_value(self, writer)
+    }
+}
+
+impl<'a> EncodeLabelValue for Cow<'a, str> {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        escape_label_value(self, writer)
+    }
+}
+
+impl EncodeLabelValue for Arc<str> {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        escape_label_value(self, writer)
+    }
+}
+
+impl EncodeLabelValue for ConstrainedLabel {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        escape_label_value(self.as_str(), writer)
+    }
+}
+
+impl Encode for ConstrainedLabel {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.encode_label_value(writer)
+    }
+}
+
+impl EncodeLabelValue for f64 {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.encode(writer)
+    }
+}
+
+impl EncodeLabelValue for u64 {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.encode(writer)
+    }
+}
+
+impl EncodeLabelValue for i64 {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.encode(writer)
+    }
+}
+
+impl EncodeLabelValue for u32 {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        self.encode(writer)
+    }
+}
+
+macro_rules! impl_encode_label_value_for_integer {
+    ($($t:ty),*) => {
+        $(
+            impl EncodeLabelValue for $t {
+                fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+                    writer.write_all(itoa::Buffer::new().format(*self).as_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_label_value_for_integer!(u8, u16, usize, i8, i16, i32, isize);
+
+impl EncodeLabelValue for f32 {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        writer.write_all(dtoa::Buffer::new().format(*self).as_bytes())
+    }
+}
+
+impl EncodeLabelValue for bool {
+    fn encode_label_value(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        writer.write_all(if *self { b"true" } else { b"false" })
+    }
+}
+
 impl Encode for &str {
     fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
-        // TODO: Can we do better?
-        writer.write_all(self.as_bytes())?;
-        Ok(())
+        escape_label_value(self, writer)
+    }
+}
+
+/// Write the given label value escaping backslashes, newlines and double
+/// quotes as mandated by the OpenMetrics specification.
+fn escape_label_value(value: &str, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+    escape(value, writer, |c| matches!(c, b'\\' | b'\n' | b'"'))
+}
+
+/// Write the given help text escaping backslashes and newlines as mandated by
+/// the OpenMetrics specification.
+fn escape_help(help: &str, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+    escape(help, writer, |c| matches!(c, b'\\' | b'\n'))
+}
+
+fn escape(
+    s: &str,
+    writer: &mut dyn Write,
+    needs_escaping: impl Fn(u8) -> bool,
+) -> Result<(), std::io::Error> {
+    let mut unescaped_start = 0;
+    for (i, c) in s.bytes().enumerate() {
+        if !needs_escaping(c) {
+            continue;
+        }
+
+        writer.write_all(&s.as_bytes()[unescaped_start..i])?;
+        match c {
+            b'\n' => writer.write_all(b"\\n")?,
+            c => writer.write_all(&[b'\\', c])?,
+        }
+        unescaped_start = i + 1;
     }
+    writer.write_all(&s.as_bytes()[unescaped_start..])?;
+
+    Ok(())
 }
 
 impl Encode for String {
@@ -176,37 +808,22 @@ impl<'a> Encode for Cow<'a, str> {
     }
 }
 
-impl Encode for MetricType {
+impl Encode for Arc<str> {
     fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
-        let t = match self {
-            MetricType::Counter => "counter",
-            MetricType::Gauge => "gauge",
-            MetricType::Histogram => "histogram",
-            MetricType::Info => "info",
-            MetricType::Unknown => "unknown",
-        };
+        self.as_ref().encode(writer)
+    }
+}
 
-        writer.write_all(t.as_bytes())?;
+impl Encode for MetricType {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        writer.write_all(self.as_str().as_bytes())?;
         Ok(())
     }
 }
 
 impl Encode for Unit {
     fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
-        let u = match self {
-            Unit::Amperes => "amperes",
-            Unit::Bytes => "bytes",
-            Unit::Celsius => "celsius",
-            Unit::Grams => "grams",
-            Unit::Joules => "joules",
-            Unit::Meters => "meters",
-            Unit::Ratios => "ratios",
-            Unit::Seconds => "seconds",
-            Unit::Volts => "volts",
-            Unit::Other(other) => other.as_str(),
-        };
-
-        writer.write_all(u.as_bytes())?;
+        writer.write_all(self.as_str().as_bytes())?;
         Ok(())
     }
 }
@@ -226,40 +843,40 @@ impl Encode for () {
 pub struct Encoder<'a, 'b> {
     writer: &'a mut dyn Write,
     name: &'a str,
-    unit: &'a Option<Unit>,
     const_labels: &'a [(Cow<'static, str>, Cow<'static, str>)],
     labels: Option<&'b dyn Encode>,
+    /// Records the samples alongside writing them, see [`record`].
+    recorder: Option<&'a mut Recorder>,
 }
 
 impl<'a, 'b> Encoder<'a, 'b> {
-    pub fn encode_suffix(&mut self, suffix: &'static str) -> Result<BucketEncoder, std::io::Error> {
-        self.write_name_and_unit()?;
+    pub fn encode_suffix(
+        &mut self,
+        suffix: &'static str,
+    ) -> Result<BucketEncoder<'_>, std::io::Error> {
+        self.writer.write_all(self.name.as_bytes())?;
 
         self.writer.write_all(b"_")?;
         self.writer.write_all(suffix.as_bytes()).map(|_| ())?;
 
-        self.encode_labels()
-    }
-
-    pub fn no_suffix(&mut self) -> Result<BucketEncoder, std::io::Error> {
-        self.write_name_and_unit()?;
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.start_sample(format!("{}_{}", self.name, suffix));
+        }
 
         self.encode_labels()
     }
 
-    fn write_name_and_unit(&mut self) -> Result<(), std::io::Error> {
+    pub fn no_suffix(&mut self) -> Result<BucketEncoder<'_>, std::io::Error> {
         self.writer.write_all(self.name.as_bytes())?;
-        if let Some(unit) = self.unit {
-            self.writer.write_all(b"_")?;
-            unit.encode(self.writer)?;
+
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.start_sample(self.name.to_owned());
         }
 
-        Ok(())
+        self.encode_labels()
     }
 
-    // TODO: Consider caching the encoded labels for Histograms as they stay the
-    // same but are currently encoded multiple times.
-    fn encode_labels(&mut self) -> Result<BucketEncoder, std::io::Error> {
+    fn encode_labels(&mut self) -> Result<BucketEncoder<'_>, std::io::Error> {
         let mut opened_curly_brackets = false;
 
         if !self.const_labels.is_empty() {
@@ -279,21 +896,36 @@ impl<'a, 'b> Encoder<'a, 'b> {
             labels.encode(self.writer)?;
         }
 
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.push_labels(self.const_labels, self.labels)?;
+        }
+
         Ok(BucketEncoder {
             opened_curly_brackets,
             writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
         })
     }
 
+    fn reborrow(&mut self) -> Encoder<'_, 'b> {
+        Encoder {
+            writer: self.writer,
+            name: self.name,
+            const_labels: self.const_labels,
+            labels: self.labels,
+            recorder: self.recorder.as_deref_mut(),
+        }
+    }
+
     pub fn with_label_set<'c, 'd>(&'c mut self, label_set: &'d dyn Encode) -> Encoder<'c, 'd> {
         debug_assert!(self.labels.is_none());
 
         Encoder {
             writer: self.writer,
             name: self.name,
-            unit: self.unit,
             const_labels: self.const_labels,
             labels: Some(label_set),
+            recorder: self.recorder.as_deref_mut(),
         }
     }
 }
@@ -302,10 +934,11 @@ impl<'a, 'b> Encoder<'a, 'b> {
 pub struct BucketEncoder<'a> {
     writer: &'a mut dyn Write,
     opened_curly_brackets: bool,
+    recorder: Option<&'a mut Recorder>,
 }
 
 impl<'a> BucketEncoder<'a> {
-    fn encode_bucket(&mut self, upper_bound: f64) -> Result<ValueEncoder, std::io::Error> {
+    fn encode_bucket(&mut self, upper_bound: f64) -> Result<ValueEncoder<'_>, std::io::Error> {
         if self.opened_curly_brackets {
             self.writer.write_all(b",")?;
         } else {
@@ -313,65 +946,229 @@ impl<'a> BucketEncoder<'a> {
         }
 
         self.writer.write_all(b"le=\"")?;
-        if upper_bound == f64::MAX {
+        if upper_bound == f64::INFINITY {
             self.writer.write_all(b"+Inf")?;
         } else {
             upper_bound.encode(self.writer)?;
         }
         self.writer.write_all(b"\"}")?;
 
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            if upper_bound == f64::INFINITY {
+                recorder.push_label("le", &"+Inf")?;
+            } else {
+                recorder.push_label("le", &upper_bound)?;
+            }
+        }
+
         Ok(ValueEncoder {
             writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
         })
     }
 
-    fn no_bucket(&mut self) -> Result<ValueEncoder, std::io::Error> {
+    /// Encodes the `quantile` label of a [`Summary`] sample.
+    fn encode_quantile(&mut self, quantile: f64) -> Result<ValueEncoder<'_>, std::io::Error> {
         if self.opened_curly_brackets {
-            self.writer.write_all(b"}")?;
+            self.writer.write_all(b",")?;
+        } else {
+            self.writer.write_all(b"{")?;
         }
-        Ok(ValueEncoder {
-            writer: self.writer,
-        })
-    }
-}
 
-#[must_use]
-pub struct ValueEncoder<'a> {
-    writer: &'a mut dyn Write,
-}
+        self.writer.write_all(b"quantile=\"")?;
+        quantile.encode(self.writer)?;
+        self.writer.write_all(b"\"}")?;
 
-impl<'a> ValueEncoder<'a> {
-    fn encode_value<V: Encode>(&mut self, v: V) -> Result<ExemplarEncoder, std::io::Error> {
-        self.writer.write_all(b" ")?;
-        v.encode(self.writer)?;
-        Ok(ExemplarEncoder {
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.push_label("quantile", &quantile)?;
+        }
+
+        Ok(ValueEncoder {
             writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
         })
     }
-}
-
-#[must_use]
-pub struct ExemplarEncoder<'a> {
-    writer: &'a mut dyn Write,
-}
+
+    /// Encodes the label of a [`StateSet`] sample, using the metric name as the
+    /// label name and the state name as the label value.
+    fn encode_state(
+        &mut self,
+        name: &str,
+        state: &'static str,
+    ) -> Result<ValueEncoder<'_>, std::io::Error> {
+        if self.opened_curly_brackets {
+            self.writer.write_all(b",")?;
+        } else {
+            self.writer.write_all(b"{")?;
+        }
+
+        self.writer.write_all(name.as_bytes())?;
+        self.writer.write_all(b"=\"")?;
+        state.encode(self.writer)?;
+        self.writer.write_all(b"\"}")?;
+
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.push_label(name, &state)?;
+        }
+
+        Ok(ValueEncoder {
+            writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
+        })
+    }
+
+    pub(crate) fn no_bucket(&mut self) -> Result<ValueEncoder<'_>, std::io::Error> {
+        if self.opened_curly_brackets {
+            self.writer.write_all(b"}")?;
+        }
+        Ok(ValueEncoder {
+            writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
+        })
+    }
+}
+
+#[must_use]
+pub struct ValueEncoder<'a> {
+    writer: &'a mut dyn Write,
+    recorder: Option<&'a mut Recorder>,
+}
+
+impl<'a> ValueEncoder<'a> {
+    pub(crate) fn encode_value<V: Encode>(
+        &mut self,
+        v: V,
+    ) -> Result<ExemplarEncoder<'_>, std::io::Error> {
+        self.writer.write_all(b" ")?;
+        v.encode(self.writer)?;
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.push_value(&v)?;
+        }
+        Ok(ExemplarEncoder {
+            writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
+        })
+    }
+}
+
+#[must_use]
+pub struct ExemplarEncoder<'a> {
+    writer: &'a mut dyn Write,
+    recorder: Option<&'a mut Recorder>,
+}
 
 impl<'a> ExemplarEncoder<'a> {
+    /// Encodes the given [`Exemplar`], including its timestamp if any, unless
+    /// the combined length of its label names and values exceeds
+    /// [`EXEMPLAR_MAX_LABEL_SET_LENGTH`], in which case the exemplar is
+    /// omitted, emitting a warning event with the `tracing` feature enabled.
     fn encode_exemplar<S: Encode, V: Encode>(
         &mut self,
         exemplar: &Exemplar<S, V>,
     ) -> Result<(), std::io::Error> {
+        let mut length = LabelSetLength::default();
+        exemplar.label_set.encode(&mut length)?;
+        if length.count > EXEMPLAR_MAX_LABEL_SET_LENGTH {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                length = length.count,
+                limit = EXEMPLAR_MAX_LABEL_SET_LENGTH,
+                "Omitted exemplar whose label set exceeds the length limit."
+            );
+            return self.no_exemplar();
+        }
+
         self.writer.write_all(b" # {")?;
         exemplar.label_set.encode(self.writer)?;
         self.writer.write_all(b"} ")?;
         exemplar.value.encode(self.writer)?;
+        if let Some(timestamp) = exemplar.timestamp {
+            let seconds = timestamp
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0);
+            self.writer.write_all(b" ")?;
+            seconds.encode(self.writer)?;
+        }
         self.writer.write_all(b"\n")?;
         Ok(())
     }
 
-    fn no_exemplar(&mut self) -> Result<(), std::io::Error> {
+    pub(crate) fn no_exemplar(&mut self) -> Result<(), std::io::Error> {
         self.writer.write_all(b"\n")?;
         Ok(())
     }
+
+    /// Encodes the given timestamp in seconds since the Unix epoch, to be
+    /// followed by an optional exemplar.
+    pub(crate) fn encode_timestamp(
+        &mut self,
+        timestamp: std::time::SystemTime,
+    ) -> Result<ExemplarEncoder<'_>, std::io::Error> {
+        let seconds = timestamp
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        self.writer.write_all(b" ")?;
+        seconds.encode(self.writer)?;
+        if let Some(recorder) = self.recorder.as_deref_mut() {
+            recorder.set_timestamp(timestamp);
+        }
+        Ok(ExemplarEncoder {
+            writer: self.writer,
+            recorder: self.recorder.as_deref_mut(),
+        })
+    }
+}
+
+/// Maximum combined length of the label names and values of an [`Exemplar`]'s
+/// label set in UTF-8 code points, as mandated by the OpenMetrics
+/// specification.
+const EXEMPLAR_MAX_LABEL_SET_LENGTH: usize = 128;
+
+/// [`Write`]r counting the UTF-8 code points of the label names and values of
+/// an encoded label set, excluding the `=`, `"` and `,` separating them as well
+/// as the backslash of escape sequences.
+#[derive(Default)]
+struct LabelSetLength {
+    count: usize,
+    in_value: bool,
+    escaped: bool,
+}
+
+impl Write for LabelSetLength {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        for byte in buf {
+            // Only count the first byte of each UTF-8 code point.
+            if byte & 0b1100_0000 == 0b1000_0000 {
+                continue;
+            }
+
+            if self.in_value {
+                match byte {
+                    _ if self.escaped => {
+                        self.escaped = false;
+                        self.count += 1;
+                    }
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_value = false,
+                    _ => self.count += 1,
+                }
+            } else {
+                match byte {
+                    b'"' => self.in_value = true,
+                    b'=' | b',' => {}
+                    _ => self.count += 1,
+                }
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
 }
 
 pub trait EncodeMetric {
@@ -380,6 +1177,25 @@ pub trait EncodeMetric {
     // One can not use [`TypedMetric`] directly, as associated constants are not
     // object safe and thus can not be used with dynamic dispatching.
     fn metric_type(&self) -> MetricType;
+
+    /// Whether the metric has no samples to expose, in which case [`encode`]
+    /// skips the entire metric family, including its `HELP` and `TYPE` lines.
+    ///
+    /// Only a [`Family`] without any label sets is empty. All other metric
+    /// types always expose their samples, e.g. a zero-valued sample for a
+    /// [`Counter`] or [`Histogram`] that was never touched.
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The number of series, i.e. label sets, the metric exposes, see
+    /// [`Registry::series_len`].
+    ///
+    /// Only a [`Family`] exposes a number of series other than one, namely one
+    /// per label set.
+    fn series_len(&self) -> usize {
+        1
+    }
 }
 
 impl EncodeMetric for Box<dyn EncodeMetric> {
@@ -390,6 +1206,95 @@ impl EncodeMetric for Box<dyn EncodeMetric> {
     fn metric_type(&self) -> MetricType {
         self.deref().metric_type()
     }
+
+    fn is_empty(&self) -> bool {
+        self.deref().is_empty()
+    }
+
+    fn series_len(&self) -> usize {
+        self.deref().series_len()
+    }
+}
+
+/// Allows registering a shared handle to a metric, e.g. an `Arc<Counter>`,
+/// without boxing.
+impl<M: EncodeMetric + ?Sized> EncodeMetric for Arc<M> {
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        self.deref().encode(encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        self.deref().metric_type()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.deref().is_empty()
+    }
+
+    fn series_len(&self) -> usize {
+        self.deref().series_len()
+    }
+}
+
+/// Encodes the guarded metric, holding the lock for the duration of each
+/// call, e.g. while encoding all samples of the metric. Make sure not to
+/// hold the lock yourself while encoding the [`Registry`] from the same
+/// thread, as that deadlocks. A poisoned lock is recovered from, continuing
+/// to encode the metric.
+impl<M: EncodeMetric + ?Sized> EncodeMetric for Mutex<M> {
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .encode(encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .metric_type()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    fn series_len(&self) -> usize {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .series_len()
+    }
+}
+
+/// Encodes the guarded metric, holding the read lock for the duration of each
+/// call, e.g. while encoding all samples of the metric. Writers are blocked
+/// in the meantime. A poisoned lock is recovered from, continuing to encode
+/// the metric.
+impl<M: EncodeMetric + ?Sized> EncodeMetric for RwLock<M> {
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        self.read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .encode(encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        self.read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .metric_type()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    fn series_len(&self) -> usize {
+        self.read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .series_len()
+    }
 }
 
 pub trait SendEncodeMetric: EncodeMetric + Send {}
@@ -404,6 +1309,14 @@ impl EncodeMetric for Box<dyn SendEncodeMetric> {
     fn metric_type(&self) -> MetricType {
         self.deref().metric_type()
     }
+
+    fn is_empty(&self) -> bool {
+        self.deref().is_empty()
+    }
+
+    fn series_len(&self) -> usize {
+        self.deref().series_len()
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////
@@ -441,6 +1354,37 @@ where
     }
 }
 
+impl<N, A> EncodeMetric for CounterWithCreated<N, A>
+where
+    N: Encode,
+    A: counter::Atomic<N>,
+{
+    fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+        encoder
+            .encode_suffix("total")?
+            .no_bucket()?
+            .encode_value(self.get())?
+            .no_exemplar()?;
+
+        let created = self
+            .created()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        encoder
+            .encode_suffix("created")?
+            .no_bucket()?
+            .encode_value(created)?
+            .no_exemplar()?;
+
+        Ok(())
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
 fn encode_counter_with_maybe_exemplar<S, N>(
     value: N,
     exemplar: Option<&Exemplar<S, N>>,
@@ -484,19 +1428,82 @@ where
     }
 }
 
+impl<N, A> EncodeMetric for GaugeWithTimestamp<N, A>
+where
+    N: Encode,
+    A: gauge::Atomic<N>,
+{
+    fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+        let timestamp = self.timestamp();
+        let mut bucket_encoder = encoder.no_suffix()?;
+        let mut value_encoder = bucket_encoder.no_bucket()?;
+        let mut exemplar_encoder = value_encoder.encode_value(self.get())?;
+
+        match timestamp {
+            Some(timestamp) => exemplar_encoder
+                .encode_timestamp(timestamp)?
+                .no_exemplar()?,
+            None => exemplar_encoder.no_exemplar()?,
+        }
+
+        Ok(())
+    }
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+impl EncodeMetric for GaugeFn {
+    fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+        encoder
+            .no_suffix()?
+            .no_bucket()?
+            .encode_value(self.get())?
+            .no_exemplar()?;
+
+        Ok(())
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
 /////////////////////////////////////////////////////////////////////////////////
 // Family
 
-impl<S, M, C> EncodeMetric for Family<S, M, C>
+impl<S, M, C, H> EncodeMetric for Family<S, M, C, H>
 where
     S: Clone + std::hash::Hash + Eq + Encode,
     M: EncodeMetric + TypedMetric,
     C: MetricConstructor<M>,
+    H: std::hash::BuildHasher + Clone,
 {
     fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
-        let guard = self.read();
-        for (label_set, m) in guard.iter() {
-            let encoder = encoder.with_label_set(label_set);
+        let guards = self.read();
+
+        // Encode all label sets into a single buffer once, both to sort by and
+        // to write each sample's labels from, instead of allocating and
+        // encoding per label set and sample.
+        let mut buffer = Vec::new();
+        let mut metrics = Vec::with_capacity(guards.iter().map(|shard| shard.len()).sum());
+        for (label_set, m) in guards.iter().flat_map(|shard| shard.iter()) {
+            let start = buffer.len();
+            label_set.encode(&mut buffer)?;
+            metrics.push((start..buffer.len(), m));
+        }
+        metrics.sort_by(|(a, _), (b, _)| buffer[a.clone()].cmp(&buffer[b.clone()]));
+
+        for (range, m) in metrics {
+            // A label set might encode to nothing, e.g. a derived label set
+            // with all optional fields absent.
+            if range.is_empty() {
+                m.encode(encoder.reborrow())?;
+                continue;
+            }
+
+            let label_set = EncodedLabelSet(&buffer[range]);
+            let encoder = encoder.with_label_set(&label_set);
             m.encode(encoder)?;
         }
         Ok(())
@@ -505,6 +1512,48 @@ where
     fn metric_type(&self) -> MetricType {
         M::TYPE
     }
+
+    fn is_empty(&self) -> bool {
+        Family::is_empty(self)
+    }
+
+    fn series_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////
+// CounterVec
+
+impl<N, A> EncodeMetric for CounterVec<N, A>
+where
+    N: Encode,
+    A: counter::Atomic<N>,
+{
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        self.family().encode(encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+
+    fn is_empty(&self) -> bool {
+        self.family().is_empty()
+    }
+
+    fn series_len(&self) -> usize {
+        self.family().series_len()
+    }
+}
+
+/// A label set already encoded by [`Encode::encode`].
+struct EncodedLabelSet<'a>(&'a [u8]);
+
+impl<'a> Encode for EncodedLabelSet<'a> {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        writer.write_all(self.0)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////
@@ -514,7 +1563,7 @@ impl EncodeMetric for Histogram {
     fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
         let (sum, count, buckets) = self.get();
         // TODO: Would be better to use never type instead of `()`.
-        encode_histogram_with_maybe_exemplars::<()>(sum, count, &buckets, None, encoder)
+        encode_histogram_with_maybe_exemplars::<()>(sum, count, buckets.iter(), None, encoder)
     }
 
     fn metric_type(&self) -> MetricType {
@@ -526,7 +1575,13 @@ impl<S: Encode> EncodeMetric for HistogramWithExemplars<S> {
     fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
         let inner = self.inner();
         let (sum, count, buckets) = inner.histogram.get();
-        encode_histogram_with_maybe_exemplars(sum, count, &buckets, Some(&inner.exemplars), encoder)
+        encode_histogram_with_maybe_exemplars(
+            sum,
+            count,
+            buckets.iter(),
+            Some(&inner.exemplars),
+            encoder,
+        )
     }
 
     fn metric_type(&self) -> MetricType {
@@ -537,7 +1592,7 @@ impl<S: Encode> EncodeMetric for HistogramWithExemplars<S> {
 fn encode_histogram_with_maybe_exemplars<S: Encode>(
     sum: f64,
     count: u64,
-    buckets: &[(f64, u64)],
+    buckets: impl Iterator<Item = (f64, u64)>,
     exemplars: Option<&HashMap<usize, Exemplar<S, f64>>>,
     mut encoder: Encoder,
 ) -> Result<(), std::io::Error> {
@@ -553,13 +1608,13 @@ fn encode_histogram_with_maybe_exemplars<S: Encode>(
         .no_exemplar()?;
 
     let mut cummulative = 0;
-    for (i, (upper_bound, count)) in buckets.iter().enumerate() {
+    for (i, (upper_bound, count)) in buckets.enumerate() {
         cummulative += count;
         let mut bucket_encoder = encoder.encode_suffix("bucket")?;
-        let mut value_encoder = bucket_encoder.encode_bucket(*upper_bound)?;
+        let mut value_encoder = bucket_encoder.encode_bucket(upper_bound)?;
         let mut exemplar_encoder = value_encoder.encode_value(cummulative)?;
 
-        match exemplars.map(|es| es.get(&i)).flatten() {
+        match exemplars.and_then(|es| es.get(&i)) {
             Some(exemplar) => exemplar_encoder.encode_exemplar(exemplar)?,
             None => exemplar_encoder.no_exemplar()?,
         }
@@ -568,6 +1623,56 @@ fn encode_histogram_with_maybe_exemplars<S: Encode>(
     Ok(())
 }
 
+/////////////////////////////////////////////////////////////////////////////////
+// NativeHistogram
+
+impl EncodeMetric for NativeHistogram {
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        let (sum, count, buckets) = self.get();
+        encode_histogram_with_maybe_exemplars::<()>(sum, count, buckets.into_iter(), None, encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////
+// GaugeHistogram
+
+impl EncodeMetric for GaugeHistogram {
+    fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+        let (sum, count, buckets) = self.get();
+
+        encoder
+            .encode_suffix("gsum")?
+            .no_bucket()?
+            .encode_value(sum)?
+            .no_exemplar()?;
+        encoder
+            .encode_suffix("gcount")?
+            .no_bucket()?
+            .encode_value(count)?
+            .no_exemplar()?;
+
+        let mut cummulative = 0;
+        for (upper_bound, count) in buckets.iter() {
+            cummulative += count;
+            encoder
+                .encode_suffix("bucket")?
+                .encode_bucket(*upper_bound)?
+                .encode_value(cummulative)?
+                .no_exemplar()?;
+        }
+
+        Ok(())
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
 /////////////////////////////////////////////////////////////////////////////////
 // Info
 
@@ -591,41 +1696,955 @@ where
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::metrics::counter::Counter;
-    use crate::metrics::gauge::Gauge;
-    use crate::metrics::histogram::exponential_buckets;
-    use pyo3::{prelude::*, types::PyModule};
-    use std::borrow::Cow;
-
-    #[test]
-    fn encode_counter() {
-        let counter: Counter = Counter::default();
-        let mut registry = Registry::default();
-        registry.register("my_counter", "My counter", counter.clone());
-
-        let mut encoded = Vec::new();
+/////////////////////////////////////////////////////////////////////////////////
+// StateSet
 
-        encode(&mut encoded, &registry).unwrap();
+impl<T: state_set::EnumLike> EncodeMetric for StateSet<T> {
+    fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+        let name = encoder.name;
+        let active = self.get();
+
+        for state in T::variants() {
+            encoder
+                .no_suffix()?
+                .encode_state(name, state.name())?
+                .encode_value(if *state == active { 1u32 } else { 0u32 })?
+                .no_exemplar()?;
+        }
 
-        parse_with_python_client(String::from_utf8(encoded).unwrap());
+        Ok(())
     }
 
-    #[test]
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////
+// Summary
+
+impl EncodeMetric for Summary {
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        let (sum, count, quantiles) = self.get();
+        encode_summary(sum, count, quantiles, encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+impl EncodeMetric for TDigestSummary {
+    fn encode(&self, encoder: Encoder) -> Result<(), std::io::Error> {
+        let (sum, count, quantiles) = self.get();
+        encode_summary(sum, count, quantiles, encoder)
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+fn encode_summary(
+    sum: f64,
+    count: u64,
+    quantiles: Vec<(f64, f64)>,
+    mut encoder: Encoder,
+) -> Result<(), std::io::Error> {
+    for (quantile, value) in quantiles {
+        encoder
+            .no_suffix()?
+            .encode_quantile(quantile)?
+            .encode_value(value)?
+            .no_exemplar()?;
+    }
+    encoder
+        .encode_suffix("sum")?
+        .no_bucket()?
+        .encode_value(sum)?
+        .no_exemplar()?;
+    encoder
+        .encode_suffix("count")?
+        .no_bucket()?
+        .encode_value(count)?
+        .no_exemplar()?;
+
+    Ok(())
+}
+
+/////////////////////////////////////////////////////////////////////////////////
+// Unknown
+
+impl EncodeMetric for Unknown {
+    fn encode(&self, mut encoder: Encoder) -> Result<(), std::io::Error> {
+        encoder
+            .no_suffix()?
+            .no_bucket()?
+            .encode_value(self.get())?
+            .no_exemplar()?;
+
+        Ok(())
+    }
+
+    fn metric_type(&self) -> MetricType {
+        Self::TYPE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::Collector;
+    use crate::metrics::counter::Counter;
+    use crate::metrics::gauge::Gauge;
+    use crate::metrics::histogram::exponential_buckets;
+    use pyo3::{prelude::*, types::PyModule};
+    use std::borrow::Cow;
+
+    #[test]
+    fn encode_counter() {
+        let counter: Counter = Counter::default();
+        let mut registry = Registry::default();
+        registry.register("my_counter", "My counter", counter.clone());
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_registered_with_total_suffix() {
+        for name in ["my_counter", "my_counter_total"] {
+            let counter: Counter = Counter::default();
+            let mut registry = Registry::default();
+            registry.register(name, "My counter", counter.clone());
+            counter.inc();
+
+            let mut encoded = Vec::new();
+            encode(&mut encoded, &registry).unwrap();
+
+            let expected = "# HELP my_counter My counter.\n".to_owned()
+                + "# TYPE my_counter counter\n"
+                + "my_counter_total 1\n"
+                + "# EOF\n";
+            assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+            parse_with_python_client(String::from_utf8(encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn encode_seeded_counter_and_gauge() {
+        let mut registry = <Registry>::default();
+        let counter: Counter = Counter::new(42);
+        registry.register("my_counter", "My counter", Box::new(counter.clone()));
+        let gauge = Gauge::<i64, std::sync::atomic::AtomicI64>::new(-7);
+        registry.register("my_gauge", "My gauge", Box::new(gauge));
+        counter.inc();
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 43\n"
+            + "# HELP my_gauge My gauge.\n"
+            + "# TYPE my_gauge gauge\n"
+            + "my_gauge -7\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_unregistered_counter() {
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", counter.clone());
+        registry.register("my_other_counter", "My other counter", counter);
+        registry.unregister("my_counter");
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_other_counter My other counter.\n".to_owned()
+            + "# TYPE my_other_counter counter\n"
+            + "my_other_counter_total 0\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn sample_counter() {
+        let mut counter = SampleCounter {
+            writer: Vec::new(),
+            samples: 0,
+            line_start: true,
+        };
+        counter
+            .write_all(b"# HELP a A.\n# TYPE a counter\na_total 1\n")
+            .unwrap();
+        counter.write_all(b"a_created").unwrap();
+        counter.write_all(b" 1\n# EOF\n").unwrap();
+
+        assert_eq!(2, counter.samples);
+    }
+
+    #[test]
+    fn encode_counter_with_created() {
+        let mut registry = Registry::default();
+        let counter = Counter::<u64>::with_created_now();
+        registry.register("my_counter", "My counter", counter.clone());
+        counter.inc();
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        let encoded = String::from_utf8(encoded).unwrap();
+
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 1\n"
+            + "my_counter_created ";
+        assert!(encoded.starts_with(&expected));
+
+        let created: f64 = encoded[expected.len()..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let expected_created = counter
+            .created()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        assert_eq!(expected_created, created);
+
+        parse_with_python_client(encoded);
+    }
+
+    #[test]
     fn encode_counter_with_unit() {
         let mut registry = Registry::default();
-        let counter: Counter = Counter::default();
-        registry.register_with_unit("my_counter", "My counter", Unit::Seconds, counter.clone());
+        let counter: Counter = Counter::default();
+        registry.register_with_unit("my_counter", "My counter", Unit::Seconds, counter.clone());
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_seconds My counter.\n".to_owned()
+            + "# TYPE my_counter_seconds counter\n"
+            + "# UNIT my_counter_seconds seconds\n"
+            + "my_counter_seconds_total 0\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_with_metadata_type_only_and_omit() {
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry.register_with_unit("my_counter", "My counter", Unit::Seconds, counter.clone());
+        counter.inc();
+
+        let mut encoded = Vec::new();
+        encode_with_metadata(&mut encoded, &registry, Metadata::TypeOnly).unwrap();
+        let expected = "# TYPE my_counter_seconds counter\n".to_owned()
+            + "my_counter_seconds_total 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded).unwrap());
+
+        let mut encoded = Vec::new();
+        encode_with_metadata(&mut encoded, &registry, Metadata::Omit).unwrap();
+        assert_eq!(
+            "my_counter_seconds_total 1\n# EOF\n",
+            String::from_utf8(encoded).unwrap()
+        );
+
+        let mut full = Vec::new();
+        encode_with_metadata(&mut full, &registry, Metadata::Full).unwrap();
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        assert_eq!(encoded, full);
+    }
+
+    #[test]
+    fn encode_gauge_fn() {
+        let value = Arc::new(std::sync::atomic::AtomicU64::new(1.5f64.to_bits()));
+        let mut registry = Registry::default();
+        let v = value.clone();
+        registry.register(
+            "my_gauge",
+            "My gauge",
+            GaugeFn::new(move || f64::from_bits(v.load(std::sync::atomic::Ordering::Relaxed))),
+        );
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        let expected = "# HELP my_gauge My gauge.\n".to_owned()
+            + "# TYPE my_gauge gauge\n"
+            + "my_gauge 1.5\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded).unwrap());
+
+        value.store((-4.0f64).to_bits(), std::sync::atomic::Ordering::Relaxed);
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        assert!(String::from_utf8(encoded.clone())
+            .unwrap()
+            .contains("\nmy_gauge -4.0\n"));
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_f64() {
+        let mut registry = Registry::default();
+        let counter = Counter::<f64, std::sync::atomic::AtomicU64>::default();
+        registry.register("my_counter", "My counter", counter.clone());
+        counter.inc_by(1.5);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 1.5\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_families_u64_and_f64() {
+        let mut registry = <Registry>::default();
+        let requests = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register("requests", "Number of requests", Box::new(requests.clone()));
+        let busy = Family::<Vec<(String, String)>, counter::CounterF64>::default();
+        registry.register_with_unit(
+            "busy",
+            "Time spent busy",
+            Unit::Seconds,
+            Box::new(busy.clone()),
+        );
+
+        let label_set = vec![("method".to_string(), "GET".to_string())];
+        requests.get_or_create(&label_set).inc_by(2);
+        busy.get_or_create(&label_set).inc_by(0.25);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP busy_seconds Time spent busy.\n".to_owned()
+            + "# TYPE busy_seconds counter\n"
+            + "# UNIT busy_seconds seconds\n"
+            + "busy_seconds_total{method=\"GET\"} 0.25\n"
+            + "# HELP requests Number of requests.\n"
+            + "# TYPE requests counter\n"
+            + "requests_total{method=\"GET\"} 2\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_with_exemplar() {
+        let mut registry = Registry::default();
+
+        let counter_with_exemplar: CounterWithExemplar<(String, u64)> =
+            CounterWithExemplar::default();
+        registry.register_with_unit(
+            "my_counter_with_exemplar",
+            "My counter with exemplar",
+            Unit::Seconds,
+            counter_with_exemplar.clone(),
+        );
+
+        counter_with_exemplar.inc_by(1, Some(("user_id".to_string(), 42)));
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_with_exemplar_seconds My counter with exemplar.\n"
+            .to_owned()
+            + "# TYPE my_counter_with_exemplar_seconds counter\n"
+            + "# UNIT my_counter_with_exemplar_seconds seconds\n"
+            + "my_counter_with_exemplar_seconds_total 1 # {user_id=\"42\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_with_exemplar_with_timestamp() {
+        let mut registry = Registry::default();
+
+        let counter_with_exemplar: CounterWithExemplar<(String, u64)> =
+            CounterWithExemplar::default();
+        registry.register(
+            "my_counter_with_exemplar",
+            "My counter with exemplar",
+            counter_with_exemplar.clone(),
+        );
+
+        counter_with_exemplar.inc_by_with_timestamp(
+            1,
+            Some(("user_id".to_string(), 42)),
+            std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_500),
+        );
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_with_exemplar My counter with exemplar.\n".to_owned()
+            + "# TYPE my_counter_with_exemplar counter\n"
+            + "my_counter_with_exemplar_total 1 # {user_id=\"42\"} 1 1.5\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_with_exemplar_exceeding_length_limit() {
+        let mut registry = Registry::default();
+
+        let counter_with_exemplar: CounterWithExemplar<(String, String)> =
+            CounterWithExemplar::default();
+        registry.register(
+            "my_counter_with_exemplar",
+            "My counter with exemplar",
+            counter_with_exemplar.clone(),
+        );
+
+        // 8 characters for the label name plus 121 for the value exceed the
+        // limit of 128.
+        counter_with_exemplar.inc_by(1, Some(("trace_id".to_string(), "ä".repeat(121))));
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_with_exemplar My counter with exemplar.\n".to_owned()
+            + "# TYPE my_counter_with_exemplar counter\n"
+            + "my_counter_with_exemplar_total 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn label_set_length() {
+        let mut length = LabelSetLength::default();
+        vec![
+            ("trace_id".to_string(), "ä".repeat(120)),
+            ("a".to_string(), "b".to_string()),
+        ]
+        .encode(&mut length)
+        .unwrap();
+        assert_eq!(8 + 120 + 1 + 1, length.count);
+
+        let mut length = LabelSetLength::default();
+        vec![("a".to_string(), "\"\\\n".to_string())]
+            .encode(&mut length)
+            .unwrap();
+        assert_eq!(1 + 3, length.count);
+    }
+
+    #[test]
+    fn escape_label_values_and_help() {
+        let mut registry = Registry::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register(
+            "my_counter_family",
+            "My counter family\nwith a \\ \"quoted\" help",
+            family.clone(),
+        );
+
+        family
+            .get_or_create(&vec![(
+                "path".to_string(),
+                "C:\\my \"dir\"\nsecond line".to_string(),
+            )])
+            .inc();
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_family My counter family\\nwith a \\\\ \"quoted\" help.\n"
+            .to_owned()
+            + "# TYPE my_counter_family counter\n"
+            + "my_counter_family_total{path=\"C:\\\\my \\\"dir\\\"\\nsecond line\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_gauge() {
+        let mut registry = Registry::default();
+        let gauge: Gauge = Gauge::default();
+        registry.register("my_gauge", "My gauge", gauge.clone());
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_gauge_with_timestamp() {
+        let mut registry = Registry::default();
+        let gauge: GaugeWithTimestamp = GaugeWithTimestamp::default();
+        registry.register("my_gauge", "My gauge", gauge.clone());
+        gauge.set_with_timestamp(
+            42,
+            std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_500_000_000_123),
+        );
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_gauge My gauge.\n".to_owned()
+            + "# TYPE my_gauge gauge\n"
+            + "my_gauge 42 1500000000.123\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_gauge_below_zero() {
+        let mut registry = Registry::default();
+        let gauge = Gauge::<i64, std::sync::atomic::AtomicI64>::default();
+        registry.register("my_gauge", "My gauge", gauge.clone());
+        gauge.set(-42);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_gauge My gauge.\n".to_owned()
+            + "# TYPE my_gauge gauge\n"
+            + "my_gauge -42\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_family() {
+        let mut registry = Registry::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register("my_counter_family", "My counter family", family.clone());
+
+        family
+            .get_or_create(&vec![
+                ("method".to_string(), "GET".to_string()),
+                ("status".to_string(), "200".to_string()),
+            ])
+            .inc();
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_family_with_interned_label_set() {
+        use crate::metrics::family::Interner;
+
+        let interned = Family::<Vec<(Arc<str>, Arc<str>)>, Counter>::default();
+        let owned = Family::<Vec<(String, String)>, Counter>::default();
+        let interner = Interner::default();
+        for (method, status) in [("GET", "200"), ("PUT", "200"), ("GET", "500")] {
+            interned
+                .get_or_create(&vec![
+                    (interner.intern("method"), interner.intern(method)),
+                    (interner.intern("status"), interner.intern(status)),
+                ])
+                .inc();
+            owned
+                .get_or_create(&vec![
+                    ("method".to_string(), method.to_string()),
+                    ("status".to_string(), status.to_string()),
+                ])
+                .inc();
+        }
+
+        let mut encoded_interned = Vec::new();
+        let mut registry = Registry::default();
+        registry.register("my_counter_family", "My counter family", interned);
+        encode(&mut encoded_interned, &registry).unwrap();
+
+        let mut encoded_owned = Vec::new();
+        let mut registry = Registry::default();
+        registry.register("my_counter_family", "My counter family", owned);
+        encode(&mut encoded_owned, &registry).unwrap();
+
+        assert_eq!(encoded_owned, encoded_interned);
+    }
+
+    #[test]
+    fn encode_counter_family_with_nested_sub_registry_labels() {
+        let mut registry = <Registry>::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry
+            .sub_registry_with_label((Cow::Borrowed("a"), Cow::Borrowed("1")))
+            .sub_registry_with_label((Cow::Borrowed("b"), Cow::Borrowed("2")))
+            .sub_registry_with_label((Cow::Borrowed("c"), Cow::Borrowed("3")))
+            .register(
+                "my_counter_family",
+                "My counter family",
+                Box::new(family.clone()),
+            );
+
+        family
+            .get_or_create(&vec![("method".to_string(), "GET".to_string())])
+            .inc();
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_family My counter family.\n".to_owned()
+            + "# TYPE my_counter_family counter\n"
+            + "my_counter_family_total{a=\"1\",b=\"2\",c=\"3\",method=\"GET\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_family_with_array_label_set() {
+        let mut registry = Registry::default();
+        let family = Family::<[(&str, &str); 2], Counter>::default();
+        registry.register("my_counter_family", "My counter family", family.clone());
+
+        family
+            .get_or_create(&[("status", "200"), ("method", "GET")])
+            .inc();
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_family My counter family.\n".to_owned()
+            + "# TYPE my_counter_family counter\n"
+            + "my_counter_family_total{status=\"200\",method=\"GET\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_family_with_primitive_label_values() {
+        let mut registry = <Registry>::default();
+        let family = Family::<Vec<(&str, i32)>, Counter>::default();
+        registry.register(
+            "my_counter_family",
+            "My counter family",
+            Box::new(family.clone()),
+        );
+        let other_family = Family::<Vec<(&str, bool)>, Counter>::default();
+        registry.register(
+            "my_other_counter_family",
+            "My other counter family",
+            Box::new(other_family.clone()),
+        );
+
+        family.get_or_create(&vec![("shard", -1)]).inc();
+        other_family.get_or_create(&vec![("cached", true)]).inc();
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter_family My counter family.\n".to_owned()
+            + "# TYPE my_counter_family counter\n"
+            + "my_counter_family_total{shard=\"-1\"} 1\n"
+            + "# HELP my_other_counter_family My other counter family.\n"
+            + "# TYPE my_other_counter_family counter\n"
+            + "my_other_counter_family_total{cached=\"true\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        let value = "a\"b".to_string();
+        let mut label = Vec::new();
+        ("key", &value).encode(&mut label).unwrap();
+        assert_eq!("key=\"a\\\"b\"", String::from_utf8(label).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_collector_with_prefix_with_label() {
+        struct MyCollector;
+
+        impl Collector for MyCollector {
+            fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+                let gauge: Gauge = Gauge::default();
+                gauge.set(42);
+                vec![(
+                    Descriptor::new("my_gauge", "My gauge", None, vec![]),
+                    Box::new(gauge),
+                )]
+            }
+        }
+
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", counter);
+        registry
+            .sub_registry_with_prefix("my_prefix")
+            .sub_registry_with_label((Cow::Borrowed("my_key"), Cow::Borrowed("my_value")))
+            .register_collector(Box::new(MyCollector));
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 0\n"
+            + "# HELP my_prefix_my_gauge My gauge.\n"
+            + "# TYPE my_prefix_my_gauge gauge\n"
+            + "my_prefix_my_gauge{my_key=\"my_value\"} 42\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_collector_name_collision() {
+        struct MyCollector;
+
+        impl Collector for MyCollector {
+            fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+                vec![(
+                    Descriptor::new("my_counter", "My collected counter", None, vec![]),
+                    Box::new(Counter::<u64>::default()),
+                )]
+            }
+        }
+
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", counter);
+        registry.register_collector(Box::new(MyCollector));
+        assert_eq!(1, registry.iter_collectors().count());
+
+        let mut encoded = Vec::new();
+        let error = encode(&mut encoded, &registry).unwrap_err();
+        assert!(matches!(error, EncodeError::DuplicateName(name) if name == "my_counter"));
+        assert!(encoded.is_empty());
+    }
+
+    #[test]
+    fn encode_collector_total_suffix_collision() {
+        struct MyCollector;
+
+        impl Collector for MyCollector {
+            fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+                vec![(
+                    Descriptor::new("my_counter_total", "My collected counter", None, vec![]),
+                    Box::new(Counter::<u64>::default()),
+                )]
+            }
+        }
+
+        let mut registry = Registry::default();
+        registry.register("my_counter", "My counter", Counter::<u64>::default());
+        registry.register_collector(Box::new(MyCollector));
+
+        let error = encode(&mut Vec::new(), &registry).unwrap_err();
+        assert!(matches!(error, EncodeError::DuplicateName(name) if name == "my_counter_total"));
+    }
+
+    #[test]
+    fn encode_self_metrics() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // Advances by 250ms on each call.
+        let ticks = AtomicU64::new(0);
+        let clock =
+            move || UNIX_EPOCH + Duration::from_millis(250 * ticks.fetch_add(1, Ordering::SeqCst));
+
+        let mut registry = Registry::default();
+        registry.register("my_counter", "My counter", Counter::<u64>::default());
+        registry.enable_self_metrics_with_clock(clock);
+        registry.enable_self_metrics();
+
+        let expected = |scrapes, duration| {
+            "# HELP my_counter My counter.\n".to_owned()
+                + "# TYPE my_counter counter\n"
+                + "my_counter_total 0\n"
+                + "# HELP prometheus_client_scrape_duration_seconds Duration of the most recent successful scrape of the registry.\n"
+                + "# TYPE prometheus_client_scrape_duration_seconds gauge\n"
+                + "# UNIT prometheus_client_scrape_duration_seconds seconds\n"
+                + format!("prometheus_client_scrape_duration_seconds {}\n", duration).as_str()
+                + "# HELP prometheus_client_scrapes Number of successful scrapes of the registry.\n"
+                + "# TYPE prometheus_client_scrapes counter\n"
+                + format!("prometheus_client_scrapes_total {}\n", scrapes).as_str()
+                + "# EOF\n"
+        };
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        assert_eq!(expected(0, "0.0"), String::from_utf8(encoded).unwrap());
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        assert_eq!(
+            expected(1, "0.25"),
+            String::from_utf8(encoded.clone()).unwrap()
+        );
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_families_in_chunks() {
+        let mut registry = <Registry>::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register(
+            "my_counter_family",
+            "My counter family",
+            Box::new(family.clone()),
+        );
+        family
+            .get_or_create(&vec![("method".to_string(), "GET".to_string())])
+            .inc();
+        registry.register("my_gauge", "My gauge", Box::new(Counter::<u64>::default()));
+
+        let chunks: Vec<Vec<u8>> = registry
+            .encode_families()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(3, chunks.len());
+        assert!(chunks[0].starts_with(b"# HELP my_counter_family "));
+        assert!(chunks[1].starts_with(b"# HELP my_gauge "));
+        assert_eq!(b"# EOF\n".to_vec(), chunks[2]);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        assert_eq!(encoded, chunks.concat());
+
+        registry.register_with_unit(
+            "my_other_counter",
+            "My other counter",
+            Unit::Other("milli seconds".to_string()),
+            Box::new(Counter::<u64>::default()),
+        );
+        let mut families = registry.encode_families();
+        assert!(families.next().unwrap().is_err());
+        assert!(families.next().is_none());
+    }
+
+    #[test]
+    fn encode_reserved_label_name() {
+        for name in RESERVED_LABEL_NAMES {
+            let mut registry = Registry::default();
+            let family = Family::<Vec<(String, String)>, Gauge>::default();
+            registry.register("my_gauge_family", "My gauge family", family.clone());
+            family
+                .get_or_create(&vec![(name.to_string(), "1.0".to_string())])
+                .set(1);
+
+            let error = encode(&mut Vec::new(), &registry).unwrap_err();
+            assert!(error.to_string().contains(&format!("{:?}", name)));
+            assert!(matches!(error, EncodeError::ReservedLabel(names) if names == [name]));
+        }
+
+        // Label names merely starting with a reserved name are fine.
+        let mut registry = Registry::default();
+        let family = Family::<Vec<(String, String)>, Gauge>::default();
+        registry.register("my_gauge_family", "My gauge family", family.clone());
+        family
+            .get_or_create(&vec![("level".to_string(), "1".to_string())])
+            .set(1);
+        encode(&mut Vec::new(), &registry).unwrap();
+    }
+
+    #[test]
+    fn encode_invalid_unit_and_label_names() {
+        let mut registry = Registry::default();
+        registry.register_with_unit(
+            "my_counter",
+            "My counter",
+            Unit::Other("milli seconds".to_string()),
+            Counter::<u64>::default(),
+        );
+        registry.register_with_unit(
+            "my_other_counter",
+            "My other counter",
+            Unit::Other("%".to_string()),
+            Counter::<u64>::default(),
+        );
 
         let mut encoded = Vec::new();
-        encode(&mut encoded, &registry).unwrap();
+        let error = encode(&mut encoded, &registry).unwrap_err();
+        assert!(error.to_string().contains("\"milli seconds\""));
+        assert!(error.to_string().contains("\"%\""));
+        assert!(matches!(error, EncodeError::InvalidName(names) if names.len() == 2));
+        assert!(encoded.is_empty());
 
-        let expected = "# HELP my_counter_seconds My counter.\n".to_owned()
-            + "# TYPE my_counter_seconds counter\n"
-            + "# UNIT my_counter_seconds seconds\n"
-            + "my_counter_seconds_total 0\n"
+        let mut registry = Registry::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register("my_counter_family", "My counter family", family.clone());
+        family
+            .get_or_create(&vec![("1st\"} 1\n".to_string(), "GET".to_string())])
+            .inc();
+
+        let error = encode(&mut Vec::new(), &registry).unwrap_err();
+        assert!(error.to_string().contains("1st"));
+        assert!(matches!(error, EncodeError::InvalidName(_)));
+    }
+
+    #[test]
+    fn encode_shared_and_locked_metrics() {
+        let mut registry = <Registry>::default();
+        let counter = Arc::new(Counter::<u64>::default());
+        registry.register("my_counter", "My counter", Box::new(counter.clone()));
+        let gauge = Arc::new(Mutex::new(Gauge::<u64>::default()));
+        registry.register("my_gauge", "My gauge", Box::new(gauge.clone()));
+        registry.register(
+            "my_empty_family",
+            "My empty family",
+            Box::new(RwLock::new(
+                Family::<Vec<(String, String)>, Counter>::default(),
+            )),
+        );
+
+        counter.inc();
+        gauge.lock().unwrap().set(3);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 1\n"
+            + "# HELP my_gauge My gauge.\n"
+            + "# TYPE my_gauge gauge\n"
+            + "my_gauge 3\n"
             + "# EOF\n";
         assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
 
@@ -633,28 +2652,58 @@ mod tests {
     }
 
     #[test]
-    fn encode_counter_with_exemplar() {
-        let mut registry = Registry::default();
+    fn encode_poisoned_locked_metrics() {
+        let mut registry = <Registry>::default();
+        let gauge = Arc::new(Mutex::new(Gauge::<u64>::default()));
+        registry.register("my_gauge", "My gauge", Box::new(gauge.clone()));
+        let counter = Arc::new(RwLock::new(Counter::<u64>::default()));
+        registry.register("my_counter", "My counter", Box::new(counter.clone()));
+
+        gauge.lock().unwrap().set(3);
+        counter.read().unwrap().inc();
+        let (poisoned_gauge, poisoned_counter) = (gauge.clone(), counter.clone());
+        std::thread::spawn(move || {
+            let _gauge = poisoned_gauge.lock().unwrap();
+            let _counter = poisoned_counter.write().unwrap();
+            panic!("Poison the locks.");
+        })
+        .join()
+        .unwrap_err();
+        assert!(gauge.is_poisoned());
+        assert!(counter.is_poisoned());
 
-        let counter_with_exemplar: CounterWithExemplar<(String, u64)> =
-            CounterWithExemplar::default();
-        registry.register_with_unit(
-            "my_counter_with_exemplar",
-            "My counter with exemplar",
-            Unit::Seconds,
-            counter_with_exemplar.clone(),
-        );
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 1\n"
+            + "# HELP my_gauge My gauge.\n"
+            + "# TYPE my_gauge gauge\n"
+            + "my_gauge 3\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded).unwrap());
+    }
 
-        counter_with_exemplar.inc_by(1, Some(("user_id".to_string(), 42)));
+    #[test]
+    fn encode_counter_family_after_remove() {
+        let mut registry = Registry::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register("my_counter_family", "My counter family", family.clone());
+
+        let get = vec![("method".to_string(), "GET".to_string())];
+        family.get_or_create(&get).inc();
+        family
+            .get_or_create(&vec![("method".to_string(), "PUT".to_string())])
+            .inc();
+        family.remove(&vec![("method".to_string(), "PUT".to_string())]);
 
         let mut encoded = Vec::new();
+
         encode(&mut encoded, &registry).unwrap();
 
-        let expected = "# HELP my_counter_with_exemplar_seconds My counter with exemplar.\n"
-            .to_owned()
-            + "# TYPE my_counter_with_exemplar_seconds counter\n"
-            + "# UNIT my_counter_with_exemplar_seconds seconds\n"
-            + "my_counter_with_exemplar_seconds_total 1 # {user_id=\"42\"} 1\n"
+        let expected = "# HELP my_counter_family My counter family.\n".to_owned()
+            + "# TYPE my_counter_family counter\n"
+            + "my_counter_family_total{method=\"GET\"} 1\n"
             + "# EOF\n";
         assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
 
@@ -662,35 +2711,148 @@ mod tests {
     }
 
     #[test]
-    fn encode_gauge() {
-        let mut registry = Registry::default();
-        let gauge: Gauge = Gauge::default();
-        registry.register("my_gauge", "My gauge", gauge.clone());
+    fn encode_skips_empty_family() {
+        let mut registry = <Registry>::default();
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        registry.register(
+            "my_counter_family",
+            "My counter family",
+            Box::new(family.clone()),
+        );
+        registry.register(
+            "my_counter",
+            "My counter",
+            Box::new(Counter::<u64>::default()),
+        );
 
         let mut encoded = Vec::new();
-
         encode(&mut encoded, &registry).unwrap();
 
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 0\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
         parse_with_python_client(String::from_utf8(encoded).unwrap());
     }
 
     #[test]
-    fn encode_counter_family() {
+    fn encode_counter_family_with_const_labels() {
         let mut registry = Registry::default();
+        let sub_registry =
+            registry.sub_registry_with_label((Cow::Borrowed("my_key"), Cow::Borrowed("my_value")));
         let family = Family::<Vec<(String, String)>, Counter>::default();
-        registry.register("my_counter_family", "My counter family", family.clone());
+        sub_registry.register_with_labels(
+            "my_counter_family",
+            "My counter family",
+            [("version", "1.2.3")],
+            family.clone(),
+        );
 
         family
-            .get_or_create(&vec![
-                ("method".to_string(), "GET".to_string()),
-                ("status".to_string(), "200".to_string()),
-            ])
+            .get_or_create(&vec![("method".to_string(), "GET".to_string())])
             .inc();
 
         let mut encoded = Vec::new();
 
         encode(&mut encoded, &registry).unwrap();
 
+        let expected = "# HELP my_counter_family My counter family.\n".to_owned()
+            + "# TYPE my_counter_family counter\n"
+            + "my_counter_family_total{my_key=\"my_value\",version=\"1.2.3\",method=\"GET\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_counter_with_nested_prefix() {
+        let mut registry = Registry::default();
+        let counter: Counter = Counter::default();
+        registry
+            .sub_registry_with_prefix("outer")
+            .sub_registry_with_prefix("inner")
+            .register("my_counter", "My counter", counter);
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP outer_inner_my_counter My counter.\n".to_owned()
+            + "# TYPE outer_inner_my_counter counter\n"
+            + "outer_inner_my_counter_total 0\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_sorted() {
+        let mut registry = <Registry>::default();
+        let family = Family::<Vec<(String, String)>, Counter>::new_with_shards(4);
+        registry.register(
+            "b_counter_family",
+            "My counter family",
+            Box::new(family.clone()),
+        );
+        let counter: Counter = Counter::default();
+        registry.register("a_counter", "My counter", Box::new(counter));
+
+        for method in ["PUT", "GET", "POST", "DELETE"] {
+            family
+                .get_or_create(&vec![("method".to_string(), method.to_string())])
+                .inc();
+        }
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP a_counter My counter.\n".to_owned()
+            + "# TYPE a_counter counter\n"
+            + "a_counter_total 0\n"
+            + "# HELP b_counter_family My counter family.\n"
+            + "# TYPE b_counter_family counter\n"
+            + "b_counter_family_total{method=\"DELETE\"} 1\n"
+            + "b_counter_family_total{method=\"GET\"} 1\n"
+            + "b_counter_family_total{method=\"POST\"} 1\n"
+            + "b_counter_family_total{method=\"PUT\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_sorted_by_family_name() {
+        let mut registry = <Registry>::default();
+        let counter: Counter = Counter::default();
+        registry.register("a_total", "My counter", Box::new(counter));
+        let gauge: Gauge = Gauge::default();
+        registry.register("a_b", "My gauge", Box::new(gauge));
+        let gauge: Gauge = Gauge::default();
+        registry.register_with_unit("a", "My other gauge", Unit::Bytes, Box::new(gauge));
+
+        let mut encoded = Vec::new();
+
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP a My counter.\n".to_owned()
+            + "# TYPE a counter\n"
+            + "a_total 0\n"
+            + "# HELP a_b My gauge.\n"
+            + "# TYPE a_b gauge\n"
+            + "a_b 0\n"
+            + "# HELP a_bytes My other gauge.\n"
+            + "# TYPE a_bytes gauge\n"
+            + "# UNIT a_bytes bytes\n"
+            + "a_bytes 0\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
         parse_with_python_client(String::from_utf8(encoded).unwrap());
     }
 
@@ -742,6 +2904,68 @@ mod tests {
         parse_with_python_client(String::from_utf8(encoded).unwrap());
     }
 
+    #[test]
+    fn encode_state_set() {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Connection {
+            Down,
+            Up,
+        }
+
+        impl state_set::EnumLike for Connection {
+            fn variants() -> &'static [Self] {
+                &[Connection::Down, Connection::Up]
+            }
+
+            fn name(&self) -> &'static str {
+                match self {
+                    Connection::Down => "down",
+                    Connection::Up => "up",
+                }
+            }
+        }
+
+        let mut registry = Registry::default();
+        let family = Family::<Vec<(String, String)>, StateSet<Connection>>::default();
+        registry.register("connection", "Connection state", family.clone());
+        family
+            .get_or_create(&vec![("peer".to_string(), "a".to_string())])
+            .set(Connection::Up);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP connection Connection state.\n".to_owned()
+            + "# TYPE connection stateset\n"
+            + "connection{peer=\"a\",connection=\"down\"} 0\n"
+            + "connection{peer=\"a\",connection=\"up\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_build_info() {
+        let mut registry = Registry::default();
+        let info = Info::new(vec![
+            ("version".to_string(), "1.2.3".to_string()),
+            ("commit".to_string(), "e5ee1f2".to_string()),
+        ]);
+        registry.register("build_info", "Build information", info);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP build Build information.\n".to_owned()
+            + "# TYPE build info\n"
+            + "build_info{version=\"1.2.3\",commit=\"e5ee1f2\"} 1\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
     #[test]
     fn encode_histogram() {
         let mut registry = Registry::default();
@@ -756,6 +2980,154 @@ mod tests {
         parse_with_python_client(String::from_utf8(encoded).unwrap());
     }
 
+    #[test]
+    fn encode_histogram_with_negative_buckets() {
+        let mut registry = Registry::default();
+        let histogram = Histogram::new(vec![-10.0, -1.0, 0.0, 1.0, 10.0].into_iter());
+        registry.register("my_histogram", "My histogram", histogram.clone());
+        for v in [-20.0, -5.0, -1.0, 0.5, 3.0, 20.0] {
+            histogram.observe(v);
+        }
+        histogram.observe(-30.0);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_histogram My histogram.\n".to_owned()
+            + "# TYPE my_histogram histogram\n"
+            + "my_histogram_sum -32.5\n"
+            + "my_histogram_count 7\n"
+            + "my_histogram_bucket{le=\"-10.0\"} 2\n"
+            + "my_histogram_bucket{le=\"-1.0\"} 4\n"
+            + "my_histogram_bucket{le=\"0.0\"} 4\n"
+            + "my_histogram_bucket{le=\"1.0\"} 5\n"
+            + "my_histogram_bucket{le=\"10.0\"} 6\n"
+            + "my_histogram_bucket{le=\"+Inf\"} 7\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_native_histogram() {
+        let mut registry = Registry::default();
+        let histogram = NativeHistogram::new(0);
+        registry.register("my_histogram", "My histogram", histogram.clone());
+        histogram.observe(-3.0);
+        histogram.observe(0.0);
+        histogram.observe(1.5);
+        histogram.observe(4.0);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_histogram My histogram.\n".to_owned()
+            + "# TYPE my_histogram histogram\n"
+            + "my_histogram_sum 2.5\n"
+            + "my_histogram_count 4\n"
+            + "my_histogram_bucket{le=\"-2.0\"} 1\n"
+            + "my_histogram_bucket{le=\"0.0\"} 2\n"
+            + "my_histogram_bucket{le=\"2.0\"} 3\n"
+            + "my_histogram_bucket{le=\"4.0\"} 4\n"
+            + "my_histogram_bucket{le=\"+Inf\"} 4\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_gauge_histogram() {
+        let mut registry = Registry::default();
+        let gauge_histogram = GaugeHistogram::new(vec![1.0, 10.0].into_iter());
+        registry.register("queue_age", "Age of queued items", gauge_histogram.clone());
+        gauge_histogram.set(vec![0.5, 5.0]);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP queue_age Age of queued items.\n".to_owned()
+            + "# TYPE queue_age gaugehistogram\n"
+            + "queue_age_gsum 5.5\n"
+            + "queue_age_gcount 2\n"
+            + "queue_age_bucket{le=\"1.0\"} 1\n"
+            + "queue_age_bucket{le=\"10.0\"} 2\n"
+            + "queue_age_bucket{le=\"+Inf\"} 2\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_summary() {
+        let mut registry = Registry::default();
+        let summary = Summary::new(vec![0.5, 0.9], 0.01);
+        registry.register("my_summary", "My summary", summary.clone());
+        for v in 1..=10 {
+            summary.observe(v as f64);
+        }
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_summary My summary.\n".to_owned()
+            + "# TYPE my_summary summary\n"
+            + "my_summary{quantile=\"0.5\"} 5.0\n"
+            + "my_summary{quantile=\"0.9\"} 9.0\n"
+            + "my_summary_sum 55.0\n"
+            + "my_summary_count 10\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_t_digest_summary() {
+        let mut registry = Registry::default();
+        let summary = TDigestSummary::new(vec![0.5, 0.9, 0.99], 100.0);
+        registry.register("my_summary", "My summary", summary.clone());
+        for v in 1..=10 {
+            summary.observe(v as f64);
+        }
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_summary My summary.\n".to_owned()
+            + "# TYPE my_summary summary\n"
+            + "my_summary{quantile=\"0.5\"} 5.5\n"
+            + "my_summary{quantile=\"0.9\"} 9.5\n"
+            + "my_summary{quantile=\"0.99\"} 10.0\n"
+            + "my_summary_sum 55.0\n"
+            + "my_summary_count 10\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
+    #[test]
+    fn encode_unknown() {
+        let mut registry = Registry::default();
+        let unknown = Unknown::default();
+        registry.register("my_unknown", "My unknown", unknown.clone());
+        unknown.set(4.2);
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_unknown My unknown.\n".to_owned()
+            + "# TYPE my_unknown unknown\n"
+            + "my_unknown 4.2\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
     #[test]
     fn encode_histogram_family() {
         let mut registry = Registry::default();
@@ -807,10 +3179,36 @@ mod tests {
         parse_with_python_client(String::from_utf8(encoded).unwrap());
     }
 
+    #[test]
+    fn encode_histogram_with_latest_exemplar_per_bucket() {
+        let mut registry = Registry::default();
+        let histogram = HistogramWithExemplars::new(vec![1.0].into_iter());
+        registry.register("my_histogram", "My histogram", histogram.clone());
+        histogram.observe(0.5, Some(("user_id".to_string(), 1u64)));
+        histogram.observe(0.7, Some(("user_id".to_string(), 2u64)));
+        histogram.observe(0.8, None);
+        histogram.observe(5.0, Some(("user_id".to_string(), 3u64)));
+
+        assert_eq!(2, histogram.inner().exemplars.len());
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+
+        let expected = "# HELP my_histogram My histogram.\n".to_owned()
+            + "# TYPE my_histogram histogram\n"
+            + "my_histogram_sum 7.0\n"
+            + "my_histogram_count 4\n"
+            + "my_histogram_bucket{le=\"1.0\"} 3 # {user_id=\"2\"} 0.7\n"
+            + "my_histogram_bucket{le=\"+Inf\"} 4 # {user_id=\"3\"} 5.0\n"
+            + "# EOF\n";
+        assert_eq!(expected, String::from_utf8(encoded.clone()).unwrap());
+
+        parse_with_python_client(String::from_utf8(encoded).unwrap());
+    }
+
     fn parse_with_python_client(input: String) {
         pyo3::prepare_freethreaded_python();
 
-        println!("{:?}", input);
         Python::with_gil(|py| {
             let parser = PyModule::from_code(
                 py,
@@ -0,0 +1,4 @@
+//! Integrations with third party libraries, each behind a feature flag.
+
+#[cfg(feature = "axum")]
+pub mod axum;
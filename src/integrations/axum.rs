@@ -0,0 +1,104 @@
+//! Module serving the metrics of a [`Registry`] via [axum](https://docs.rs/axum).
+//!
+//! See [`metrics_handler`] for details.
+
+use crate::encoding::{content_type, encode_to_vec, Format};
+use crate::registry::Registry;
+
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Axum handler encoding the metrics of the shared [`Registry`] on each
+/// request.
+///
+/// The metrics are encoded in the OpenMetrics text format, the only
+/// [`Format`] supported for now, and the `Content-Type` header of the
+/// response is set accordingly. In case
+/// encoding fails, a 500 response describing the error is returned. A poisoned
+/// [`Mutex`] is recovered from, continuing to serve the metrics.
+///
+/// ```
+/// # use axum::routing::get;
+/// # use axum::Router;
+/// # use prometheus_client::integrations::axum::metrics_handler;
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::registry::Registry;
+/// # use std::sync::{Arc, Mutex};
+/// #
+/// let mut registry = <Registry>::default();
+/// let counter: Counter = Counter::default();
+/// registry.register("my_counter", "This is my counter", Box::new(counter));
+///
+/// let app: Router = Router::new()
+///     .route("/metrics", get(metrics_handler))
+///     .with_state(Arc::new(Mutex::new(registry)));
+/// ```
+pub async fn metrics_handler(State(registry): State<Arc<Mutex<Registry>>>) -> Response {
+    let format = Format::OpenMetricsText;
+
+    // A panic while the lock was held, e.g. on a duplicate registration,
+    // leaves the registry itself intact. Keep serving it.
+    let result = encode_to_vec(
+        &registry.lock().unwrap_or_else(PoisonError::into_inner),
+        format,
+    );
+
+    match result {
+        Ok(body) => ([(CONTENT_TYPE, content_type(format))], body).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to encode metrics: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::counter::Counter;
+
+    #[async_std::test]
+    async fn serve_metrics() {
+        let mut registry = <Registry>::default();
+        let counter: Counter = Counter::default();
+        registry.register("my_counter", "My counter", Box::new(counter.clone()));
+        counter.inc();
+
+        let response = metrics_handler(State(Arc::new(Mutex::new(registry)))).await;
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            response.headers()[CONTENT_TYPE]
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let expected = "# HELP my_counter My counter.\n".to_owned()
+            + "# TYPE my_counter counter\n"
+            + "my_counter_total 1\n"
+            + "# EOF\n";
+        assert_eq!(expected.as_bytes(), &body[..]);
+    }
+
+    #[async_std::test]
+    async fn serve_metrics_after_poisoning() {
+        let registry = Arc::new(Mutex::new(<Registry>::default()));
+        let poisoned = registry.clone();
+        std::thread::spawn(move || {
+            let _guard = poisoned.lock().unwrap();
+            panic!("Poison the lock.");
+        })
+        .join()
+        .unwrap_err();
+
+        let response = metrics_handler(State(registry)).await;
+
+        assert_eq!(StatusCode::OK, response.status());
+    }
+}
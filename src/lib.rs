@@ -76,6 +76,9 @@
 //!
 //! [examples]: https://github.com/prometheus/client_rust/tree/master/examples
 
+pub mod clock;
+pub mod collector;
 pub mod encoding;
+pub mod integrations;
 pub mod metrics;
 pub mod registry;
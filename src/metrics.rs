@@ -1,27 +1,105 @@
 //! Metric type implementations.
 
 pub mod counter;
+pub mod counter_vec;
 pub mod exemplar;
 pub mod family;
 pub mod gauge;
+pub mod gauge_histogram;
 pub mod histogram;
 pub mod info;
+pub mod native_histogram;
+pub mod state_set;
+pub mod summary;
+pub mod t_digest_summary;
+pub mod unknown;
 
 /// A metric that is aware of its Open Metrics metric type.
 pub trait TypedMetric {
     const TYPE: MetricType = MetricType::Unknown;
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MetricType {
     Counter,
     Gauge,
+    GaugeHistogram,
     Histogram,
     Info,
+    StateSet,
+    Summary,
     Unknown,
-    // Not (yet) supported metric types.
-    //
-    // GaugeHistogram,
-    // StateSet,
-    // Summary
+}
+
+impl MetricType {
+    /// All metric types.
+    const VARIANTS: [MetricType; 8] = [
+        MetricType::Counter,
+        MetricType::Gauge,
+        MetricType::GaugeHistogram,
+        MetricType::Histogram,
+        MetricType::Info,
+        MetricType::StateSet,
+        MetricType::Summary,
+        MetricType::Unknown,
+    ];
+
+    /// The Open Metrics type token of the metric type, as used in `# TYPE`
+    /// lines.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::MetricType;
+    /// assert_eq!("gaugehistogram", MetricType::GaugeHistogram.as_str());
+    /// assert_eq!(Ok(MetricType::GaugeHistogram), "gaugehistogram".parse());
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::GaugeHistogram => "gaugehistogram",
+            MetricType::Histogram => "histogram",
+            MetricType::Info => "info",
+            MetricType::StateSet => "stateset",
+            MetricType::Summary => "summary",
+            MetricType::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::str::FromStr for MetricType {
+    type Err = ParseMetricTypeError;
+
+    /// Parse an Open Metrics type token, see [`MetricType::as_str`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MetricType::VARIANTS
+            .iter()
+            .find(|t| t.as_str() == s)
+            .copied()
+            .ok_or_else(|| ParseMetricTypeError(s.to_owned()))
+    }
+}
+
+/// Error returned when parsing an unknown [`MetricType`] token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseMetricTypeError(String);
+
+impl std::fmt::Display for ParseMetricTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown metric type {:?}.", self.0)
+    }
+}
+
+impl std::error::Error for ParseMetricTypeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_type_round_trip() {
+        for t in MetricType::VARIANTS {
+            assert_eq!(Ok(t), t.as_str().parse());
+        }
+        assert!("Counter".parse::<MetricType>().is_err());
+    }
 }
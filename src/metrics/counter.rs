@@ -3,9 +3,11 @@
 //! See [`Counter`] for details.
 
 use super::{MetricType, TypedMetric};
+use crate::clock::{Clock, SystemClock};
 use std::marker::PhantomData;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 /// Open Metrics [`Counter`] to measure discrete events.
 ///
@@ -27,8 +29,15 @@ use std::sync::Arc;
 /// let _value: u64 = counter.get();
 /// ```
 ///
+/// Note: Integer counters saturate at their maximum value, e.g. [`u64::MAX`],
+/// instead of wrapping around. A [`Counter`] reporting its maximum value thus
+/// indicates that it saturated.
+///
 /// ## Using [`AtomicU64`] as storage and [`f64`] on the interface
 ///
+/// Note: As a counter is monotonically increasing, negative values passed to
+/// [`Counter::inc_by`] are ignored.
+///
 /// ```
 /// # use prometheus_client::metrics::counter::Counter;
 /// # use std::sync::atomic::AtomicU64;
@@ -36,11 +45,25 @@ use std::sync::Arc;
 /// counter.inc();
 /// let _value: f64 = counter.get();
 /// ```
+///
+/// See [`CounterF64`] for a shorthand.
 pub struct Counter<N = u64, A = AtomicU64> {
     value: Arc<A>,
     phantom: PhantomData<N>,
 }
 
+/// [`Counter`] of [`f64`] values stored in an [`AtomicU64`], e.g. to
+/// accumulate seconds via [`Counter::inc_by_duration`]. [`Counter`] itself
+/// defaults to [`u64`] values.
+///
+/// ```
+/// # use prometheus_client::metrics::counter::CounterF64;
+/// # use prometheus_client::metrics::family::Family;
+/// let family = Family::<Vec<(String, String)>, CounterF64>::default();
+/// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc_by(0.5);
+/// ```
+pub type CounterF64 = Counter<f64, AtomicU64>;
+
 impl<N, A> Clone for Counter<N, A> {
     fn clone(&self) -> Self {
         Self {
@@ -59,6 +82,26 @@ impl<N, A: Default> Default for Counter<N, A> {
     }
 }
 
+impl<N, A: Atomic<N> + Default> Counter<N, A> {
+    /// Create a new [`Counter`] starting at the given value, e.g. to restore a
+    /// count persisted across restarts. [`Counter::default`] starts at zero.
+    ///
+    /// Note: Seeding is intended for restoring persisted state only. A
+    /// [`Counter`] must never decrease, thus never seed it with a value lower
+    /// than one previously exposed for the same series.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// let counter: Counter = Counter::new(42);
+    /// assert_eq!(42, counter.get());
+    /// ```
+    pub fn new(initial: N) -> Self {
+        let counter = Self::default();
+        counter.inc_by(initial);
+        counter
+    }
+}
+
 impl<N, A: Atomic<N>> Counter<N, A> {
     /// Increase the [`Counter`] by 1, returning the previous value.
     pub fn inc(&self) -> N {
@@ -71,6 +114,17 @@ impl<N, A: Atomic<N>> Counter<N, A> {
     }
 
     /// Get the current value of the [`Counter`].
+    ///
+    /// The value is read atomically without locking, e.g. to assert on a
+    /// [`Counter`] in tests or to expose it through a custom
+    /// [`Collector`](crate::collector::Collector).
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// let counter: Counter = Counter::default();
+    /// counter.inc_by(2);
+    /// assert_eq!(2, counter.get());
+    /// ```
     pub fn get(&self) -> N {
         self.value.get()
     }
@@ -88,6 +142,152 @@ impl<N, A: Atomic<N>> Counter<N, A> {
     }
 }
 
+impl<N, A: Reset> Counter<N, A> {
+    /// Reset the [`Counter`] to zero.
+    ///
+    /// Note: This is intended for test harnesses only. An Open Metrics counter
+    /// is monotonically increasing, thus resetting it in production code
+    /// breaks the assumptions of consumers, e.g. rate calculations.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// let counter: Counter = Counter::default();
+    /// counter.inc();
+    /// counter.reset();
+    /// assert_eq!(0, counter.get());
+    /// ```
+    pub fn reset(&self) {
+        self.value.reset()
+    }
+}
+
+impl<A: Atomic<f64>> Counter<f64, A> {
+    /// Increase the [`Counter`] by the given [`Duration`] in seconds, the base
+    /// unit of time recommended by Open Metrics, returning the previous value,
+    /// e.g. to accumulate the total time spent.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use std::sync::atomic::AtomicU64;
+    /// # use std::time::Duration;
+    /// let counter = Counter::<f64, AtomicU64>::default();
+    /// counter.inc_by_duration(Duration::from_millis(1500));
+    /// assert_eq!(1.5, counter.get());
+    /// ```
+    pub fn inc_by_duration(&self, d: Duration) -> f64 {
+        self.inc_by(d.as_secs_f64())
+    }
+}
+
+impl<N, A: Default> Counter<N, A> {
+    /// Create a [`CounterWithCreated`] recording the current time as its
+    /// creation time.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// let counter = Counter::<u64>::with_created_now();
+    /// counter.inc();
+    /// let _created = counter.created();
+    /// ```
+    pub fn with_created_now() -> CounterWithCreated<N, A> {
+        CounterWithCreated::default()
+    }
+}
+
+/// Open Metrics [`Counter`] exposing the time it was created at, allowing
+/// consumers to detect counter resets.
+///
+/// The creation time is recorded once when the [`CounterWithCreated`] is
+/// constructed, e.g. via [`Counter::with_created_now`] or as a new member of a
+/// [`Family`](crate::metrics::family::Family), and is never changed
+/// afterwards. It is exposed as a `_created` sample in the text format.
+///
+/// ```
+/// # use prometheus_client::metrics::counter::CounterWithCreated;
+/// # use prometheus_client::metrics::family::Family;
+/// let family = Family::<Vec<(String, String)>, CounterWithCreated>::default();
+/// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+/// ```
+pub struct CounterWithCreated<N = u64, A = AtomicU64> {
+    counter: Counter<N, A>,
+    created: SystemTime,
+}
+
+impl<N, A> Clone for CounterWithCreated<N, A> {
+    fn clone(&self) -> Self {
+        Self {
+            counter: self.counter.clone(),
+            created: self.created,
+        }
+    }
+}
+
+impl<N, A: Default> Default for CounterWithCreated<N, A> {
+    fn default() -> Self {
+        Self {
+            counter: Counter::default(),
+            created: SystemClock.now(),
+        }
+    }
+}
+
+impl<N, A: Default> CounterWithCreated<N, A> {
+    /// Create a [`CounterWithCreated`] recording the current time of the
+    /// given [`Clock`] as its creation time.
+    ///
+    /// Use [`Family::new_with_constructor`] to do so for the members of a
+    /// [`Family`].
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::CounterWithCreated;
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use std::time::{SystemTime, UNIX_EPOCH};
+    /// fn clock() -> SystemTime {
+    ///     UNIX_EPOCH
+    /// }
+    ///
+    /// let family = Family::<Vec<(String, String)>, CounterWithCreated>::new_with_constructor(
+    ///     || CounterWithCreated::new_with_clock(&clock),
+    /// );
+    /// let created = family.get_or_create(&vec![]).created();
+    /// assert_eq!(UNIX_EPOCH, created);
+    /// ```
+    ///
+    /// [`Family`]: crate::metrics::family::Family
+    /// [`Family::new_with_constructor`]: crate::metrics::family::Family::new_with_constructor
+    pub fn new_with_clock<C: Clock + ?Sized>(clock: &C) -> Self {
+        Self {
+            counter: Counter::default(),
+            created: clock.now(),
+        }
+    }
+}
+
+impl<N, A: Atomic<N>> CounterWithCreated<N, A> {
+    /// Increase the [`CounterWithCreated`] by 1, returning the previous value.
+    pub fn inc(&self) -> N {
+        self.counter.inc()
+    }
+
+    /// Increase the [`CounterWithCreated`] by `v`, returning the previous
+    /// value.
+    pub fn inc_by(&self, v: N) -> N {
+        self.counter.inc_by(v)
+    }
+
+    /// Get the current value of the [`CounterWithCreated`].
+    pub fn get(&self) -> N {
+        self.counter.get()
+    }
+}
+
+impl<N, A> CounterWithCreated<N, A> {
+    /// Get the time the [`CounterWithCreated`] was created at.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+}
+
 pub trait Atomic<N> {
     fn inc(&self) -> N;
 
@@ -96,13 +296,41 @@ pub trait Atomic<N> {
     fn get(&self) -> N;
 }
 
+/// Atomic types whose value can be reset to zero, see [`Counter::reset`].
+///
+/// Separate from [`Atomic`] so that existing implementations of [`Atomic`]
+/// remain valid.
+pub trait Reset {
+    fn reset(&self);
+}
+
+impl Reset for AtomicU64 {
+    /// Resets `u64` as well as `f64` counters, as zero is represented by the
+    /// same bits.
+    fn reset(&self) {
+        self.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Reset for AtomicU32 {
+    fn reset(&self) {
+        self.store(0, Ordering::Relaxed);
+    }
+}
+
 impl Atomic<u64> for AtomicU64 {
     fn inc(&self) -> u64 {
         self.inc_by(1)
     }
 
     fn inc_by(&self, v: u64) -> u64 {
-        self.fetch_add(v, Ordering::Relaxed)
+        // Saturate instead of wrapping around, as a counter must never
+        // decrease, without ever storing a wrapped value.
+        match self.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+            Some(old.saturating_add(v))
+        }) {
+            Ok(old) | Err(old) => old,
+        }
     }
 
     fn get(&self) -> u64 {
@@ -116,7 +344,13 @@ impl Atomic<u32> for AtomicU32 {
     }
 
     fn inc_by(&self, v: u32) -> u32 {
-        self.fetch_add(v, Ordering::Relaxed)
+        // Saturate instead of wrapping around, as a counter must never
+        // decrease, without ever storing a wrapped value.
+        match self.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+            Some(old.saturating_add(v))
+        }) {
+            Ok(old) | Err(old) => old,
+        }
     }
 
     fn get(&self) -> u32 {
@@ -131,6 +365,11 @@ impl Atomic<f64> for AtomicU64 {
 
     fn inc_by(&self, v: f64) -> f64 {
         let mut old_u64 = self.load(Ordering::Relaxed);
+        // A counter must never decrease.
+        if v < 0.0 {
+            return f64::from_bits(old_u64);
+        }
+
         let mut old_f64;
         loop {
             old_f64 = f64::from_bits(old_u64);
@@ -153,10 +392,15 @@ impl<N, A> TypedMetric for Counter<N, A> {
     const TYPE: MetricType = MetricType::Counter;
 }
 
+impl<N, A> TypedMetric for CounterWithCreated<N, A> {
+    const TYPE: MetricType = MetricType::Counter;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use quickcheck::QuickCheck;
+    use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
     fn inc_and_get() {
@@ -165,6 +409,90 @@ mod tests {
         assert_eq!(1, counter.get());
     }
 
+    #[test]
+    fn inc_by_duration() {
+        let counter = Counter::<f64, AtomicU64>::default();
+        assert_eq!(0.0, counter.inc_by_duration(Duration::from_millis(250)));
+        counter.inc_by_duration(Duration::from_secs(2));
+        counter.inc_by_duration(Duration::from_nanos(500_000_000));
+        assert_eq!(2.75, counter.get());
+    }
+
+    #[test]
+    fn inc_by_saturates() {
+        let counter: Counter = Counter::default();
+        counter.inc_by(u64::MAX - 1);
+        assert_eq!(u64::MAX - 1, counter.inc_by(2));
+        assert_eq!(u64::MAX, counter.get());
+        assert_eq!(u64::MAX, counter.inc());
+        assert_eq!(u64::MAX, counter.get());
+
+        let counter = Counter::<u32, AtomicU32>::default();
+        counter.inc_by(u32::MAX);
+        counter.inc();
+        assert_eq!(u32::MAX, counter.get());
+    }
+
+    #[test]
+    fn saturated_counter_never_decreases() {
+        let counter: Counter = Counter::default();
+        counter.inc_by(u64::MAX);
+
+        let threads = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        assert_eq!(u64::MAX, counter.inc_by(2));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..10_000 {
+            assert_eq!(u64::MAX, counter.get());
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let counter: Counter = Counter::default();
+        counter.inc_by(42);
+        counter.reset();
+        assert_eq!(0, counter.get());
+
+        let counter = Counter::<f64, AtomicU64>::default();
+        counter.inc_by(1.5);
+        counter.reset();
+        assert_eq!(0.0, counter.get());
+    }
+
+    #[test]
+    fn created_is_not_changed_by_inc() {
+        let before = SystemTime::now();
+        let counter = Counter::<u64>::with_created_now();
+        let created = counter.created();
+        assert!(created >= before);
+
+        counter.inc();
+        counter.inc_by(10);
+        assert_eq!(11, counter.get());
+        assert_eq!(created, counter.created());
+        assert_eq!(created, counter.clone().created());
+    }
+
+    #[test]
+    fn created_with_clock() {
+        let clock = || UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+        let counter = CounterWithCreated::<u64>::new_with_clock(&clock);
+        assert_eq!(
+            UNIX_EPOCH + Duration::from_secs(1_500_000_000),
+            counter.created()
+        );
+    }
+
     #[test]
     fn f64_stored_in_atomic_u64() {
         fn prop(fs: Vec<f64>) {
@@ -173,7 +501,8 @@ mod tests {
                 // Map infinite, subnormal and NaN to 0.0.
                 .map(|f| if f.is_normal() { f } else { 0.0 })
                 .collect();
-            let sum = fs.iter().sum();
+            // Negative values are ignored.
+            let sum = fs.iter().filter(|f| **f >= 0.0).sum();
             let counter = Counter::<f64, AtomicU64>::default();
             for f in fs {
                 counter.inc_by(f);
@@ -183,4 +512,12 @@ mod tests {
 
         QuickCheck::new().tests(10).quickcheck(prop as fn(_))
     }
+
+    #[test]
+    fn f64_ignores_negative_values() {
+        let counter = Counter::<f64, AtomicU64>::default();
+        counter.inc_by(1.5);
+        assert_eq!(1.5, counter.inc_by(-1.0));
+        assert_eq!(1.5, counter.get());
+    }
 }
@@ -0,0 +1,139 @@
+//! Module implementing a [`Family`] of [`Counter`]s keyed by label values.
+//!
+//! See [`CounterVec`] for details.
+
+use super::counter::{Atomic, Counter};
+use super::family::{Family, MetricRef};
+use super::{MetricType, TypedMetric};
+use std::collections::hash_map::RandomState;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+/// [`Family`] of [`Counter`]s with a fixed list of label names, accessed by
+/// label values only.
+///
+/// Eases the migration from the `CounterVec` of the `prometheus` crate. Use a
+/// [`Family`] with a custom label set type instead to have the compiler check
+/// the label names and values.
+///
+/// ```
+/// # use prometheus_client::encoding::text::encode;
+/// # use prometheus_client::metrics::counter_vec::CounterVec;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = Registry::default();
+/// let requests = CounterVec::<u64>::new(&["method", "status"]);
+/// registry.register("requests", "Number of requests", requests.clone());
+///
+/// requests.with_label_values(&["GET", "200"]).inc();
+///
+/// let mut encoded = Vec::new();
+/// encode(&mut encoded, &registry).unwrap();
+/// assert!(String::from_utf8(encoded)
+///     .unwrap()
+///     .contains("requests_total{method=\"GET\",status=\"200\"} 1\n"));
+/// ```
+pub struct CounterVec<N = u64, A = AtomicU64> {
+    label_names: Arc<[String]>,
+    family: Family<Vec<(String, String)>, Counter<N, A>>,
+}
+
+impl<N, A> Clone for CounterVec<N, A> {
+    fn clone(&self) -> Self {
+        CounterVec {
+            label_names: self.label_names.clone(),
+            family: self.family.clone(),
+        }
+    }
+}
+
+impl<N, A: Atomic<N> + Default> CounterVec<N, A> {
+    /// Create a new [`CounterVec`] with the given label names, in the order
+    /// label values are passed to [`CounterVec::with_label_values`].
+    pub fn new(label_names: &[&str]) -> Self {
+        Self {
+            label_names: label_names.iter().map(|name| name.to_string()).collect(),
+            family: Family::default(),
+        }
+    }
+
+    /// Access the [`Counter`] with the given label values, creating it if it
+    /// does not yet exist. See [`Family::get_or_create`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of label values does not match the number of
+    /// label names passed to [`CounterVec::new`].
+    pub fn with_label_values(
+        &self,
+        label_values: &[&str],
+    ) -> MetricRef<'_, Vec<(String, String)>, Counter<N, A>, RandomState> {
+        assert_eq!(
+            self.label_names.len(),
+            label_values.len(),
+            "Expected {} label values for label names {:?}, got {}.",
+            self.label_names.len(),
+            self.label_names,
+            label_values.len()
+        );
+
+        let label_set: Vec<(String, String)> = self
+            .label_names
+            .iter()
+            .zip(label_values)
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect();
+        self.family.get_or_create(&label_set)
+    }
+}
+
+impl<N, A> CounterVec<N, A> {
+    /// The label names passed to [`CounterVec::new`].
+    pub fn label_names(&self) -> &[String] {
+        &self.label_names
+    }
+
+    /// The underlying [`Family`], e.g. to remove a label set.
+    pub fn family(&self) -> &Family<Vec<(String, String)>, Counter<N, A>> {
+        &self.family
+    }
+}
+
+impl<N, A> TypedMetric for CounterVec<N, A> {
+    const TYPE: MetricType = MetricType::Counter;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_label_values() {
+        let counters = CounterVec::<u64>::new(&["method", "status"]);
+        counters.with_label_values(&["GET", "200"]).inc();
+        counters.with_label_values(&["GET", "200"]).inc();
+        counters.with_label_values(&["PUT", "200"]).inc();
+
+        assert_eq!(2, counters.with_label_values(&["GET", "200"]).get());
+        assert_eq!(
+            1,
+            counters
+                .family()
+                .get(&vec![
+                    ("method".to_string(), "PUT".to_string()),
+                    ("status".to_string(), "200".to_string()),
+                ])
+                .unwrap()
+                .get()
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Expected 2 label values for label names [\"method\", \"status\"], got 1."
+    )]
+    fn with_label_values_arity_mismatch() {
+        let counters = CounterVec::<u64>::new(&["method", "status"]);
+        counters.with_label_values(&["GET"]);
+    }
+}
@@ -8,10 +8,12 @@ use owning_ref::OwningRef;
 use std::collections::HashMap;
 use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::SystemTime;
 
 pub struct Exemplar<S, V> {
     pub(crate) label_set: S,
     pub(crate) value: V,
+    pub(crate) timestamp: Option<SystemTime>,
 }
 
 /////////////////////////////////////////////////////////////////////////////////
@@ -20,6 +22,11 @@ pub struct Exemplar<S, V> {
 /// Open Metrics [`Counter`] with an [`Exemplar`] to both measure discrete
 /// events and track references to data outside of the metric set.
 ///
+/// Note: Open Metrics limits the combined length of the label names and values
+/// of an [`Exemplar`] to 128 UTF-8 characters. Exemplars exceeding the limit
+/// are not exposed. With the `tracing` feature enabled, each omitted exemplar
+/// emits a warning event.
+///
 /// ```
 /// # use prometheus_client::metrics::exemplar::CounterWithExemplar;
 /// let counter_with_exemplar = CounterWithExemplar::<Vec<(String, String)>>::default();
@@ -61,11 +68,38 @@ impl<S, N: Clone, A: counter::Atomic<N>> CounterWithExemplar<S, N, A> {
     /// Increase the [`CounterWithExemplar`] by `v`, updating the [`Exemplar`]
     /// if a label set is provided, returning the previous value.
     pub fn inc_by(&self, v: N, label_set: Option<S>) -> N {
+        self.inc_by_with_maybe_timestamp(v, label_set, None)
+    }
+
+    /// Like [`CounterWithExemplar::inc_by`], exposing the given timestamp with
+    /// the [`Exemplar`].
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::exemplar::CounterWithExemplar;
+    /// # use std::time::SystemTime;
+    /// let counter_with_exemplar = CounterWithExemplar::<Vec<(String, String)>>::default();
+    /// counter_with_exemplar.inc_by_with_timestamp(
+    ///     1,
+    ///     Some(vec![("trace_id".to_string(), "abc".to_string())]),
+    ///     SystemTime::now(),
+    /// );
+    /// ```
+    pub fn inc_by_with_timestamp(&self, v: N, label_set: Option<S>, timestamp: SystemTime) -> N {
+        self.inc_by_with_maybe_timestamp(v, label_set, Some(timestamp))
+    }
+
+    fn inc_by_with_maybe_timestamp(
+        &self,
+        v: N,
+        label_set: Option<S>,
+        timestamp: Option<SystemTime>,
+    ) -> N {
         let mut inner = self.inner.write().expect("Lock not to be poisoned.");
 
         inner.exemplar = label_set.map(|label_set| Exemplar {
             label_set,
             value: v.clone(),
+            timestamp,
         });
 
         inner.counter.inc_by(v)
@@ -73,7 +107,7 @@ impl<S, N: Clone, A: counter::Atomic<N>> CounterWithExemplar<S, N, A> {
 
     /// Get the current value of the [`CounterWithExemplar`] as well as its
     /// [`Exemplar`] if any.
-    pub fn get(&self) -> (N, RwLockGuardedCounterWithExemplar<S, N, A>) {
+    pub fn get(&self) -> (N, RwLockGuardedCounterWithExemplar<'_, S, N, A>) {
         let inner = self.inner.read().expect("Lock not to be poisoned.");
         let value = inner.counter.get();
         let exemplar = OwningRef::new(inner).map(|inner| &inner.exemplar);
@@ -88,7 +122,7 @@ impl<S, N: Clone, A: counter::Atomic<N>> CounterWithExemplar<S, N, A> {
     /// The caller of this function has to uphold the property of an Open
     /// Metrics counter namely that the value is monotonically increasing, i.e.
     /// either stays the same or increases.
-    pub fn inner(&self) -> OwningRef<RwLockReadGuard<CounterWithExemplarInner<S, N, A>>, A> {
+    pub fn inner(&self) -> OwningRef<RwLockReadGuard<'_, CounterWithExemplarInner<S, N, A>>, A> {
         OwningRef::new(self.inner.read().expect("Lock not to be poisoned."))
             .map(|inner| inner.counter.inner())
     }
@@ -103,6 +137,9 @@ type RwLockGuardedCounterWithExemplar<'a, S, N, A> =
 /// Open Metrics [`Histogram`] to both measure distributions of discrete events.
 /// and track references to data outside of the metric set.
 ///
+/// Note: See [`CounterWithExemplar`] on the length limit of an [`Exemplar`]'s
+/// label set.
+///
 /// ```
 /// # use prometheus_client::metrics::exemplar::HistogramWithExemplars;
 /// # use prometheus_client::metrics::histogram::exponential_buckets;
@@ -137,7 +174,27 @@ impl<S> HistogramWithExemplars<S> {
         }
     }
 
+    /// Observe the given value, recording an [`Exemplar`] for the bucket the
+    /// value falls into if a label set is provided.
+    ///
+    /// Each bucket keeps at most one [`Exemplar`], the most recent one, thus
+    /// bounding the memory used by exemplars by the number of buckets.
     pub fn observe(&self, v: f64, label_set: Option<S>) {
+        self.observe_with_maybe_timestamp(v, label_set, None)
+    }
+
+    /// Like [`HistogramWithExemplars::observe`], exposing the given timestamp
+    /// with the [`Exemplar`].
+    pub fn observe_with_timestamp(&self, v: f64, label_set: Option<S>, timestamp: SystemTime) {
+        self.observe_with_maybe_timestamp(v, label_set, Some(timestamp))
+    }
+
+    fn observe_with_maybe_timestamp(
+        &self,
+        v: f64,
+        label_set: Option<S>,
+        timestamp: Option<SystemTime>,
+    ) {
         let mut inner = self.inner.write().expect("Lock not to be poisoned.");
         let bucket = inner.histogram.observe_and_bucket(v);
         if let (Some(bucket), Some(label_set)) = (bucket, label_set) {
@@ -146,12 +203,13 @@ impl<S> HistogramWithExemplars<S> {
                 Exemplar {
                     label_set,
                     value: v,
+                    timestamp,
                 },
             );
         }
     }
 
-    pub(crate) fn inner(&self) -> RwLockReadGuard<HistogramWithExemplarsInner<S>> {
+    pub(crate) fn inner(&self) -> RwLockReadGuard<'_, HistogramWithExemplarsInner<S>> {
         self.inner.read().expect("Lock not to be poisoned.")
     }
 }
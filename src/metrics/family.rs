@@ -2,10 +2,15 @@
 //!
 //! See [`Family`] for details.
 
+use super::counter::Counter;
 use super::{MetricType, TypedMetric};
 use owning_ref::OwningRef;
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
 
 /// Representation of the OpenMetrics *MetricFamily* data type.
 ///
@@ -96,9 +101,64 @@ use std::sync::{Arc, RwLock, RwLockReadGuard};
 /// #                "# EOF\n";
 /// # assert_eq!(expected, String::from_utf8(buffer).unwrap());
 /// ```
-// TODO: Consider exposing hash algorithm.
-pub struct Family<S, M, C = fn() -> M> {
-    metrics: Arc<RwLock<HashMap<S, M>>>,
+///
+/// ### [`Family`] of histograms with shared buckets
+///
+/// Metric types without a meaningful [`Default`], like
+/// [`Histogram`](crate::metrics::histogram::Histogram), need a
+/// [`MetricConstructor`], creating the metric for each new label set. Use
+/// [`HistogramConstructor`](crate::metrics::histogram::HistogramConstructor)
+/// to give every label set the same buckets.
+///
+/// ```
+/// # use prometheus_client::metrics::family::Family;
+/// # use prometheus_client::metrics::histogram::{exponential_buckets, Histogram, HistogramConstructor};
+/// let family = Family::<Vec<(String, String)>, Histogram, _>::new_with_constructor(
+///     HistogramConstructor::new(exponential_buckets(0.005, 2.0, 10)),
+/// );
+///
+/// // Record a request latency of 42ms for route `/`.
+/// family.get_or_create(&vec![("route".to_owned(), "/".to_owned())]).observe(0.042);
+/// ```
+///
+/// # Sharding
+///
+/// Sharding is opt-in. By default all metrics of a [`Family`] are stored in a
+/// single map behind a single lock, as most families see little contention
+/// and a single shard skips hashing label sets to select a shard. Creating a
+/// new metric via [`Family::get_or_create`] takes the write lock, blocking all
+/// concurrent accesses to the [`Family`]. Under high concurrency with many
+/// label sets, one can spread the metrics across multiple shards, each behind
+/// its own lock, via [`Family::new_with_shards`] or
+/// [`Family::new_with_constructor_and_shards`]. The shard of a label set is
+/// determined by its hash.
+///
+/// ```
+/// # use prometheus_client::metrics::counter::{Atomic, Counter};
+/// # use prometheus_client::metrics::family::Family;
+/// #
+/// let family = Family::<Vec<(String, String)>, Counter>::new_with_shards(16);
+/// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+/// ```
+///
+/// # Bounding the number of series
+///
+/// A bug producing unbounded label values, e.g. a request ID used as a label,
+/// makes a [`Family`] grow without bound. [`Family::with_max_series`] caps the
+/// number of label sets. See [`Family::with_max_series`] for what happens
+/// once the limit is reached.
+///
+/// # Panics while holding a lock
+///
+/// A panic while a shard is write-locked, e.g. by a custom constructor passed
+/// to [`Family::new_with_constructor`], poisons the lock. A [`Family`] ignores
+/// the poisoning, as each metric is either fully inserted or not at all, so
+/// that a single panic does not break the [`Family`], nor encoding it, for
+/// the remainder of the process.
+pub struct Family<S, M, C = fn() -> M, H = RandomState> {
+    metrics: Arc<Vec<RwLock<HashMap<S, M, H>>>>,
+    /// Hashes label sets to select their shard, see [`Family::shard_index`].
+    hash_builder: H,
     /// Function that when called constructs a new metric.
     ///
     /// For most metric types this would simply be its [`Default`]
@@ -108,6 +168,45 @@ pub struct Family<S, M, C = fn() -> M> {
     /// specific buckets, a custom constructor is set via
     /// [`Family::new_with_constructor`].
     constructor: C,
+    /// Limit on the number of label sets, set via [`Family::with_max_series`].
+    limit: Option<Arc<SeriesLimit<S, M, H>>>,
+    /// Default labels merged into each label set, set via
+    /// [`Family::with_default_labels`].
+    defaults: Option<(Arc<S>, MergeFn<S>)>,
+    /// Sorts the labels of each label set, set via
+    /// [`Family::with_sorted_labels`].
+    sort: Option<fn(&S) -> S>,
+}
+
+/// Reference to a metric of a [`Family`], holding the read lock of its shard.
+pub(crate) type MetricRef<'a, S, M, H> = OwningRef<RwLockReadGuard<'a, HashMap<S, M, H>>, M>;
+
+/// Reference to a metric of a [`Family`], sharing the read lock of its shard
+/// with the other references returned by [`Family::get_or_create_many`].
+pub(crate) type SharedMetricRef<'a, S, M, H> =
+    OwningRef<Arc<RwLockReadGuard<'a, HashMap<S, M, H>>>, M>;
+
+struct SeriesLimit<S, M, H> {
+    max: usize,
+    series: AtomicUsize,
+    dropped: Counter,
+    /// Holds the single metric shared by all label sets refused due to the
+    /// limit, keyed by the label set returned by `sentinel`. Never exposed on
+    /// encoding.
+    fallback: RwLock<HashMap<S, M, H>>,
+    sentinel: fn() -> S,
+}
+
+/// Series reserved via [`Family::reserve_series`], released again when
+/// dropped, e.g. on a panic before the new metric is inserted.
+struct SeriesReservation<'a, S, M, H>(Option<&'a SeriesLimit<S, M, H>>);
+
+impl<'a, S, M, H> Drop for SeriesReservation<'a, S, M, H> {
+    fn drop(&mut self) {
+        if let Some(limit) = self.0 {
+            limit.series.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
 }
 
 /// A constructor for creating new metrics in a [`Family`] when calling
@@ -156,12 +255,25 @@ impl<M, F: Fn() -> M> MetricConstructor<M> for F {
     }
 }
 
+/// Number of shards used by [`Family::default`] and
+/// [`Family::new_with_constructor`], i.e. sharding is opt-in.
+const DEFAULT_SHARDS: usize = 1;
+
 impl<S: Clone + std::hash::Hash + Eq, M: Default> Default for Family<S, M> {
     fn default() -> Self {
-        Self {
-            metrics: Arc::new(RwLock::new(Default::default())),
-            constructor: M::default,
-        }
+        Self::new_with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl<S: Clone + std::hash::Hash + Eq, M: Default> Family<S, M> {
+    /// Create a metric family spreading its metrics across the given number of
+    /// shards. See [`Family`] for details on sharding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    pub fn new_with_shards(shards: usize) -> Self {
+        Self::new_with_constructor_and_shards(M::default, shards)
     }
 }
 
@@ -171,7 +283,7 @@ impl<S: Clone + std::hash::Hash + Eq, M, C> Family<S, M, C> {
     ///
     /// When calling [`Family::get_or_create`] a [`Family`] needs to be able to
     /// construct a new metric in case none exists for the given label set. In
-    /// most cases, e.g. for [`Counter`](crate::metrics::counter::Counter)
+    /// most cases, e.g. for [`Counter`]
     /// [`Family`] can just use the [`Default::default`] implementation for the
     /// metric type. For metric types such as
     /// [`Histogram`](crate::metrics::histogram::Histogram) one might want
@@ -188,14 +300,442 @@ impl<S: Clone + std::hash::Hash + Eq, M, C> Family<S, M, C> {
     /// });
     /// ```
     pub fn new_with_constructor(constructor: C) -> Self {
+        Self::new_with_constructor_and_shards(constructor, DEFAULT_SHARDS)
+    }
+
+    /// Create a metric family using a custom constructor, spreading its metrics
+    /// across the given number of shards.
+    ///
+    /// See [`Family::new_with_constructor`] for details on constructors and
+    /// [`Family`] for details on sharding.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+    /// Family::<Vec<(String, String)>, Histogram>::new_with_constructor_and_shards(
+    ///     || Histogram::new(exponential_buckets(1.0, 2.0, 10)),
+    ///     16,
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    pub fn new_with_constructor_and_shards(constructor: C, shards: usize) -> Self {
+        Self::new_with_hasher(constructor, shards, RandomState::new())
+    }
+}
+
+impl<S: Clone + std::hash::Hash + Eq, M, C, H: BuildHasher + Clone> Family<S, M, C, H> {
+    /// Create a metric family using a custom constructor, spreading its metrics
+    /// across the given number of shards, each hashing its label sets with the
+    /// given [`BuildHasher`].
+    ///
+    /// By default a [`Family`] uses the [`RandomState`] of the standard
+    /// library, i.e. SipHash, which is resistant to HashDoS attacks but slow
+    /// for the small label sets common for metrics. A faster hasher, e.g. from
+    /// the `ahash` or `fxhash` crates, speeds up [`Family::get_or_create`]. Use
+    /// a non-randomized hasher only when label values can not be controlled by
+    /// an attacker.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use std::collections::hash_map::RandomState;
+    /// let family = Family::<Vec<(String, String)>, Counter, _, _>::new_with_hasher(
+    ///     Counter::default,
+    ///     1,
+    ///     RandomState::new(),
+    /// );
+    /// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    pub fn new_with_hasher(constructor: C, shards: usize, hash_builder: H) -> Self {
+        assert!(shards > 0, "Family requires at least one shard.");
+
         Self {
-            metrics: Arc::new(RwLock::new(Default::default())),
+            metrics: Arc::new(
+                (0..shards)
+                    .map(|_| RwLock::new(HashMap::with_hasher(hash_builder.clone())))
+                    .collect(),
+            ),
+            hash_builder,
             constructor,
+            limit: None,
+            defaults: None,
+            sort: None,
+        }
+    }
+
+    /// Limit the [`Family`] to at most `max` label sets.
+    ///
+    /// Once the limit is reached, no new metrics are created.
+    /// [`Family::get_or_create`] returns a single fallback metric, shared by
+    /// all label sets refused due to the limit, while
+    /// [`Family::try_get_or_create`] returns [`None`]. Either way the counter
+    /// returned by [`Family::dropped_series`] is incremented, which can itself
+    /// be registered in order to alert on the limit being hit.
+    ///
+    /// The fallback metric is kept apart from the other metrics under the
+    /// [`Default`] label set. It is not exposed on encoding, neither under
+    /// that nor under any other label set.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// # let mut registry = <Registry>::default();
+    /// let family = Family::<Vec<(String, String)>, Counter>::default().with_max_series(1);
+    /// registry.register("requests", "Number of requests", Box::new(family.clone()));
+    /// registry.register(
+    ///     "requests_dropped_series",
+    ///     "Number of requests series dropped due to the series limit",
+    ///     Box::new(family.dropped_series()),
+    /// );
+    ///
+    /// family.get_or_create(&vec![("path".to_owned(), "/a".to_owned())]).inc();
+    /// assert!(family.try_get_or_create(&vec![("path".to_owned(), "/b".to_owned())]).is_none());
+    /// assert_eq!(1, family.dropped_series().get());
+    /// ```
+    pub fn with_max_series(mut self, max: usize) -> Self
+    where
+        S: Default,
+    {
+        let series = self
+            .metrics
+            .iter()
+            .map(|shard| shard.read().unwrap_or_else(PoisonError::into_inner).len())
+            .sum();
+        self.limit = Some(Arc::new(SeriesLimit {
+            max,
+            series: AtomicUsize::new(series),
+            dropped: Counter::default(),
+            fallback: RwLock::new(HashMap::with_hasher(self.hash_builder.clone())),
+            sentinel: S::default,
+        }));
+        self
+    }
+
+    /// Reserve capacity for at least `capacity` label sets, spread evenly
+    /// across the shards, avoiding repeated reallocations while a [`Family`]
+    /// of known size is populated, e.g. during startup.
+    ///
+    /// The capacity is only a hint. Label sets beyond it are stored as usual,
+    /// and label sets are not necessarily spread evenly across shards.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+    /// #
+    /// let family = Family::<Vec<(String, String)>, Counter>::default().with_capacity(1_000);
+    ///
+    /// let histograms = Family::<Vec<(String, String)>, Histogram>::new_with_constructor(|| {
+    ///     Histogram::new(exponential_buckets(1.0, 2.0, 10))
+    /// })
+    /// .with_capacity(1_000);
+    /// ```
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        let per_shard = capacity.div_ceil(self.metrics.len());
+        for shard in self.metrics.iter() {
+            shard
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .reserve(per_shard);
+        }
+        self
+    }
+
+    /// Fill in the labels missing from the label sets passed to
+    /// [`Family::get_or_create`], [`Family::try_get_or_create`],
+    /// [`Family::get`] and [`Family::remove`] from the given default label
+    /// set, see [`MergeLabelSet`]. Labels provided explicitly override the
+    /// defaults.
+    ///
+    /// The merged label set is both the key of the metric and the label set
+    /// exposed on encoding. The default labels come first, in the order of
+    /// the defaults, followed by the remaining labels, so that label sets
+    /// only differing in the order of default labels refer to the same
+    /// metric.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(&str, &str)>, Counter>::default()
+    ///     .with_default_labels(vec![("env", "prod"), ("method", "GET")]);
+    ///
+    /// family.get_or_create(&vec![("method", "PUT")]).inc();
+    /// family.get_or_create(&vec![]).inc();
+    ///
+    /// assert_eq!(1, family.get(&vec![("env", "prod"), ("method", "PUT")]).unwrap().get());
+    /// assert_eq!(1, family.get(&vec![("method", "PUT"), ("env", "prod")]).unwrap().get());
+    /// assert_eq!(1, family.get(&vec![("env", "prod"), ("method", "GET")]).unwrap().get());
+    /// ```
+    pub fn with_default_labels(mut self, defaults: S) -> Self
+    where
+        S: MergeLabelSet,
+    {
+        self.defaults = Some((Arc::new(defaults), S::merge));
+        self
+    }
+
+    /// Sort the labels of the label sets passed to [`Family::get_or_create`],
+    /// [`Family::try_get_or_create`], [`Family::get`] and [`Family::remove`]
+    /// by name, see [`SortLabelSet`], so that label sets only differing in the
+    /// order of their labels refer to the same metric.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(&str, &str)>, Counter>::default().with_sorted_labels();
+    ///
+    /// family.get_or_create(&vec![("method", "GET"), ("status", "200")]).inc();
+    /// family.get_or_create(&vec![("status", "200"), ("method", "GET")]).inc();
+    ///
+    /// assert_eq!(2, family.get(&vec![("method", "GET"), ("status", "200")]).unwrap().get());
+    /// ```
+    pub fn with_sorted_labels(mut self) -> Self
+    where
+        S: SortLabelSet,
+    {
+        self.sort = Some(S::sorted);
+        self
+    }
+
+    /// The given label set merged with the default labels and sorted, if
+    /// configured via [`Family::with_default_labels`] and
+    /// [`Family::with_sorted_labels`] respectively.
+    fn normalize<'a>(&self, label_set: &'a S) -> Cow<'a, S> {
+        let label_set = match &self.defaults {
+            Some((defaults, merge)) => match merge(label_set, defaults) {
+                Some(merged) => Cow::Owned(merged),
+                None => Cow::Borrowed(label_set),
+            },
+            None => Cow::Borrowed(label_set),
+        };
+
+        match self.sort {
+            Some(sort) => Cow::Owned(sort(&label_set)),
+            None => label_set,
+        }
+    }
+
+    /// [`Counter`] of the number of times a new label set was refused due to
+    /// the limit set via [`Family::with_max_series`].
+    ///
+    /// Stays at zero if no limit is set.
+    pub fn dropped_series(&self) -> Counter {
+        self.limit
+            .as_ref()
+            .map(|limit| limit.dropped.clone())
+            .unwrap_or_default()
+    }
+
+    /// The shard holding the metric of the given label set.
+    fn shard(&self, label_set: &S) -> &RwLock<HashMap<S, M, H>> {
+        &self.metrics[self.shard_index(label_set)]
+    }
+
+    /// The index of the shard holding the metric of the given label set.
+    fn shard_index(&self, label_set: &S) -> usize {
+        if self.metrics.len() == 1 {
+            return 0;
+        }
+
+        // The shards hash with the same hasher. Select the shard by the middle
+        // bits of the hash, as the hash map of each shard uses the lowest bits to
+        // select a bucket and the highest bits to tell entries apart.
+        ((self.hash_builder.hash_one(label_set) >> 24) as u32 as usize) % self.metrics.len()
+    }
+
+    /// Account for a new label set, returning `false` if the limit set via
+    /// [`Family::with_max_series`] is reached.
+    fn reserve_series(&self) -> bool {
+        if let Some(limit) = &self.limit {
+            if limit.series.fetch_add(1, Ordering::Relaxed) >= limit.max {
+                limit.series.fetch_sub(1, Ordering::Relaxed);
+                limit.dropped.inc();
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Access a metric with the given label set, returning [`None`] if it does
+    /// not yet exist.
+    ///
+    /// In contrast to [`Family::get_or_create`] this method never creates a new
+    /// metric and thus only ever takes the read lock. The lock is held for as
+    /// long as the returned reference is alive.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(String, String)>, Counter>::default();
+    /// let labels = vec![("method".to_owned(), "GET".to_owned())];
+    ///
+    /// assert!(family.get(&labels).is_none());
+    ///
+    /// family.get_or_create(&labels).inc();
+    /// assert_eq!(1, family.get(&labels).unwrap().get());
+    /// ```
+    pub fn get(&self, label_set: &S) -> Option<MetricRef<'_, S, M, H>> {
+        let label_set = &*self.normalize(label_set);
+        let read_guard = self
+            .shard(label_set)
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        OwningRef::new(read_guard)
+            .try_map(|metrics| metrics.get(label_set).ok_or(()))
+            .ok()
+    }
+
+    /// Remove a label set from the metric family, returning whether a metric
+    /// was removed.
+    ///
+    /// Removing a label set is the way to mark a series stale, e.g. when
+    /// mirroring an upstream series that disappeared: Prometheus records a
+    /// staleness marker for every series present in the previous scrape but
+    /// absent from the current one. Staleness markers can not be exposed
+    /// directly, as a `NaN` value is stored as a regular `NaN` sample.
+    ///
+    /// Note: A label set removed while being concurrently recreated via
+    /// [`Family::get_or_create`] is either removed or recreated afterwards,
+    /// depending on which call takes the lock last. Values recorded on a
+    /// metric after it has been removed are lost.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(String, String)>, Counter>::default();
+    ///
+    /// // Will create the metric with label `method="GET"` on first call and
+    /// // return a reference.
+    /// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+    ///
+    /// // Will remove the metric with label `method="GET"` from the family.
+    /// assert!(family.remove(&vec![("method".to_owned(), "GET".to_owned())]));
+    /// ```
+    pub fn remove(&self, label_set: &S) -> bool {
+        let label_set = &*self.normalize(label_set);
+        let removed = self
+            .shard(label_set)
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(label_set)
+            .is_some();
+
+        if let (true, Some(limit)) = (removed, &self.limit) {
+            limit.series.fetch_sub(1, Ordering::Relaxed);
+        }
+
+        removed
+    }
+
+    /// Remove all label sets from the metric family.
+    ///
+    /// See [`Family::remove`] for the caveats of removing label sets.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(String, String)>, Counter>::default();
+    ///
+    /// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+    /// family.get_or_create(&vec![("method".to_owned(), "PUT".to_owned())]).inc();
+    ///
+    /// // Will remove all metrics from the family.
+    /// family.clear();
+    /// ```
+    pub fn clear(&self) {
+        for shard in self.metrics.iter() {
+            let mut shard = shard.write().unwrap_or_else(PoisonError::into_inner);
+            if let Some(limit) = &self.limit {
+                limit.series.fetch_sub(shard.len(), Ordering::Relaxed);
+            }
+            shard.clear()
+        }
+    }
+
+    /// The number of label sets, i.e. series, currently held by the
+    /// [`Family`], e.g. to expose the cardinality of each [`Family`] via a
+    /// [`Collector`](crate::collector::Collector).
+    ///
+    /// The count is a point-in-time snapshot. Each shard is read-locked in
+    /// turn, thus label sets concurrently created or removed might or might
+    /// not be counted. The fallback metric of a [`Family`] with a limit, see
+    /// [`Family::with_max_series`], is not counted.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(String, String)>, Counter>::default();
+    /// assert!(family.is_empty());
+    ///
+    /// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+    /// family.get_or_create(&vec![("method".to_owned(), "PUT".to_owned())]).inc();
+    /// assert_eq!(2, family.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.metrics
+            .iter()
+            .map(|shard| shard.read().unwrap_or_else(PoisonError::into_inner).len())
+            .sum()
+    }
+
+    /// Whether the [`Family`] currently holds no label sets, see
+    /// [`Family::len`].
+    pub fn is_empty(&self) -> bool {
+        self.metrics.iter().all(|shard| {
+            shard
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .is_empty()
+        })
+    }
+
+    /// Call `f` with each label set and its metric.
+    ///
+    /// Label sets are visited in no particular order. Each shard is read-locked
+    /// while its metrics are visited, thus `f` must not create or remove
+    /// metrics of the same [`Family`], e.g. via [`Family::get_or_create`] with
+    /// a new label set or [`Family::remove`], as doing so deadlocks.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(String, String)>, Counter>::default();
+    /// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
+    /// family.get_or_create(&vec![("method".to_owned(), "PUT".to_owned())]).inc_by(2);
+    ///
+    /// let mut total = 0;
+    /// family.for_each(|_labels, counter| total += counter.get());
+    /// assert_eq!(3, total);
+    /// ```
+    pub fn for_each<F: FnMut(&S, &M)>(&self, mut f: F) {
+        for shard in self.metrics.iter() {
+            for (label_set, metric) in shard.read().unwrap_or_else(PoisonError::into_inner).iter() {
+                f(label_set, metric);
+            }
         }
     }
 }
 
-impl<S: Clone + std::hash::Hash + Eq, M, C: MetricConstructor<M>> Family<S, M, C> {
+impl<S: Clone + std::hash::Hash + Eq, M, C: MetricConstructor<M>, H: BuildHasher + Clone>
+    Family<S, M, C, H>
+{
     /// Access a metric with the given label set, creating it if one does not
     /// yet exist.
     ///
@@ -213,42 +753,497 @@ impl<S: Clone + std::hash::Hash + Eq, M, C: MetricConstructor<M>> Family<S, M, C
     /// // calls.
     /// family.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]).inc();
     /// ```
-    pub fn get_or_create(&self, label_set: &S) -> OwningRef<RwLockReadGuard<HashMap<S, M>>, M> {
-        let read_guard = self.metrics.read().expect("Lock not to be poisoned.");
-        if let Ok(metric) =
-            OwningRef::new(read_guard).try_map(|metrics| metrics.get(label_set).ok_or(()))
-        {
+    ///
+    /// The metric of a label set is constructed exactly once, even when
+    /// multiple threads concurrently access the same new label set.
+    ///
+    /// Once the limit set via [`Family::with_max_series`] is reached, a shared
+    /// fallback metric is returned for new label sets.
+    pub fn get_or_create(&self, label_set: &S) -> MetricRef<'_, S, M, H> {
+        let label_set = &*self.normalize(label_set);
+        if let Some(metric) = self.try_get_or_create_normalized(label_set) {
             return metric;
         }
 
-        let mut write_guard = self.metrics.write().unwrap();
-        write_guard.insert(label_set.clone(), self.constructor.new_metric());
-
-        drop(write_guard);
-
-        let read_guard = self.metrics.read().unwrap();
-        OwningRef::new(read_guard).map(|metrics| {
+        let fallback = self
+            .fallback()
+            .expect("Only to be refused a new metric with a limit set.");
+        OwningRef::new(fallback.read().unwrap_or_else(PoisonError::into_inner)).map(|metrics| {
             metrics
-                .get(label_set)
-                .expect("Metric to exist after creating it.")
+                .values()
+                .next()
+                .expect("Fallback metric to exist after creating it.")
         })
     }
 
-    pub(crate) fn read(&self) -> RwLockReadGuard<HashMap<S, M>> {
-        self.metrics.read().unwrap()
+    /// Access the metrics with the given label sets, creating those that do
+    /// not yet exist, in the same order as the given label sets.
+    ///
+    /// In contrast to calling [`Family::get_or_create`] for each label set,
+    /// the lock of each shard is only acquired once, taking the write lock
+    /// only if one of its label sets is new. The read lock of the shard is
+    /// then shared by the references to its metrics and held until the last
+    /// of them is dropped. In the meantime, new label sets of the same shard
+    /// can not be created, neither by other threads nor by the calling thread.
+    /// Drop the returned references before creating further metrics.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic, Counter};
+    /// # use prometheus_client::metrics::family::Family;
+    /// #
+    /// let family = Family::<Vec<(&str, &str)>, Counter>::default();
+    ///
+    /// for counter in family.get_or_create_many(&[vec![("method", "GET")], vec![]]) {
+    ///     counter.inc();
+    /// }
+    ///
+    /// assert_eq!(1, family.get(&vec![("method", "GET")]).unwrap().get());
+    /// assert_eq!(1, family.get(&vec![]).unwrap().get());
+    /// ```
+    ///
+    /// Once the limit set via [`Family::with_max_series`] is reached, the
+    /// shared fallback metric is returned for new label sets.
+    pub fn get_or_create_many(&self, label_sets: &[S]) -> Vec<SharedMetricRef<'_, S, M, H>> {
+        let label_sets: Vec<Cow<'_, S>> = label_sets
+            .iter()
+            .map(|label_set| self.normalize(label_set))
+            .collect();
+        let shard_indices: Vec<usize> = label_sets
+            .iter()
+            .map(|label_set| self.shard_index(label_set))
+            .collect();
+
+        // Create the fallback metric upfront, as its write lock must not be
+        // acquired while holding the read locks of the shards below.
+        let fallback = if label_sets.is_empty() {
+            None
+        } else {
+            self.fallback()
+        };
+
+        // Lock the shards in ascending order, like `Family::read` does, in
+        // order not to deadlock with concurrent calls.
+        let mut guards: Vec<Option<Arc<_>>> = vec![None; self.metrics.len()];
+        let mut refused = vec![false; label_sets.len()];
+        for (index, shard) in self.metrics.iter().enumerate() {
+            let members: Vec<(usize, &S)> = shard_indices
+                .iter()
+                .zip(label_sets.iter())
+                .enumerate()
+                .filter(|(_, (shard_index, _))| **shard_index == index)
+                .map(|(i, (_, label_set))| (i, label_set.as_ref()))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+
+            // A concurrent `Family::remove` or `Family::clear` might delete
+            // the new metrics in between releasing the write and acquiring the
+            // read lock. Create them again if so.
+            loop {
+                let read_guard = shard.read().unwrap_or_else(PoisonError::into_inner);
+                if members
+                    .iter()
+                    .all(|(i, label_set)| refused[*i] || read_guard.contains_key(*label_set))
+                {
+                    guards[index] = Some(Arc::new(read_guard));
+                    break;
+                }
+                drop(read_guard);
+
+                let mut write_guard = shard.write().unwrap_or_else(PoisonError::into_inner);
+                for (i, label_set) in members.iter() {
+                    if refused[*i] || write_guard.contains_key(*label_set) {
+                        continue;
+                    }
+                    if !self.insert_new(&mut write_guard, label_set) {
+                        refused[*i] = true;
+                    }
+                }
+            }
+        }
+
+        let fallback_guard = if refused.contains(&true) {
+            fallback
+                .map(|fallback| Arc::new(fallback.read().unwrap_or_else(PoisonError::into_inner)))
+        } else {
+            None
+        };
+
+        label_sets
+            .iter()
+            .zip(shard_indices)
+            .zip(refused)
+            .map(|((label_set, index), refused)| {
+                if refused {
+                    let guard = fallback_guard
+                        .clone()
+                        .expect("Only to be refused a new metric with a limit set.");
+                    OwningRef::new(guard).map(|metrics| {
+                        metrics
+                            .values()
+                            .next()
+                            .expect("Fallback metric to exist after creating it.")
+                    })
+                } else {
+                    let guard = guards[index]
+                        .clone()
+                        .expect("Shard of each label set to be locked.");
+                    OwningRef::new(guard).map(|metrics| {
+                        metrics
+                            .get(label_set.as_ref())
+                            .expect("Metric to exist while holding the read lock.")
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// The lock holding the fallback metric, creating the latter if it does
+    /// not yet exist, or [`None`] if no limit is set via
+    /// [`Family::with_max_series`].
+    fn fallback(&self) -> Option<&RwLock<HashMap<S, M, H>>> {
+        let limit = self.limit.as_ref()?;
+        if limit
+            .fallback
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+        {
+            limit
+                .fallback
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry((limit.sentinel)())
+                .or_insert_with(|| self.constructor.new_metric());
+        }
+
+        Some(&limit.fallback)
+    }
+
+    /// Access a metric with the given label set, creating it if one does not
+    /// yet exist, unless the limit set via [`Family::with_max_series`] is
+    /// reached, in which case [`None`] is returned.
+    pub fn try_get_or_create(&self, label_set: &S) -> Option<MetricRef<'_, S, M, H>> {
+        self.try_get_or_create_normalized(&self.normalize(label_set))
+    }
+
+    /// [`Family::try_get_or_create`] with the label set already normalized,
+    /// see [`Family::normalize`].
+    fn try_get_or_create_normalized(&self, label_set: &S) -> Option<MetricRef<'_, S, M, H>> {
+        let shard = self.shard(label_set);
+        // A concurrent `Family::remove` or `Family::clear` might delete the
+        // new metric in between releasing the write and acquiring the read
+        // lock. Create it again if so.
+        loop {
+            let read_guard = shard.read().unwrap_or_else(PoisonError::into_inner);
+            if let Ok(metric) =
+                OwningRef::new(read_guard).try_map(|metrics| metrics.get(label_set).ok_or(()))
+            {
+                return Some(metric);
+            }
+
+            let mut write_guard = shard.write().unwrap_or_else(PoisonError::into_inner);
+            // Another thread might have created the metric in between releasing
+            // the read and acquiring the write lock. Only construct it if not.
+            if !write_guard.contains_key(label_set) && !self.insert_new(&mut write_guard, label_set)
+            {
+                return None;
+            }
+        }
+    }
+
+    /// Insert a new metric with the given label set, returning `false` if the
+    /// limit set via [`Family::with_max_series`] is reached.
+    fn insert_new(&self, metrics: &mut HashMap<S, M, H>, label_set: &S) -> bool {
+        if !self.reserve_series() {
+            return false;
+        }
+
+        // Release the reserved series again should the constructor panic.
+        let reservation = SeriesReservation(self.limit.as_deref());
+        metrics.insert(label_set.clone(), self.constructor.new_metric());
+        std::mem::forget(reservation);
+
+        true
+    }
+
+    /// Read-lock all shards, providing a consistent view of all metrics for as
+    /// long as the returned guards are alive.
+    pub(crate) fn read(&self) -> Vec<RwLockReadGuard<'_, HashMap<S, M, H>>> {
+        self.metrics
+            .iter()
+            .map(|shard| shard.read().unwrap_or_else(PoisonError::into_inner))
+            .collect()
     }
 }
 
-impl<S, M, C: Clone> Clone for Family<S, M, C> {
+impl<
+        S: Clone + std::hash::Hash + Eq,
+        M: Aggregate,
+        C: MetricConstructor<M>,
+        H: BuildHasher + Clone,
+    > Family<S, M, C, H>
+{
+    /// The total count and sum of the observations of all metrics of the
+    /// [`Family`], e.g. for a quick sanity check without scraping.
+    ///
+    /// All shards are read-locked while folding, providing a consistent view.
+    /// Observations of the fallback metric used once the limit set via
+    /// [`Family::with_max_series`] is reached are not included, in line with
+    /// the fallback metric not being exposed on encoding.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+    /// #
+    /// let family = Family::<Vec<(&str, &str)>, Histogram>::new_with_constructor(|| {
+    ///     Histogram::new(exponential_buckets(1.0, 2.0, 10))
+    /// });
+    /// family.get_or_create(&vec![("method", "GET")]).observe(1.0);
+    /// family.get_or_create(&vec![("method", "PUT")]).observe(2.5);
+    ///
+    /// assert_eq!((2, 3.5), family.aggregate());
+    /// ```
+    pub fn aggregate(&self) -> (u64, f64) {
+        self.read()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .fold((0, 0.0), |(count, sum), metric| {
+                (count + metric.count(), sum + metric.sum())
+            })
+    }
+}
+
+/// A metric aggregating observations into a count and a sum, e.g. a
+/// [`Histogram`](crate::metrics::histogram::Histogram). See
+/// [`Family::aggregate`].
+pub trait Aggregate {
+    /// The number of observations.
+    fn count(&self) -> u64;
+
+    /// The sum of all observations.
+    fn sum(&self) -> f64;
+}
+
+/// See [`MergeLabelSet::merge`].
+type MergeFn<S> = fn(&S, &S) -> Option<S>;
+
+/// A label set that can be completed with default labels, see
+/// [`Family::with_default_labels`].
+pub trait MergeLabelSet: Sized {
+    /// Merge the given defaults into the label set, keeping the labels of the
+    /// label set where both define a label.
+    ///
+    /// The result has to be the same for label sets only differing in the
+    /// order of the labels also defined by the defaults. Returns `None` if the
+    /// label set is merged already, avoiding a copy.
+    fn merge(&self, defaults: &Self) -> Option<Self>;
+}
+
+/// The labels also defined by the defaults come first, in the order of the
+/// defaults, followed by the remaining labels in their given order.
+impl<K: PartialEq + Clone, V: Clone> MergeLabelSet for Vec<(K, V)> {
+    fn merge(&self, defaults: &Self) -> Option<Self> {
+        let merged = self.len() >= defaults.len()
+            && self.iter().zip(defaults).all(|((k, _), (d, _))| k == d)
+            && !self[defaults.len()..]
+                .iter()
+                .any(|(k, _)| defaults.iter().any(|(d, _)| k == d));
+        if merged {
+            return None;
+        }
+
+        let mut merged: Vec<(K, V)> = defaults
+            .iter()
+            .map(|(key, value)| {
+                self.iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| (key.clone(), v.clone()))
+                    .unwrap_or_else(|| (key.clone(), value.clone()))
+            })
+            .collect();
+        merged.extend(
+            self.iter()
+                .filter(|(k, _)| !defaults.iter().any(|(d, _)| k == d))
+                .cloned(),
+        );
+        Some(merged)
+    }
+}
+
+/// A label set whose labels can be sorted by name, see
+/// [`Family::with_sorted_labels`].
+pub trait SortLabelSet {
+    /// The label set with its labels sorted by name.
+    fn sorted(&self) -> Self;
+}
+
+/// Labels with the same name keep their relative order.
+impl<K: Ord + Clone, V: Clone> SortLabelSet for Vec<(K, V)> {
+    fn sorted(&self) -> Self {
+        let mut sorted = self.clone();
+        sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+        sorted
+    }
+}
+
+/// Allow-list of label values, mapping any other value to a sentinel in order
+/// to bound the cardinality of a [`Family`].
+///
+/// Use a [`LabelAllowList`] when label values stem from untrusted input, e.g. a
+/// user supplied status string. Values are constrained when constructing the
+/// label set, thus values outside of the allow-list never create a new metric.
+///
+/// ```
+/// # use prometheus_client::metrics::counter::{Atomic, Counter};
+/// # use prometheus_client::metrics::family::{ConstrainedLabel, Family, LabelAllowList};
+/// #
+/// let status = LabelAllowList::new(["200", "404", "500"]);
+/// let family = Family::<Vec<(String, ConstrainedLabel)>, Counter>::default();
+///
+/// family.get_or_create(&vec![("status".to_owned(), status.constrain("404"))]).inc();
+/// family.get_or_create(&vec![("status".to_owned(), status.constrain("418"))]).inc();
+/// family.get_or_create(&vec![("status".to_owned(), status.constrain("999"))]).inc();
+///
+/// assert_eq!(
+///     2,
+///     family.get(&vec![("status".to_owned(), status.constrain("other"))]).unwrap().get(),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct LabelAllowList {
+    allowed: HashSet<String>,
+    sentinel: String,
+}
+
+impl LabelAllowList {
+    /// Create an allow-list mapping any value not in `allowed` to `"other"`.
+    pub fn new<I, V>(allowed: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<String>,
+    {
+        Self::new_with_sentinel(allowed, "other")
+    }
+
+    /// Create an allow-list mapping any value not in `allowed` to the given
+    /// sentinel.
+    pub fn new_with_sentinel<I, V, S>(allowed: I, sentinel: S) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<String>,
+        S: Into<String>,
+    {
+        Self {
+            allowed: allowed.into_iter().map(Into::into).collect(),
+            sentinel: sentinel.into(),
+        }
+    }
+
+    /// Constrain the given value to the allow-list, returning the value itself
+    /// if allowed and the sentinel otherwise.
+    pub fn constrain(&self, value: &str) -> ConstrainedLabel {
+        if self.allowed.contains(value) {
+            ConstrainedLabel(value.to_owned())
+        } else {
+            ConstrainedLabel(self.sentinel.clone())
+        }
+    }
+}
+
+/// A label value constrained by a [`LabelAllowList`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ConstrainedLabel(String);
+
+impl ConstrainedLabel {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// String interner deduplicating label names and values across the label
+/// sets of a [`Family`], e.g. to reduce the memory footprint of a [`Family`]
+/// with many series sharing a handful of label names.
+///
+/// Each distinct string is stored once. Label sets built from the returned
+/// [`Arc<str>`]s encode the same as label sets built from [`String`]s.
+///
+/// Note: Interned strings are never released, thus only intern strings of a
+/// bounded set, e.g. in combination with a [`LabelAllowList`].
+///
+/// ```
+/// # use prometheus_client::metrics::counter::{Atomic, Counter};
+/// # use prometheus_client::metrics::family::{Family, Interner};
+/// # use std::sync::Arc;
+/// #
+/// let interner = Interner::default();
+/// let family = Family::<Vec<(Arc<str>, Arc<str>)>, Counter>::default();
+///
+/// for path in ["/", "/metrics", "/"] {
+///     family
+///         .get_or_create(&vec![(interner.intern("path"), interner.intern(path))])
+///         .inc();
+/// }
+///
+/// assert_eq!(3, interner.len());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    strings: Arc<RwLock<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    /// Get the interned copy of the given string, interning it if not yet
+    /// interned.
+    pub fn intern(&self, s: &str) -> Arc<str> {
+        if let Some(interned) = self
+            .strings
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(s)
+        {
+            return interned.clone();
+        }
+
+        let mut strings = self.strings.write().unwrap_or_else(PoisonError::into_inner);
+        match strings.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(s);
+                strings.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// The number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S, M, C: Clone, H: Clone> Clone for Family<S, M, C, H> {
     fn clone(&self) -> Self {
         Family {
             metrics: self.metrics.clone(),
+            hash_builder: self.hash_builder.clone(),
             constructor: self.constructor.clone(),
+            limit: self.limit.clone(),
+            defaults: self.defaults.clone(),
+            sort: self.sort,
         }
     }
 }
 
-impl<S, M: TypedMetric, C> TypedMetric for Family<S, M, C> {
+impl<S, M: TypedMetric, C, H> TypedMetric for Family<S, M, C, H> {
     const TYPE: MetricType = <M as TypedMetric>::TYPE;
 }
 
@@ -256,7 +1251,108 @@ impl<S, M: TypedMetric, C> TypedMetric for Family<S, M, C> {
 mod tests {
     use super::*;
     use crate::metrics::counter::Counter;
-    use crate::metrics::histogram::{exponential_buckets, Histogram};
+    use crate::metrics::histogram::{exponential_buckets, Histogram, HistogramConstructor};
+
+    fn len<S: Clone + std::hash::Hash + Eq, M, C: MetricConstructor<M>, H: BuildHasher + Clone>(
+        family: &Family<S, M, C, H>,
+    ) -> usize {
+        family.read().iter().map(|shard| shard.len()).sum()
+    }
+
+    #[test]
+    fn counter_family_recovers_from_poisoned_lock() {
+        use crate::encoding::text::encode;
+        use crate::registry::Registry;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::sync::atomic::AtomicBool;
+
+        let fail = Arc::new(AtomicBool::new(true));
+        let family = {
+            let fail = fail.clone();
+            Family::<Vec<(String, String)>, Counter, _>::new_with_constructor(move || {
+                if fail.swap(false, Ordering::SeqCst) {
+                    panic!("Failed to construct metric.");
+                }
+                Counter::default()
+            })
+        };
+        let mut registry = Registry::default();
+        registry.register("my_counter", "My counter", family.clone());
+
+        let label_set = vec![("method".to_string(), "GET".to_string())];
+        assert!(catch_unwind(AssertUnwindSafe(|| family.get_or_create(&label_set).inc())).is_err());
+        assert!(family.metrics[0].is_poisoned());
+
+        family.get_or_create(&label_set).inc();
+        assert_eq!(1, family.get(&label_set).unwrap().get());
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &registry).unwrap();
+        assert!(String::from_utf8(encoded)
+            .unwrap()
+            .contains("my_counter_total{method=\"GET\"} 1\n"));
+    }
+
+    #[test]
+    fn counter_family_with_max_series_recovers_from_panicking_constructor() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::sync::atomic::AtomicBool;
+
+        let fail = Arc::new(AtomicBool::new(true));
+        let family = {
+            let fail = fail.clone();
+            Family::<Vec<(String, String)>, Counter, _>::new_with_constructor(move || {
+                if fail.swap(false, Ordering::SeqCst) {
+                    panic!("Failed to construct metric.");
+                }
+                Counter::default()
+            })
+            .with_max_series(1)
+        };
+
+        let get = vec![("method".to_string(), "GET".to_string())];
+        assert!(catch_unwind(AssertUnwindSafe(|| family.get_or_create(&get).inc())).is_err());
+        assert!(family.is_empty());
+
+        let put = vec![("method".to_string(), "PUT".to_string())];
+        family.get_or_create(&put).inc();
+        assert_eq!(1, family.get(&put).unwrap().get());
+        assert_eq!(0, family.dropped_series().get());
+    }
+
+    #[test]
+    fn counter_family_len() {
+        let family =
+            Family::<Vec<(String, String)>, Counter>::new_with_shards(4).with_max_series(2);
+        assert!(family.is_empty());
+        assert_eq!(0, family.len());
+
+        for method in ["GET", "PUT", "POST"] {
+            family
+                .get_or_create(&vec![("method".to_string(), method.to_string())])
+                .inc();
+        }
+        assert!(!family.is_empty());
+        assert_eq!(2, family.len());
+
+        family.remove(&vec![("method".to_string(), "GET".to_string())]);
+        assert_eq!(1, family.len());
+        family.clear();
+        assert!(family.is_empty());
+    }
+
+    #[test]
+    fn counter_family_with_capacity() {
+        let family =
+            Family::<Vec<(String, String)>, Counter>::new_with_shards(4).with_capacity(1_000);
+        for shard in family.read() {
+            assert!(shard.capacity() >= 250);
+        }
+
+        let label_set = vec![("method".to_string(), "GET".to_string())];
+        family.get_or_create(&label_set).inc();
+        assert_eq!(1, family.get(&label_set).unwrap().get());
+    }
 
     #[test]
     fn counter_family() {
@@ -274,6 +1370,282 @@ mod tests {
         );
     }
 
+    #[test]
+    fn counter_family_concurrent_get_or_create_and_remove() {
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        let label_set = vec![("method".to_string(), "GET".to_string())];
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let family = family.clone();
+                let label_set = label_set.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        match i {
+                            0 => {
+                                family.remove(&label_set);
+                            }
+                            1 => family.clear(),
+                            2 => {
+                                family.get_or_create(&label_set).inc();
+                            }
+                            _ => {
+                                for counter in
+                                    family.get_or_create_many(std::slice::from_ref(&label_set))
+                                {
+                                    counter.inc();
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn counter_family_concurrent_get_or_create() {
+        use std::sync::Barrier;
+
+        const THREADS: usize = 32;
+
+        let constructed = Arc::new(AtomicUsize::new(0));
+        let family = {
+            let constructed = constructed.clone();
+            Family::<Vec<(String, String)>, Counter, _>::new_with_constructor(move || {
+                constructed.fetch_add(1, Ordering::Relaxed);
+                Counter::default()
+            })
+        };
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let label_set = vec![("method".to_string(), "GET".to_string())];
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let family = family.clone();
+                let barrier = barrier.clone();
+                let label_set = label_set.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    family.get_or_create(&label_set).inc();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(1, constructed.load(Ordering::Relaxed));
+        assert_eq!(1, len(&family));
+        assert_eq!(THREADS as u64, family.get_or_create(&label_set).get());
+    }
+
+    #[test]
+    fn interner_shares_storage() {
+        let interner = Interner::default();
+        let a = interner.intern("method");
+        let method = String::from("method");
+        let b = interner.intern(&method);
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(1, interner.len());
+
+        interner.intern("GET");
+        assert_eq!(2, interner.len());
+    }
+
+    #[test]
+    fn counter_family_with_default_labels() {
+        let family = Family::<Vec<(String, String)>, Counter>::default().with_default_labels(vec![
+            ("env".to_string(), "prod".to_string()),
+            ("method".to_string(), "GET".to_string()),
+        ]);
+        let put = vec![("method".to_string(), "PUT".to_string())];
+
+        family.get_or_create(&put).inc();
+        family.get_or_create(&put).inc();
+        family.get_or_create(&vec![]).inc();
+
+        assert_eq!(2, len(&family));
+        assert_eq!(2, family.get(&put).unwrap().get());
+        assert!(family.remove(&vec![]));
+        assert_eq!(1, len(&family));
+
+        let mut label_sets = vec![];
+        family.for_each(|label_set, _| label_sets.push(label_set.clone()));
+        assert_eq!(
+            vec![vec![
+                ("env".to_string(), "prod".to_string()),
+                ("method".to_string(), "PUT".to_string()),
+            ]],
+            label_sets
+        );
+    }
+
+    #[test]
+    fn counter_family_with_default_labels_in_any_order() {
+        let env = ("env".to_string(), "prod".to_string());
+        let method = ("method".to_string(), "PUT".to_string());
+        let status = ("status".to_string(), "200".to_string());
+        let family = Family::<Vec<(String, String)>, Counter>::default()
+            .with_default_labels(vec![env.clone()]);
+
+        family.get_or_create(&vec![method.clone()]).inc();
+        family
+            .get_or_create(&vec![env.clone(), method.clone()])
+            .inc();
+        family
+            .get_or_create(&vec![method.clone(), env.clone()])
+            .inc();
+        assert_eq!(1, len(&family));
+        assert_eq!(3, family.get(&vec![method.clone()]).unwrap().get());
+
+        let merged = vec![env.clone(), method.clone(), status.clone()];
+        assert_eq!(None, merged.merge(&vec![env.clone()]));
+        assert_eq!(
+            Some(merged),
+            vec![method, status, env.clone()].merge(&vec![env])
+        );
+    }
+
+    #[test]
+    fn counter_family_with_sorted_labels() {
+        let family = Family::<Vec<(String, String)>, Counter>::default()
+            .with_sorted_labels()
+            .with_default_labels(vec![("env".to_string(), "prod".to_string())]);
+        let a = ("a".to_string(), "1".to_string());
+        let b = ("b".to_string(), "2".to_string());
+
+        family.get_or_create(&vec![a.clone(), b.clone()]).inc();
+        family.get_or_create(&vec![b.clone(), a.clone()]).inc();
+
+        assert_eq!(1, len(&family));
+        assert_eq!(2, family.get(&vec![b.clone(), a.clone()]).unwrap().get());
+
+        let mut label_sets = vec![];
+        family.for_each(|label_set, _| label_sets.push(label_set.clone()));
+        assert_eq!(
+            vec![vec![
+                a.clone(),
+                b.clone(),
+                ("env".to_string(), "prod".to_string())
+            ]],
+            label_sets
+        );
+
+        assert!(family.remove(&vec![b, a]));
+        assert_eq!(0, len(&family));
+    }
+
+    #[test]
+    fn counter_family_remove_and_clear() {
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        let get = vec![("method".to_string(), "GET".to_string())];
+        let put = vec![("method".to_string(), "PUT".to_string())];
+
+        family.get_or_create(&get).inc();
+        family.get_or_create(&put).inc();
+
+        assert!(family.remove(&get));
+        assert!(!family.remove(&get));
+        assert_eq!(1, len(&family));
+
+        // Recreating a removed label set starts from scratch.
+        assert_eq!(0, family.get_or_create(&get).get());
+
+        family.clear();
+        assert_eq!(0, len(&family));
+    }
+
+    #[test]
+    fn counter_family_for_each() {
+        let family = Family::<Vec<(String, String)>, Counter>::new_with_shards(4);
+        for i in 0..10 {
+            family
+                .get_or_create(&vec![("id".to_string(), i.to_string())])
+                .inc_by(i);
+        }
+
+        let mut visited = vec![];
+        family.for_each(|labels, counter| visited.push((labels[0].1.clone(), counter.get())));
+        visited.sort_by_key(|(_, v)| *v);
+        assert_eq!(
+            (0..10).map(|i| (i.to_string(), i)).collect::<Vec<_>>(),
+            visited
+        );
+    }
+
+    #[test]
+    fn counter_family_get() {
+        let family = Family::<Vec<(String, String)>, Counter>::default();
+        let labels = vec![("method".to_string(), "GET".to_string())];
+
+        assert!(family.get(&labels).is_none());
+        assert_eq!(0, len(&family));
+
+        family.get_or_create(&labels).inc();
+        assert_eq!(1, family.get(&labels).unwrap().get());
+    }
+
+    #[test]
+    fn sharded_counter_family() {
+        let family = Family::<Vec<(String, String)>, Counter>::new_with_shards(4);
+
+        for i in 0..100 {
+            family
+                .get_or_create(&vec![("id".to_string(), i.to_string())])
+                .inc();
+        }
+        assert_eq!(100, len(&family));
+        assert!(
+            family
+                .read()
+                .iter()
+                .filter(|shard| !shard.is_empty())
+                .count()
+                > 1
+        );
+
+        assert_eq!(
+            1,
+            family
+                .get(&vec![("id".to_string(), "42".to_string())])
+                .unwrap()
+                .get()
+        );
+        assert!(family.remove(&vec![("id".to_string(), "42".to_string())]));
+        assert_eq!(99, len(&family));
+
+        family.clear();
+        assert_eq!(0, len(&family));
+    }
+
+    #[test]
+    #[should_panic(expected = "Family requires at least one shard.")]
+    fn zero_shards() {
+        Family::<(), Counter>::new_with_shards(0);
+    }
+
+    #[test]
+    fn label_allow_list() {
+        let allow_list = LabelAllowList::new_with_sentinel(["GET", "PUT"], "unknown");
+
+        assert_eq!("GET", allow_list.constrain("GET").as_str());
+        assert_eq!("unknown", allow_list.constrain("get").as_str());
+        assert_eq!("unknown", allow_list.constrain("BREW").as_str());
+
+        let family = Family::<Vec<(String, ConstrainedLabel)>, Counter>::default();
+        for method in ["GET", "BREW", "CONNECT", "PUT", "GET"] {
+            family
+                .get_or_create(&vec![("method".to_string(), allow_list.constrain(method))])
+                .inc();
+        }
+        assert_eq!(3, len(&family));
+    }
+
     #[test]
     fn histogram_family() {
         Family::<(), Histogram>::new_with_constructor(|| {
@@ -295,4 +1667,179 @@ mod tests {
         let custom_builder = CustomBuilder { custom_start: 1.0 };
         Family::<(), Histogram, CustomBuilder>::new_with_constructor(custom_builder);
     }
+
+    #[test]
+    fn counter_family_max_series() {
+        let family =
+            Family::<Vec<(String, String)>, Counter>::new_with_shards(4).with_max_series(2);
+        let labels = |i: usize| vec![("id".to_string(), i.to_string())];
+
+        family.get_or_create(&labels(0)).inc();
+        family.get_or_create(&labels(1)).inc();
+        assert!(family.try_get_or_create(&labels(2)).is_none());
+        assert_eq!(1, family.dropped_series().get());
+
+        // Refused label sets share a single fallback metric.
+        family.get_or_create(&labels(2)).inc();
+        family.get_or_create(&labels(3)).inc();
+        assert_eq!(2, family.get_or_create(&labels(4)).get());
+        assert_eq!(4, family.dropped_series().get());
+        assert_eq!(2, len(&family));
+
+        // Existing label sets are still accessible.
+        assert_eq!(1, family.try_get_or_create(&labels(0)).unwrap().get());
+
+        // Removing a label set frees up room for a new one.
+        assert!(family.remove(&labels(0)));
+        assert!(family.try_get_or_create(&labels(2)).is_some());
+        family.clear();
+        assert!(family.try_get_or_create(&labels(3)).is_some());
+        assert!(family.try_get_or_create(&labels(4)).is_some());
+        assert!(family.try_get_or_create(&labels(5)).is_none());
+    }
+
+    #[test]
+    fn counter_family_max_series_fallback_keyed_by_default() {
+        let family = Family::<Vec<(String, String)>, Counter>::default().with_max_series(1);
+        let labels = |i: usize| vec![("id".to_string(), i.to_string())];
+
+        family.get_or_create(&labels(0)).inc();
+        family.get_or_create(&labels(1)).inc();
+
+        let fallback = family
+            .limit
+            .as_ref()
+            .unwrap()
+            .fallback
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![Vec::<(String, String)>::new()], fallback);
+        assert!(family.try_get_or_create(&labels(1)).is_none());
+        assert!(family.try_get_or_create(&vec![]).is_none());
+    }
+
+    #[test]
+    fn histogram_family_aggregate() {
+        let family = Family::<Vec<(String, String)>, Histogram>::new_with_constructor_and_shards(
+            || Histogram::new(exponential_buckets(1.0, 2.0, 10)),
+            4,
+        );
+        assert_eq!((0, 0.0), family.aggregate());
+
+        for i in 0..10 {
+            family
+                .get_or_create(&vec![("id".to_string(), i.to_string())])
+                .observe(i as f64);
+        }
+        family
+            .get_or_create(&vec![("id".to_string(), "0".to_string())])
+            .observe(0.5);
+
+        assert_eq!((11, 45.5), family.aggregate());
+    }
+
+    #[test]
+    fn counter_family_get_or_create_many() {
+        let family = Family::<Vec<(String, String)>, Counter>::default().with_max_series(3);
+        let labels = |i: usize| vec![("id".to_string(), i.to_string())];
+
+        family.get_or_create(&labels(0)).inc();
+
+        let label_sets: Vec<_> = (0..5).map(labels).chain([labels(1)]).collect();
+        for counter in family.get_or_create_many(&label_sets) {
+            counter.inc();
+        }
+
+        assert_eq!(3, len(&family));
+        assert_eq!(2, family.get(&labels(0)).unwrap().get());
+        assert_eq!(2, family.get(&labels(1)).unwrap().get());
+        assert_eq!(1, family.get(&labels(2)).unwrap().get());
+        assert!(family.get(&labels(3)).is_none());
+        // Refused label sets share the fallback metric.
+        assert_eq!(2, family.dropped_series().get());
+        assert_eq!(2, family.get_or_create(&labels(3)).get());
+
+        assert!(family.get_or_create_many(&[]).is_empty());
+
+        let family = Family::<Vec<(String, String)>, Counter>::new_with_shards(4);
+        let label_sets: Vec<_> = (0..100).map(labels).collect();
+        for counter in family.get_or_create_many(&label_sets) {
+            counter.inc();
+        }
+        for counter in family.get_or_create_many(&label_sets) {
+            assert_eq!(1, counter.get());
+        }
+    }
+
+    #[test]
+    fn histogram_family_with_constructor() {
+        let family = Family::<Vec<(String, String)>, Histogram, _>::new_with_constructor(
+            HistogramConstructor::new(vec![1.0, 10.0].into_iter()),
+        );
+        let index = vec![("route".to_string(), "/".to_string())];
+        let login = vec![("route".to_string(), "/login".to_string())];
+
+        family.get_or_create(&index).observe(0.5);
+        family.get_or_create(&login).observe(5.0);
+        family.get_or_create(&login).observe(50.0);
+
+        assert_eq!(
+            vec![(1.0, 1), (10.0, 1), (f64::INFINITY, 1)],
+            family.get_or_create(&index).bucket_counts()
+        );
+        assert_eq!(
+            vec![(1.0, 0), (10.0, 1), (f64::INFINITY, 2)],
+            family.get_or_create(&login).bucket_counts()
+        );
+    }
+
+    #[test]
+    fn counter_family_with_hasher() {
+        let family = Family::<Vec<(String, String)>, Counter, _, _>::new_with_hasher(
+            Counter::default,
+            4,
+            std::hash::BuildHasherDefault::<std::collections::hash_map::DefaultHasher>::default(),
+        )
+        .with_max_series(1);
+        let get = vec![("method".to_string(), "GET".to_string())];
+        let put = vec![("method".to_string(), "PUT".to_string())];
+
+        family.get_or_create(&get).inc();
+        family.get_or_create(&put).inc();
+        assert_eq!(1, family.get(&get).unwrap().get());
+        assert!(family.get(&put).is_none());
+        assert_eq!(1, len(&family));
+    }
+
+    #[test]
+    fn counter_family_selects_shard_with_hasher() {
+        #[derive(Default)]
+        struct ConstantHasher;
+
+        impl std::hash::Hasher for ConstantHasher {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
+
+        let family = Family::<Vec<(String, String)>, Counter, _, _>::new_with_hasher(
+            Counter::default,
+            4,
+            std::hash::BuildHasherDefault::<ConstantHasher>::default(),
+        );
+        for method in ["GET", "PUT", "POST", "DELETE"].iter() {
+            family
+                .get_or_create(&vec![("method".to_string(), method.to_string())])
+                .inc();
+        }
+
+        let shards = family.read();
+        assert_eq!(4, shards[0].len());
+        assert!(shards[1..].iter().all(|shard| shard.is_empty()));
+    }
 }
@@ -3,9 +3,11 @@
 //! See [`Gauge`] for details.
 
 use super::{MetricType, TypedMetric};
+use crate::clock::{Clock, SystemClock};
 use std::marker::PhantomData;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Open Metrics [`Gauge`] to record current measurements.
 ///
@@ -36,6 +38,21 @@ use std::sync::Arc;
 /// gauge.set(42.0);
 /// let _value: f64 = gauge.get();
 /// ```
+///
+/// ## Using [`AtomicI64`] as storage and [`i64`] on the interface
+///
+/// Allows the [`Gauge`] to drop below zero.
+///
+/// ```
+/// # use prometheus_client::metrics::gauge::Gauge;
+/// # use std::sync::atomic::AtomicI64;
+/// let gauge = Gauge::<i64, AtomicI64>::default();
+/// gauge.set(-42);
+/// let _value: i64 = gauge.get();
+/// ```
+///
+/// See [`GaugeWithTimestamp`] to expose a value along with the time it was
+/// observed at.
 pub struct Gauge<N = u64, A = AtomicU64> {
     value: Arc<A>,
     phantom: PhantomData<N>,
@@ -59,6 +76,23 @@ impl<N, A: Default> Default for Gauge<N, A> {
     }
 }
 
+impl<N, A: Atomic<N> + Default> Gauge<N, A> {
+    /// Create a new [`Gauge`] starting at the given value, e.g. a configured
+    /// limit. [`Gauge::default`] starts at zero.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::gauge::Gauge;
+    /// # use std::sync::atomic::AtomicI64;
+    /// let gauge = Gauge::<i64, AtomicI64>::new(-42);
+    /// assert_eq!(-42, gauge.get());
+    /// ```
+    pub fn new(initial: N) -> Self {
+        let gauge = Self::default();
+        gauge.set(initial);
+        gauge
+    }
+}
+
 impl<N, A: Atomic<N>> Gauge<N, A> {
     /// Increase the [`Gauge`] by 1, returning the previous value.
     pub fn inc(&self) -> N {
@@ -85,6 +119,27 @@ impl<N, A: Atomic<N>> Gauge<N, A> {
         self.value.set(v)
     }
 
+    /// Increase the [`Gauge`] by 1, decreasing it again once the returned
+    /// [`InProgressGuard`] is dropped, e.g. to track the number of in-flight
+    /// requests across early returns and panics.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::gauge::Gauge;
+    /// let in_flight: Gauge = Gauge::default();
+    ///
+    /// {
+    ///     let _guard = in_flight.track_inprogress();
+    ///     assert_eq!(1, in_flight.get());
+    /// }
+    /// assert_eq!(0, in_flight.get());
+    /// ```
+    pub fn track_inprogress(&self) -> InProgressGuard<N, A> {
+        self.inc();
+        InProgressGuard {
+            gauge: self.clone(),
+        }
+    }
+
     /// Get the current value of the [`Gauge`].
     pub fn get(&self) -> N {
         self.value.get()
@@ -99,6 +154,198 @@ impl<N, A: Atomic<N>> Gauge<N, A> {
     }
 }
 
+impl<A: Atomic<f64>> Gauge<f64, A> {
+    /// Sets the [`Gauge`] to the current time in seconds since the Unix epoch,
+    /// e.g. to expose the time of the last successful run of a batch job,
+    /// returning the previous value.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::gauge::Gauge;
+    /// # use std::sync::atomic::AtomicU64;
+    /// let last_success = Gauge::<f64, AtomicU64>::default();
+    /// last_success.set_to_current_time();
+    /// assert!(last_success.get() > 0.0);
+    /// ```
+    pub fn set_to_current_time(&self) -> f64 {
+        self.set_to_current_time_with_clock(&SystemClock)
+    }
+
+    /// Sets the [`Gauge`] to the current time of the given [`Clock`], see
+    /// [`Gauge::set_to_current_time`].
+    pub fn set_to_current_time_with_clock<C: Clock + ?Sized>(&self, clock: &C) -> f64 {
+        self.set(since_unix_epoch(clock).as_secs_f64())
+    }
+}
+
+macro_rules! impl_set_to_current_time {
+    ($($n:ty),*) => {
+        $(
+            impl<A: Atomic<$n>> Gauge<$n, A> {
+                /// Sets the [`Gauge`] to the current time in whole seconds
+                /// since the Unix epoch, returning the previous value. See the
+                /// `f64` variant for sub-second precision.
+                pub fn set_to_current_time(&self) -> $n {
+                    self.set_to_current_time_with_clock(&SystemClock)
+                }
+
+                /// Sets the [`Gauge`] to the current time of the given
+                /// [`Clock`], see [`Gauge::set_to_current_time`].
+                pub fn set_to_current_time_with_clock<C: Clock + ?Sized>(&self, clock: &C) -> $n {
+                    let secs = since_unix_epoch(clock).as_secs();
+                    self.set(std::convert::TryFrom::try_from(secs).unwrap_or(<$n>::MAX))
+                }
+            }
+        )*
+    };
+}
+
+impl_set_to_current_time!(u64, u32, i64);
+
+/// The current time of the given [`Clock`] as duration since the Unix epoch,
+/// or zero for times before the Unix epoch.
+fn since_unix_epoch<C: Clock + ?Sized>(clock: &C) -> Duration {
+    clock
+        .now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+}
+
+/// Open Metrics [`Gauge`] exposing its value along with the time it was
+/// observed at.
+///
+/// By default a sample carries no timestamp and Prometheus attributes it to
+/// the time of the scrape. When mirroring a value observed at a known point in
+/// time, use [`GaugeWithTimestamp::set_with_timestamp`]. Use a plain [`Gauge`]
+/// otherwise, which does not pay for tracking the timestamp.
+///
+/// ```
+/// # use prometheus_client::metrics::gauge::GaugeWithTimestamp;
+/// # use std::time::SystemTime;
+/// let gauge: GaugeWithTimestamp = GaugeWithTimestamp::default();
+/// gauge.set_with_timestamp(42, SystemTime::now());
+/// assert!(gauge.timestamp().is_some());
+/// ```
+pub struct GaugeWithTimestamp<N = u64, A = AtomicU64> {
+    gauge: Gauge<N, A>,
+    /// Nanoseconds since the Unix epoch, `0` meaning no timestamp is set.
+    timestamp: Arc<AtomicU64>,
+}
+
+impl<N, A> Clone for GaugeWithTimestamp<N, A> {
+    fn clone(&self) -> Self {
+        Self {
+            gauge: self.gauge.clone(),
+            timestamp: self.timestamp.clone(),
+        }
+    }
+}
+
+impl<N, A: Default> Default for GaugeWithTimestamp<N, A> {
+    fn default() -> Self {
+        Self {
+            gauge: Gauge::default(),
+            timestamp: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<N, A: Atomic<N>> GaugeWithTimestamp<N, A> {
+    /// Increase the [`GaugeWithTimestamp`] by 1, returning the previous
+    /// value. Clears the timestamp.
+    pub fn inc(&self) -> N {
+        self.clear_timestamp();
+        self.gauge.inc()
+    }
+
+    /// Increase the [`GaugeWithTimestamp`] by `v`, returning the previous
+    /// value. Clears the timestamp.
+    pub fn inc_by(&self, v: N) -> N {
+        self.clear_timestamp();
+        self.gauge.inc_by(v)
+    }
+
+    /// Decrease the [`GaugeWithTimestamp`] by 1, returning the previous
+    /// value. Clears the timestamp.
+    pub fn dec(&self) -> N {
+        self.clear_timestamp();
+        self.gauge.dec()
+    }
+
+    /// Decrease the [`GaugeWithTimestamp`] by `v`, returning the previous
+    /// value. Clears the timestamp.
+    pub fn dec_by(&self, v: N) -> N {
+        self.clear_timestamp();
+        self.gauge.dec_by(v)
+    }
+
+    /// Sets the [`GaugeWithTimestamp`] to `v`, returning the previous value.
+    /// Clears the timestamp.
+    pub fn set(&self, v: N) -> N {
+        self.clear_timestamp();
+        self.gauge.set(v)
+    }
+
+    /// Sets the [`GaugeWithTimestamp`] to `v` observed at `timestamp`,
+    /// returning the previous value.
+    ///
+    /// The timestamp is exposed alongside the value until the next update.
+    /// Timestamps before the Unix epoch are ignored.
+    ///
+    /// Note that value and timestamp are updated one after the other, thus a
+    /// concurrent encoding might observe the new value with the old timestamp.
+    pub fn set_with_timestamp(&self, v: N, timestamp: SystemTime) -> N {
+        let nanos = timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        let previous = self.gauge.set(v);
+        self.timestamp.store(nanos, Ordering::Relaxed);
+        previous
+    }
+
+    /// Sets the [`GaugeWithTimestamp`] to `v` observed at the current time of
+    /// the given [`Clock`], returning the previous value. See
+    /// [`GaugeWithTimestamp::set_with_timestamp`].
+    pub fn set_with_clock<C: Clock + ?Sized>(&self, v: N, clock: &C) -> N {
+        self.set_with_timestamp(v, clock.now())
+    }
+
+    /// Get the current value of the [`GaugeWithTimestamp`].
+    pub fn get(&self) -> N {
+        self.gauge.get()
+    }
+
+    /// Get the timestamp set via [`GaugeWithTimestamp::set_with_timestamp`],
+    /// if any.
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        match self.timestamp.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(UNIX_EPOCH + Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn clear_timestamp(&self) {
+        self.timestamp.store(0, Ordering::Relaxed);
+    }
+}
+
+impl<N, A> TypedMetric for GaugeWithTimestamp<N, A> {
+    const TYPE: MetricType = MetricType::Gauge;
+}
+
+/// Guard returned by [`Gauge::track_inprogress`], decreasing the [`Gauge`] by
+/// 1 when dropped.
+#[must_use = "The gauge is decreased again as soon as the guard is dropped."]
+pub struct InProgressGuard<N: 'static, A: Atomic<N>> {
+    gauge: Gauge<N, A>,
+}
+
+impl<N, A: Atomic<N>> Drop for InProgressGuard<N, A> {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
 pub trait Atomic<N> {
     fn inc(&self) -> N;
 
@@ -165,6 +412,32 @@ impl Atomic<u32> for AtomicU32 {
     }
 }
 
+impl Atomic<i64> for AtomicI64 {
+    fn inc(&self) -> i64 {
+        self.inc_by(1)
+    }
+
+    fn inc_by(&self, v: i64) -> i64 {
+        self.fetch_add(v, Ordering::Relaxed)
+    }
+
+    fn dec(&self) -> i64 {
+        self.dec_by(1)
+    }
+
+    fn dec_by(&self, v: i64) -> i64 {
+        self.fetch_sub(v, Ordering::Relaxed)
+    }
+
+    fn set(&self, v: i64) -> i64 {
+        self.swap(v, Ordering::Relaxed)
+    }
+
+    fn get(&self) -> i64 {
+        self.load(Ordering::Relaxed)
+    }
+}
+
 impl Atomic<f64> for AtomicU64 {
     fn inc(&self) -> f64 {
         self.inc_by(1.0)
@@ -217,6 +490,57 @@ impl<N, A> TypedMetric for Gauge<N, A> {
     const TYPE: MetricType = MetricType::Gauge;
 }
 
+/// Open Metrics [`Gauge`] computing its value on demand, calling the given
+/// closure each time the metric is encoded.
+///
+/// A lightweight alternative to a [`Collector`](crate::collector::Collector)
+/// for values derived from state held elsewhere, e.g. the current value of a
+/// configuration option or the length of a queue. The closure is called while
+/// encoding, thus it should be cheap and must not block.
+///
+/// ```
+/// # use prometheus_client::metrics::gauge::GaugeFn;
+/// # use prometheus_client::registry::Registry;
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// #
+/// let queue_len = Arc::new(AtomicUsize::new(0));
+///
+/// let mut registry = Registry::default();
+/// let len = queue_len.clone();
+/// let gauge = GaugeFn::new(move || len.load(Ordering::Relaxed) as f64);
+/// registry.register("queue_length", "Number of queued items", gauge.clone());
+///
+/// queue_len.store(3, Ordering::Relaxed);
+/// assert_eq!(3.0, gauge.get());
+/// ```
+pub struct GaugeFn {
+    f: Arc<dyn Fn() -> f64 + Send + Sync>,
+}
+
+impl Clone for GaugeFn {
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone() }
+    }
+}
+
+impl GaugeFn {
+    /// Create a new [`GaugeFn`] exposing the value returned by the given
+    /// closure.
+    pub fn new(f: impl Fn() -> f64 + Send + Sync + 'static) -> Self {
+        Self { f: Arc::new(f) }
+    }
+
+    /// Get the current value of the [`GaugeFn`], calling its closure.
+    pub fn get(&self) -> f64 {
+        (self.f)()
+    }
+}
+
+impl TypedMetric for GaugeFn {
+    const TYPE: MetricType = MetricType::Gauge;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +557,63 @@ mod tests {
         assert_eq!(0, gauge.set(10));
         assert_eq!(10, gauge.get());
     }
+
+    #[test]
+    fn i64_below_zero() {
+        let gauge = Gauge::<i64, AtomicI64>::default();
+        assert_eq!(0, gauge.dec());
+        assert_eq!(-1, gauge.get());
+
+        assert_eq!(-1, gauge.set(-42));
+        assert_eq!(-42, gauge.get());
+    }
+
+    #[test]
+    fn set_with_timestamp() {
+        let gauge: GaugeWithTimestamp = GaugeWithTimestamp::default();
+        assert_eq!(None, gauge.timestamp());
+
+        let timestamp = UNIX_EPOCH + Duration::from_millis(1_500_000_000_123);
+        gauge.set_with_timestamp(42, timestamp);
+        assert_eq!(42, gauge.get());
+        assert_eq!(Some(timestamp), gauge.timestamp());
+
+        gauge.inc();
+        assert_eq!(None, gauge.timestamp());
+
+        gauge.set_with_clock(7, &|| timestamp);
+        assert_eq!(Some(timestamp), gauge.timestamp());
+    }
+
+    #[test]
+    fn set_to_current_time() {
+        let clock = || UNIX_EPOCH + Duration::from_millis(1_500_000_000_500);
+
+        let gauge: Gauge = Gauge::default();
+        gauge.set_to_current_time_with_clock(&clock);
+        assert_eq!(1_500_000_000, gauge.get());
+
+        let gauge = Gauge::<f64, AtomicU64>::default();
+        gauge.set_to_current_time_with_clock(&clock);
+        assert_eq!(1_500_000_000.5, gauge.get());
+
+        let gauge = Gauge::<i64, AtomicI64>::default();
+        gauge.set_to_current_time();
+        assert!(gauge.get() > 1_500_000_000);
+    }
+
+    #[test]
+    fn track_inprogress_across_panic() {
+        let gauge: Gauge = Gauge::default();
+        gauge.set(5);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = gauge.track_inprogress();
+            assert_eq!(6, gauge.get());
+            panic!("Panicking while in progress.");
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(5, gauge.get());
+    }
 }
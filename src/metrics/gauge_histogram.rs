@@ -0,0 +1,151 @@
+//! Module implementing an Open Metrics gauge histogram.
+//!
+//! See [`GaugeHistogram`] for details.
+
+use super::family::Aggregate;
+use super::histogram::empty_buckets;
+use super::{MetricType, TypedMetric};
+use std::sync::{Arc, Mutex};
+
+/// Open Metrics [`GaugeHistogram`] to expose the current distribution of a
+/// set of values, e.g. the age of the items in a queue.
+///
+/// In contrast to a [`Histogram`](crate::metrics::histogram::Histogram),
+/// which accumulates observations over time, the distribution of a
+/// [`GaugeHistogram`] can go up and down. Each call to [`GaugeHistogram::set`]
+/// replaces the previous distribution.
+///
+/// ```
+/// # use prometheus_client::metrics::gauge_histogram::GaugeHistogram;
+/// let gauge_histogram = GaugeHistogram::new(vec![1.0, 10.0, 60.0].into_iter());
+///
+/// // The age in seconds of each item currently in the queue.
+/// gauge_histogram.set(vec![0.5, 3.0, 42.0]);
+/// assert_eq!(3, gauge_histogram.count());
+/// ```
+pub struct GaugeHistogram {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Clone for GaugeHistogram {
+    fn clone(&self) -> Self {
+        GaugeHistogram {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct Inner {
+    sum: f64,
+    count: u64,
+    buckets: Vec<(f64, u64)>,
+}
+
+impl GaugeHistogram {
+    /// Create a new [`GaugeHistogram`] with the given bucket upper bounds.
+    ///
+    /// As with [`Histogram::new`](crate::metrics::histogram::Histogram::new)
+    /// the upper bounds are sorted in ascending order and a `+Inf` bucket is
+    /// appended automatically.
+    pub fn new(buckets: impl Iterator<Item = f64>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                sum: Default::default(),
+                count: Default::default(),
+                buckets: empty_buckets(buckets),
+            })),
+        }
+    }
+
+    /// Replace the distribution of the [`GaugeHistogram`] with the given
+    /// values.
+    pub fn set(&self, values: impl IntoIterator<Item = f64>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.sum = 0.0;
+        inner.count = 0;
+        for (_upper_bound, count) in inner.buckets.iter_mut() {
+            *count = 0;
+        }
+
+        for v in values {
+            inner.sum += v;
+            inner.count += 1;
+            if let Some((_upper_bound, count)) = inner
+                .buckets
+                .iter_mut()
+                .find(|(upper_bound, _count)| *upper_bound >= v)
+            {
+                *count += 1;
+            }
+        }
+    }
+
+    /// Get the sum of the current values.
+    pub fn sum(&self) -> f64 {
+        self.inner.lock().unwrap().sum
+    }
+
+    /// Get the number of current values.
+    pub fn count(&self) -> u64 {
+        self.inner.lock().unwrap().count
+    }
+
+    /// Get the cumulative count of each bucket keyed by its upper bound. See
+    /// [`Histogram::bucket_counts`](crate::metrics::histogram::Histogram::bucket_counts).
+    pub fn bucket_counts(&self) -> Vec<(f64, u64)> {
+        self.inner
+            .lock()
+            .unwrap()
+            .buckets
+            .iter()
+            .scan(0, |cumulative, (upper_bound, count)| {
+                *cumulative += count;
+                Some((*upper_bound, *cumulative))
+            })
+            .collect()
+    }
+
+    pub(crate) fn get(&self) -> (f64, u64, Vec<(f64, u64)>) {
+        let inner = self.inner.lock().unwrap();
+        (inner.sum, inner.count, inner.buckets.clone())
+    }
+}
+
+impl TypedMetric for GaugeHistogram {
+    const TYPE: MetricType = MetricType::GaugeHistogram;
+}
+
+impl Aggregate for GaugeHistogram {
+    fn count(&self) -> u64 {
+        GaugeHistogram::count(self)
+    }
+
+    fn sum(&self) -> f64 {
+        GaugeHistogram::sum(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_replaces_distribution() {
+        let gauge_histogram = GaugeHistogram::new(vec![10.0, 1.0].into_iter());
+
+        gauge_histogram.set(vec![0.5, 5.0, f64::INFINITY]);
+        assert_eq!(3, gauge_histogram.count());
+        assert_eq!(
+            vec![(1.0, 1), (10.0, 2), (f64::INFINITY, 3)],
+            gauge_histogram.bucket_counts()
+        );
+
+        gauge_histogram.set(vec![2.0]);
+        assert_eq!(1, gauge_histogram.count());
+        assert_eq!(2.0, gauge_histogram.sum());
+        assert_eq!(
+            vec![(1.0, 0), (10.0, 1), (f64::INFINITY, 1)],
+            gauge_histogram.bucket_counts()
+        );
+    }
+}
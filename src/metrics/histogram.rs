@@ -2,10 +2,12 @@
 //!
 //! See [`Histogram`] for details.
 
+use super::family::{Aggregate, MetricConstructor};
 use super::{MetricType, TypedMetric};
-use owning_ref::OwningRef;
+use std::cmp::Ordering;
 use std::iter::{self, once};
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 /// Open Metrics [`Histogram`] to measure distributions of discrete events.
 ///
@@ -18,12 +20,16 @@ use std::sync::{Arc, Mutex, MutexGuard};
 // https://github.com/tikv/rust-prometheus/pull/314.
 pub struct Histogram {
     inner: Arc<Mutex<Inner>>,
+    /// Sorted bucket upper bounds excluding `+Inf`, immutable after
+    /// construction and thus kept outside of the lock.
+    upper_bounds: Arc<[f64]>,
 }
 
 impl Clone for Histogram {
     fn clone(&self) -> Self {
         Histogram {
             inner: self.inner.clone(),
+            upper_bounds: self.upper_bounds.clone(),
         }
     }
 }
@@ -32,69 +38,494 @@ pub(crate) struct Inner {
     // TODO: Consider allowing integer observe values.
     sum: f64,
     count: u64,
+    /// Non-cumulative count of each bucket, in the order of the upper bounds
+    /// and ending with the `+Inf` bucket.
     // TODO: Consider being generic over the bucket length.
-    buckets: Vec<(f64, u64)>,
+    buckets: Vec<u64>,
+    /// Whether negative values are rejected, see [`Histogram::reject_negative`].
+    reject_negative: bool,
 }
 
 impl Histogram {
+    /// Create a new [`Histogram`] with the given bucket upper bounds.
+    ///
+    /// The upper bounds are sorted in ascending order. A `+Inf` bucket is
+    /// appended automatically and must not be provided.
     pub fn new(buckets: impl Iterator<Item = f64>) -> Self {
+        Self::from_sorted_upper_bounds(sorted_upper_bounds(buckets), false)
+    }
+
+    /// Create a new [`Histogram`] sharing the given bucket upper bounds
+    /// instead of copying them, e.g. across all label sets of a wide
+    /// [`Family`](crate::metrics::family::Family), see
+    /// [`HistogramConstructor`].
+    ///
+    /// The upper bounds should be sorted in ascending order. Otherwise a
+    /// sorted copy is used, as with [`Histogram::new`].
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::Histogram;
+    /// # use std::sync::Arc;
+    /// let upper_bounds: Arc<[f64]> = Arc::from(vec![1.0, 5.0]);
+    /// let a = Histogram::new_with_shared_buckets(upper_bounds.clone());
+    /// let b = Histogram::new_with_shared_buckets(upper_bounds);
+    /// assert_eq!(a.buckets().as_ptr(), b.buckets().as_ptr());
+    /// ```
+    pub fn new_with_shared_buckets(upper_bounds: Arc<[f64]>) -> Self {
+        if upper_bounds.windows(2).all(|w| w[0] <= w[1]) {
+            Self::from_sorted_upper_bounds(upper_bounds, false)
+        } else {
+            Self::new(upper_bounds.iter().cloned())
+        }
+    }
+
+    fn from_sorted_upper_bounds(upper_bounds: Arc<[f64]>, reject_negative: bool) -> Self {
         Self {
             inner: Arc::new(Mutex::new(Inner {
                 sum: Default::default(),
                 count: Default::default(),
-                buckets: buckets
-                    .into_iter()
-                    .chain(once(f64::MAX))
-                    .map(|upper_bound| (upper_bound, 0))
-                    .collect(),
+                buckets: vec![0; upper_bounds.len() + 1],
+                reject_negative,
             })),
+            upper_bounds,
         }
     }
 
+    /// Reject negative values, e.g. for histograms of durations or sizes
+    /// where a negative value indicates a bug. See [`Histogram::observe`].
+    ///
+    /// The setting is shared with all clones of the [`Histogram`], including
+    /// those created before. See [`HistogramConstructor::reject_negative`] for
+    /// the [`Histogram`]s of a [`Family`](crate::metrics::family::Family).
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::{exponential_buckets, Histogram, ObserveError};
+    /// let histogram = Histogram::new(exponential_buckets(1.0, 2.0, 10)).reject_negative();
+    ///
+    /// assert_eq!(Err(ObserveError::Negative(-1.0)), histogram.try_observe(-1.0));
+    /// assert_eq!(0, histogram.count());
+    /// ```
+    pub fn reject_negative(self) -> Self {
+        self.inner.lock().unwrap().reject_negative = true;
+        self
+    }
+
+    /// Create a new [`Histogram`] with the given bucket upper bounds, failing
+    /// if they are not finite and strictly ascending.
+    ///
+    /// In contrast to [`Histogram::new`], the upper bounds are not sorted,
+    /// surfacing e.g. misconfigured buckets at startup.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::{BucketError, Histogram};
+    /// assert!(Histogram::try_new(vec![1.0, 5.0].into_iter()).is_ok());
+    /// assert_eq!(
+    ///     BucketError::Unsorted(1.0),
+    ///     Histogram::try_new(vec![5.0, 1.0].into_iter()).err().unwrap(),
+    /// );
+    /// ```
+    pub fn try_new(buckets: impl Iterator<Item = f64>) -> Result<Self, BucketError> {
+        let buckets: Vec<f64> = buckets.collect();
+        validate_buckets(&buckets)?;
+        Ok(Self::new(buckets.into_iter()))
+    }
+
+    /// Get the bucket upper bounds in ascending order, excluding the implicit
+    /// `+Inf` bucket.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::Histogram;
+    /// let histogram = Histogram::new(vec![5.0, 1.0].into_iter());
+    /// assert_eq!(&[1.0, 5.0], histogram.buckets());
+    /// ```
+    pub fn buckets(&self) -> &[f64] {
+        &self.upper_bounds
+    }
+
+    /// Observe the given value.
+    ///
+    /// `NaN` values, as well as negative values if configured via
+    /// [`Histogram::reject_negative`], are ignored, keeping the sum and all
+    /// bucket counts unchanged. Use [`Histogram::try_observe`] to detect
+    /// them.
     pub fn observe(&self, v: f64) {
         self.observe_and_bucket(v);
     }
 
+    /// Like [`Histogram::observe`], but returning an error if the value is
+    /// rejected.
+    pub fn try_observe(&self, v: f64) -> Result<(), ObserveError> {
+        self.try_observe_and_bucket(v).map(|_| ())
+    }
+
+    /// Observe the given [`Duration`] in seconds, the base unit of time
+    /// recommended by Open Metrics, see
+    /// [`Unit::Seconds`](crate::registry::Unit::Seconds).
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::{Histogram, exponential_buckets};
+    /// # use std::time::Duration;
+    /// let histogram = Histogram::new(exponential_buckets(0.005, 2.0, 10));
+    /// histogram.observe_duration(Duration::from_millis(250));
+    /// assert_eq!(0.25, histogram.sum());
+    /// ```
+    pub fn observe_duration(&self, d: Duration) {
+        self.observe(d.as_secs_f64());
+    }
+
+    /// Start a [`Timer`] observing the elapsed time in seconds into this
+    /// [`Histogram`] once it is dropped or stopped explicitly.
+    ///
+    /// The elapsed time is measured with the monotonic [`Instant`] clock.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::{Histogram, exponential_buckets};
+    /// let histogram = Histogram::new(exponential_buckets(0.005, 2.0, 10));
+    ///
+    /// let timer = histogram.start_timer();
+    /// // Do some work.
+    /// let elapsed = timer.stop_and_record();
+    ///
+    /// assert_eq!(1, histogram.count());
+    /// assert_eq!(elapsed, histogram.sum());
+    /// ```
+    pub fn start_timer(&self) -> Timer {
+        Timer {
+            histogram: Some(self.clone()),
+            start: Instant::now(),
+        }
+    }
+
     /// Observes the given value, returning the index of the first bucket the
     /// value is added to.
     ///
     /// Needed in
     /// [`HistogramWithExemplars`](crate::metrics::exemplar::HistogramWithExemplars).
     pub(crate) fn observe_and_bucket(&self, v: f64) -> Option<usize> {
+        self.try_observe_and_bucket(v).ok()
+    }
+
+    fn try_observe_and_bucket(&self, v: f64) -> Result<usize, ObserveError> {
+        if v.is_nan() {
+            return Err(ObserveError::NaN);
+        }
+
+        // Falls back to the `+Inf` bucket.
+        let i = self
+            .upper_bounds
+            .iter()
+            .position(|upper_bound| *upper_bound >= v)
+            .unwrap_or(self.upper_bounds.len());
+
         let mut inner = self.inner.lock().unwrap();
+        if inner.reject_negative && v < 0.0 {
+            return Err(ObserveError::Negative(v));
+        }
         inner.sum += v;
         inner.count += 1;
+        inner.buckets[i] += 1;
+
+        Ok(i)
+    }
+
+    /// Get the sum of all observed values.
+    pub fn sum(&self) -> f64 {
+        self.inner.lock().unwrap().sum
+    }
+
+    /// Get the number of observed values.
+    pub fn count(&self) -> u64 {
+        self.inner.lock().unwrap().count
+    }
+
+    /// Get the cumulative count of each bucket keyed by its upper bound, i.e.
+    /// the number of observed values less than or equal to the upper bound.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::histogram::Histogram;
+    /// let histogram = Histogram::new(vec![1.0, 5.0].into_iter());
+    /// histogram.observe(0.5);
+    /// histogram.observe(4.2);
+    ///
+    /// assert_eq!(
+    ///     vec![(1.0, 1), (5.0, 2), (f64::INFINITY, 2)],
+    ///     histogram.bucket_counts(),
+    /// );
+    /// ```
+    pub fn bucket_counts(&self) -> Vec<(f64, u64)> {
+        let (_sum, _count, buckets) = self.get();
+        buckets
+            .iter()
+            .scan(0, |cumulative, (upper_bound, count)| {
+                *cumulative += count;
+                Some((upper_bound, *cumulative))
+            })
+            .collect()
+    }
 
-        let first_bucket = inner
-            .buckets
-            .iter_mut()
-            .enumerate()
-            .find(|(_i, (upper_bound, _value))| upper_bound >= &v);
-
-        match first_bucket {
-            Some((i, (_upper_bound, value))) => {
-                *value += 1;
-                Some(i)
-            }
-            None => None,
+    /// Reset the sum, the count and all buckets of the [`Histogram`] to zero.
+    ///
+    /// Note: This is intended for test harnesses only, see
+    /// [`Counter::reset`](crate::metrics::counter::Counter::reset).
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.sum = 0.0;
+        inner.count = 0;
+        for count in inner.buckets.iter_mut() {
+            *count = 0;
         }
     }
 
-    pub(crate) fn get(&self) -> (f64, u64, MutexGuardedBuckets) {
+    pub(crate) fn get(&self) -> (f64, u64, Buckets<'_>) {
         let inner = self.inner.lock().unwrap();
         let sum = inner.sum;
         let count = inner.count;
-        let buckets = OwningRef::new(inner).map(|inner| &inner.buckets);
+        let buckets = Buckets {
+            upper_bounds: &self.upper_bounds,
+            inner,
+        };
         (sum, count, buckets)
     }
 }
 
-pub(crate) type MutexGuardedBuckets<'a> = OwningRef<MutexGuard<'a, Inner>, Vec<(f64, u64)>>;
+/// The buckets of a [`Histogram`], holding its lock.
+pub(crate) struct Buckets<'a> {
+    upper_bounds: &'a [f64],
+    inner: MutexGuard<'a, Inner>,
+}
+
+impl<'a> Buckets<'a> {
+    /// The upper bound and the non-cumulative count of each bucket, in
+    /// ascending order and ending with the `+Inf` bucket.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.upper_bounds
+            .iter()
+            .cloned()
+            .chain(once(f64::INFINITY))
+            .zip(self.inner.buckets.iter().cloned())
+    }
+}
+
+/// Timer returned by [`Histogram::start_timer`], observing the seconds elapsed
+/// since its creation into the [`Histogram`] exactly once, either via
+/// [`Timer::stop_and_record`], [`Timer::observe_duration`] or on drop.
+#[must_use = "The duration is observed as soon as the timer is dropped."]
+pub struct Timer {
+    histogram: Option<Histogram>,
+    start: Instant,
+}
+
+impl Timer {
+    /// Observe the elapsed time in seconds, returning the observed value.
+    pub fn stop_and_record(mut self) -> f64 {
+        self.record()
+    }
+
+    /// Observe the elapsed time in seconds.
+    pub fn observe_duration(self) {
+        self.stop_and_record();
+    }
+
+    fn record(&mut self) -> f64 {
+        let elapsed = self.start.elapsed();
+        if let Some(histogram) = self.histogram.take() {
+            histogram.observe_duration(elapsed);
+        }
+        elapsed.as_secs_f64()
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.record();
+    }
+}
+
+/// Error returned by [`Histogram::try_observe`] for a rejected value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObserveError {
+    /// The value is `NaN`.
+    NaN,
+    /// The value is negative, while the [`Histogram`] rejects negative values.
+    Negative(f64),
+}
+
+impl std::fmt::Display for ObserveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObserveError::NaN => write!(f, "Observed value is NaN."),
+            ObserveError::Negative(v) => write!(f, "Observed value {} is negative.", v),
+        }
+    }
+}
+
+impl std::error::Error for ObserveError {}
+
+/// Error returned by [`Histogram::try_new`] and
+/// [`HistogramConstructor::try_new`] for invalid bucket upper bounds, naming
+/// the offending upper bound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BucketError {
+    /// The upper bound is `NaN` or infinite. Note that the `+Inf` bucket is
+    /// appended automatically.
+    NonFinite(f64),
+    /// The upper bound equals its predecessor.
+    Duplicate(f64),
+    /// The upper bound is smaller than its predecessor.
+    Unsorted(f64),
+}
+
+impl std::fmt::Display for BucketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BucketError::NonFinite(v) => write!(f, "Bucket upper bound {} is not finite.", v),
+            BucketError::Duplicate(v) => write!(f, "Bucket upper bound {} is duplicated.", v),
+            BucketError::Unsorted(v) => write!(
+                f,
+                "Bucket upper bound {} is smaller than its predecessor.",
+                v
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BucketError {}
+
+fn validate_buckets(upper_bounds: &[f64]) -> Result<(), BucketError> {
+    let mut previous: Option<f64> = None;
+    for upper_bound in upper_bounds.iter().cloned() {
+        if !upper_bound.is_finite() {
+            return Err(BucketError::NonFinite(upper_bound));
+        }
+        match previous {
+            Some(p) if upper_bound == p => return Err(BucketError::Duplicate(upper_bound)),
+            Some(p) if upper_bound < p => return Err(BucketError::Unsorted(upper_bound)),
+            _ => {}
+        }
+        previous = Some(upper_bound);
+    }
+    Ok(())
+}
+
+/// Collects the given upper bounds sorted in ascending order.
+fn sorted_upper_bounds(upper_bounds: impl Iterator<Item = f64>) -> Arc<[f64]> {
+    let mut upper_bounds: Vec<f64> = upper_bounds.collect();
+    upper_bounds.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    upper_bounds.into()
+}
+
+/// Sorts the given upper bounds in ascending order and appends the `+Inf`
+/// bucket, each with a count of zero.
+///
+/// Shared with
+/// [`GaugeHistogram`](crate::metrics::gauge_histogram::GaugeHistogram).
+pub(crate) fn empty_buckets(upper_bounds: impl Iterator<Item = f64>) -> Vec<(f64, u64)> {
+    let mut upper_bounds: Vec<f64> = upper_bounds.collect();
+    upper_bounds.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    upper_bounds
+        .into_iter()
+        .chain(once(f64::INFINITY))
+        .map(|upper_bound| (upper_bound, 0))
+        .collect()
+}
 
 impl TypedMetric for Histogram {
     const TYPE: MetricType = MetricType::Histogram;
 }
 
+impl Aggregate for Histogram {
+    fn count(&self) -> u64 {
+        Histogram::count(self)
+    }
+
+    fn sum(&self) -> f64 {
+        Histogram::sum(self)
+    }
+}
+
+/// [`MetricConstructor`] creating [`Histogram`]s with the same bucket upper
+/// bounds, e.g. for every label set of a
+/// [`Family`](crate::metrics::family::Family).
+///
+/// All [`Histogram`]s created share a single allocation of the upper bounds,
+/// see [`Histogram::new_with_shared_buckets`].
+///
+/// ```
+/// # use prometheus_client::metrics::family::Family;
+/// # use prometheus_client::metrics::histogram::{exponential_buckets, Histogram, HistogramConstructor};
+/// let family = Family::<Vec<(String, String)>, Histogram, _>::new_with_constructor(
+///     HistogramConstructor::new(exponential_buckets(0.005, 2.0, 10)),
+/// );
+/// family
+///     .get_or_create(&vec![("route".to_owned(), "/".to_owned())])
+///     .observe(0.1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HistogramConstructor {
+    upper_bounds: Arc<[f64]>,
+    reject_negative: bool,
+}
+
+impl HistogramConstructor {
+    /// Create a [`HistogramConstructor`] with the given bucket upper bounds.
+    /// See [`Histogram::new`].
+    pub fn new(buckets: impl Iterator<Item = f64>) -> Self {
+        Self {
+            upper_bounds: sorted_upper_bounds(buckets),
+            reject_negative: false,
+        }
+    }
+
+    /// Create a [`HistogramConstructor`] with the given shared bucket upper
+    /// bounds. See [`Histogram::new_with_shared_buckets`].
+    pub fn new_with_shared_buckets(upper_bounds: Arc<[f64]>) -> Self {
+        Self {
+            upper_bounds: Histogram::new_with_shared_buckets(upper_bounds).upper_bounds,
+            reject_negative: false,
+        }
+    }
+
+    /// Create a [`HistogramConstructor`] with the given bucket upper bounds,
+    /// failing if they are invalid. See [`Histogram::try_new`].
+    pub fn try_new(buckets: impl Iterator<Item = f64>) -> Result<Self, BucketError> {
+        let buckets: Vec<f64> = buckets.collect();
+        validate_buckets(&buckets)?;
+        Ok(Self {
+            upper_bounds: buckets.into(),
+            reject_negative: false,
+        })
+    }
+
+    /// Create [`Histogram`]s rejecting negative values, see
+    /// [`Histogram::reject_negative`].
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::family::{Family, MetricConstructor};
+    /// # use prometheus_client::metrics::histogram::{Histogram, HistogramConstructor, ObserveError};
+    /// let constructor = HistogramConstructor::new(vec![1.0].into_iter()).reject_negative();
+    /// let histogram = constructor.new_metric();
+    ///
+    /// assert_eq!(Err(ObserveError::Negative(-1.0)), histogram.try_observe(-1.0));
+    /// ```
+    pub fn reject_negative(mut self) -> Self {
+        self.reject_negative = true;
+        self
+    }
+
+    /// The bucket upper bounds of the [`Histogram`]s created, in ascending
+    /// order.
+    pub fn buckets(&self) -> &[f64] {
+        &self.upper_bounds
+    }
+}
+
+impl MetricConstructor<Histogram> for HistogramConstructor {
+    fn new_metric(&self) -> Histogram {
+        Histogram::from_sorted_upper_bounds(self.upper_bounds.clone(), self.reject_negative)
+    }
+}
+
 pub fn exponential_buckets(start: f64, factor: f64, length: u16) -> impl Iterator<Item = f64> {
     iter::repeat(())
         .enumerate()
@@ -112,6 +543,7 @@ pub fn linear_buckets(start: f64, width: f64, length: u16) -> impl Iterator<Item
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metrics::family::Family;
 
     #[test]
     fn histogram() {
@@ -119,6 +551,173 @@ mod tests {
         histogram.observe(1.0);
     }
 
+    #[test]
+    fn unsorted_buckets() {
+        let histogram = Histogram::new(vec![10.0, 1.0, 5.0].into_iter());
+        histogram.observe(4.2);
+        histogram.observe(f64::INFINITY);
+
+        assert_eq!(&[1.0, 5.0, 10.0], histogram.buckets());
+
+        let (_sum, count, buckets) = histogram.get();
+        assert_eq!(2, count);
+        assert_eq!(
+            vec![(1.0, 0), (5.0, 1), (10.0, 0), (f64::INFINITY, 1)],
+            buckets.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn constructor_shares_buckets() {
+        let constructor = HistogramConstructor::new(vec![5.0, 1.0].into_iter());
+        let a = constructor.new_metric();
+        let b = constructor.new_metric();
+        assert_eq!(&[1.0, 5.0], a.buckets());
+        assert_eq!(a.buckets().as_ptr(), b.buckets().as_ptr());
+
+        a.observe(2.0);
+        assert_eq!(
+            vec![(1.0, 0), (5.0, 1), (f64::INFINITY, 1)],
+            a.bucket_counts()
+        );
+        assert_eq!(0, b.count());
+
+        // Unsorted shared upper bounds are copied.
+        let upper_bounds: Arc<[f64]> = Arc::from(vec![5.0, 1.0]);
+        let histogram = Histogram::new_with_shared_buckets(upper_bounds.clone());
+        assert_eq!(&[1.0, 5.0], histogram.buckets());
+        assert_ne!(upper_bounds.as_ptr(), histogram.buckets().as_ptr());
+    }
+
+    #[test]
+    fn observe_ignores_nan() {
+        let histogram = Histogram::new(vec![1.0, 5.0].into_iter());
+        histogram.observe(4.2);
+        histogram.observe(f64::NAN);
+
+        assert_eq!(Err(ObserveError::NaN), histogram.try_observe(f64::NAN));
+        assert_eq!(4.2, histogram.sum());
+        assert_eq!(1, histogram.count());
+        assert_eq!(
+            vec![(1.0, 0), (5.0, 1), (f64::INFINITY, 1)],
+            histogram.bucket_counts()
+        );
+    }
+
+    #[test]
+    fn reject_negative() {
+        let histogram = Histogram::new(vec![1.0].into_iter());
+        assert_eq!(Ok(()), histogram.try_observe(-1.0));
+        assert_eq!(1, histogram.count());
+
+        let histogram = Histogram::new(vec![1.0].into_iter()).reject_negative();
+        histogram.observe(-1.0);
+        assert_eq!(
+            Err(ObserveError::Negative(-0.5)),
+            histogram.try_observe(-0.5)
+        );
+        assert_eq!(Ok(()), histogram.try_observe(0.0));
+        assert_eq!(1, histogram.count());
+        assert_eq!(0.0, histogram.sum());
+    }
+
+    #[test]
+    fn reject_negative_applies_to_earlier_clones() {
+        let histogram = Histogram::new(vec![1.0].into_iter());
+        let clone = histogram.clone();
+        let _histogram = histogram.reject_negative();
+
+        assert_eq!(Err(ObserveError::Negative(-1.0)), clone.try_observe(-1.0));
+        assert_eq!(0, clone.count());
+    }
+
+    #[test]
+    fn histogram_constructor_reject_negative() {
+        let family = Family::<(), Histogram, _>::new_with_constructor(
+            HistogramConstructor::new(vec![1.0].into_iter()).reject_negative(),
+        );
+
+        family.get_or_create(&()).observe(-1.0);
+        assert_eq!(0, family.get_or_create(&()).count());
+    }
+
+    #[test]
+    fn try_new_rejects_invalid_buckets() {
+        assert!(Histogram::try_new(exponential_buckets(1.0, 2.0, 10)).is_ok());
+        assert!(Histogram::try_new(iter::empty()).is_ok());
+
+        assert_eq!(
+            Some(BucketError::Duplicate(2.0)),
+            Histogram::try_new(vec![1.0, 2.0, 2.0].into_iter()).err()
+        );
+        assert_eq!(
+            Some(BucketError::Unsorted(1.0)),
+            Histogram::try_new(vec![2.0, 1.0].into_iter()).err()
+        );
+        assert_eq!(
+            Some(BucketError::NonFinite(f64::INFINITY)),
+            Histogram::try_new(vec![1.0, f64::INFINITY].into_iter()).err()
+        );
+        assert!(matches!(
+            HistogramConstructor::try_new(vec![1.0, f64::NAN].into_iter()),
+            Err(BucketError::NonFinite(v)) if v.is_nan()
+        ));
+    }
+
+    #[test]
+    fn accessors_and_reset() {
+        let histogram = Histogram::new(vec![1.0, 5.0].into_iter());
+        histogram.observe(0.5);
+        histogram.observe(4.5);
+        histogram.observe(10.0);
+
+        assert_eq!(15.0, histogram.sum());
+        assert_eq!(3, histogram.count());
+        assert_eq!(
+            vec![(1.0, 1), (5.0, 2), (f64::INFINITY, 3)],
+            histogram.bucket_counts()
+        );
+
+        histogram.reset();
+        assert_eq!(0.0, histogram.sum());
+        assert_eq!(0, histogram.count());
+        assert_eq!(
+            vec![(1.0, 0), (5.0, 0), (f64::INFINITY, 0)],
+            histogram.bucket_counts()
+        );
+    }
+
+    #[test]
+    fn timer() {
+        let histogram = Histogram::new(exponential_buckets(0.005, 2.0, 10));
+
+        let elapsed = histogram.start_timer().stop_and_record();
+        assert!(elapsed >= 0.0);
+        assert_eq!(1, histogram.count());
+        assert_eq!(elapsed, histogram.sum());
+
+        histogram.start_timer().observe_duration();
+        assert_eq!(2, histogram.count());
+
+        {
+            let _timer = histogram.start_timer();
+        }
+        assert_eq!(3, histogram.count());
+    }
+
+    #[test]
+    fn observe_duration() {
+        let histogram = Histogram::new(vec![0.1, 1.0].into_iter());
+        histogram.observe_duration(Duration::from_millis(250));
+        histogram.observe_duration(Duration::from_micros(1_500_000));
+
+        assert_eq!(1.75, histogram.sum());
+        assert_eq!(
+            vec![(0.1, 0), (1.0, 1), (f64::INFINITY, 1)],
+            histogram.get().2.iter().collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn exponential() {
         assert_eq!(
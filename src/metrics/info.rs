@@ -7,6 +7,11 @@ use crate::metrics::{MetricType, TypedMetric};
 /// Open Metrics [`Info`] metric "to expose textual information which SHOULD NOT
 /// change during process lifetime".
 ///
+/// An [`Info`] metric always has the value `1`, with the information carried
+/// by its label set. The `_info` suffix is appended on encoding and thus
+/// should not be part of the registered name. A trailing `_info` in the
+/// registered name is stripped for the metric family name.
+///
 /// ```
 /// # use prometheus_client::metrics::info::Info;
 ///
@@ -0,0 +1,396 @@
+//! Module implementing a Prometheus native histogram.
+//!
+//! See [`NativeHistogram`] for details.
+
+use super::family::Aggregate;
+use super::{MetricType, TypedMetric};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Smallest supported schema, i.e. a bucket growth factor of 65536.
+pub const MIN_SCHEMA: i8 = -4;
+
+/// Largest supported schema, i.e. a bucket growth factor of about 1.0027.
+pub const MAX_SCHEMA: i8 = 8;
+
+/// Default limit of the number of populated buckets, see
+/// [`NativeHistogram::with_max_buckets`].
+pub const DEFAULT_MAX_BUCKETS: usize = 160;
+
+/// Prometheus [`NativeHistogram`] with sparse, exponentially growing buckets.
+///
+/// In contrast to a [`Histogram`](crate::metrics::histogram::Histogram), the
+/// buckets don't need to be configured upfront. Instead the bucket
+/// boundaries are given by the `schema`, the resolution of the
+/// [`NativeHistogram`]: The upper bound of the bucket with index `i` is
+/// `2^(i * 2^-schema)`, i.e. each bucket is `2^(2^-schema)` times as wide as
+/// its predecessor. Only buckets with observations are stored. Values with an
+/// absolute value of at most the zero threshold are counted in a dedicated
+/// zero bucket, negative values in buckets mirroring the positive ones.
+///
+/// The number of populated buckets is limited, see
+/// [`NativeHistogram::with_max_buckets`]. Once the limit is exceeded, the
+/// resolution is halved, i.e. the schema decreased by one, merging each pair
+/// of neighbouring buckets.
+///
+/// The OpenMetrics text format has no representation of native histograms.
+/// [`encode`](crate::encoding::text::encode) thus exposes a
+/// [`NativeHistogram`] as a classic histogram, with one `le` bucket per
+/// populated sparse bucket as well as the zero bucket. Note that the set of
+/// buckets, and thus of series, grows with the spread of the observed values,
+/// up to the bucket limit, and changes whenever the resolution is reduced.
+///
+/// ```
+/// # use prometheus_client::metrics::native_histogram::NativeHistogram;
+/// let histogram = NativeHistogram::new(0);
+/// histogram.observe(3.0);
+/// histogram.observe(4.0);
+/// histogram.observe(-0.5);
+/// histogram.observe(0.0);
+///
+/// // With schema 0, bucket 2 covers (2, 4].
+/// assert_eq!(vec![(2, 2)], histogram.positive_buckets());
+/// // Bucket -1 covers the negative values with an absolute value in (0.25, 0.5].
+/// assert_eq!(vec![(-1, 1)], histogram.negative_buckets());
+/// assert_eq!(1, histogram.zero_count());
+/// ```
+pub struct NativeHistogram {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Clone for NativeHistogram {
+    fn clone(&self) -> Self {
+        NativeHistogram {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct Inner {
+    schema: i8,
+    zero_threshold: f64,
+    max_buckets: usize,
+    sum: f64,
+    count: u64,
+    zero_count: u64,
+    /// Number of `-Inf` observations, counted in the lowest bucket.
+    negative_infinity_count: u64,
+    positive: BTreeMap<i32, u64>,
+    negative: BTreeMap<i32, u64>,
+}
+
+impl NativeHistogram {
+    /// Create a new [`NativeHistogram`] with the given schema and a zero
+    /// threshold of `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the schema is not within [`MIN_SCHEMA`] and [`MAX_SCHEMA`].
+    pub fn new(schema: i8) -> Self {
+        assert!(
+            (MIN_SCHEMA..=MAX_SCHEMA).contains(&schema),
+            "Native histogram schema {} not within [{}, {}].",
+            schema,
+            MIN_SCHEMA,
+            MAX_SCHEMA
+        );
+
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                schema,
+                zero_threshold: 0.0,
+                max_buckets: DEFAULT_MAX_BUCKETS,
+                sum: Default::default(),
+                count: Default::default(),
+                zero_count: Default::default(),
+                negative_infinity_count: Default::default(),
+                positive: Default::default(),
+                negative: Default::default(),
+            })),
+        }
+    }
+
+    /// Count values with an absolute value of at most `zero_threshold` in the
+    /// zero bucket instead of the regular buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `zero_threshold` is negative or not finite.
+    pub fn with_zero_threshold(self, zero_threshold: f64) -> Self {
+        assert!(
+            zero_threshold.is_finite() && zero_threshold >= 0.0,
+            "Native histogram zero threshold {} not finite and non-negative.",
+            zero_threshold
+        );
+        self.inner.lock().unwrap().zero_threshold = zero_threshold;
+        self
+    }
+
+    /// Limit the number of populated positive and negative buckets to
+    /// `max_buckets`, [`DEFAULT_MAX_BUCKETS`] by default.
+    ///
+    /// Each time an observation exceeds the limit, the resolution is halved,
+    /// down to [`MIN_SCHEMA`], at which point the limit is no longer enforced.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::native_histogram::NativeHistogram;
+    /// let histogram = NativeHistogram::new(3).with_max_buckets(4);
+    /// for v in 1..=100 {
+    ///     histogram.observe(v as f64);
+    /// }
+    ///
+    /// assert!(histogram.positive_buckets().len() <= 4);
+    /// assert!(histogram.schema() < 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_buckets` is zero.
+    pub fn with_max_buckets(self, max_buckets: usize) -> Self {
+        assert!(
+            max_buckets > 0,
+            "Native histogram bucket limit must not be zero."
+        );
+        self.inner.lock().unwrap().max_buckets = max_buckets;
+        self
+    }
+
+    /// Observe the given value.
+    ///
+    /// `NaN` values are ignored. `+Inf` is only counted in the `+Inf` bucket,
+    /// `-Inf` in the lowest bucket.
+    pub fn observe(&self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.sum += v;
+        inner.count += 1;
+
+        if v == f64::NEG_INFINITY {
+            inner.negative_infinity_count += 1;
+            return;
+        }
+        if !v.is_finite() {
+            return;
+        }
+
+        if v.abs() <= inner.zero_threshold {
+            inner.zero_count += 1;
+            return;
+        }
+
+        let index = bucket_index(inner.schema, v.abs());
+        let buckets = if v > 0.0 {
+            &mut inner.positive
+        } else {
+            &mut inner.negative
+        };
+        *buckets.entry(index).or_default() += 1;
+
+        while inner.positive.len() + inner.negative.len() > inner.max_buckets
+            && inner.schema > MIN_SCHEMA
+        {
+            inner.reduce_resolution();
+        }
+    }
+
+    pub fn schema(&self) -> i8 {
+        self.inner.lock().unwrap().schema
+    }
+
+    pub fn zero_threshold(&self) -> f64 {
+        self.inner.lock().unwrap().zero_threshold
+    }
+
+    /// Get the sum of all observed values.
+    pub fn sum(&self) -> f64 {
+        self.inner.lock().unwrap().sum
+    }
+
+    /// Get the number of observed values.
+    pub fn count(&self) -> u64 {
+        self.inner.lock().unwrap().count
+    }
+
+    /// Get the number of observed values in the zero bucket.
+    pub fn zero_count(&self) -> u64 {
+        self.inner.lock().unwrap().zero_count
+    }
+
+    /// Get the count of each populated bucket of positive values, keyed by
+    /// bucket index in ascending order.
+    pub fn positive_buckets(&self) -> Vec<(i32, u64)> {
+        let inner = self.inner.lock().unwrap();
+        inner.positive.iter().map(|(i, c)| (*i, *c)).collect()
+    }
+
+    /// Get the count of each populated bucket of negative values, keyed by
+    /// bucket index in ascending order, i.e. in ascending order of absolute
+    /// value.
+    pub fn negative_buckets(&self) -> Vec<(i32, u64)> {
+        let inner = self.inner.lock().unwrap();
+        inner.negative.iter().map(|(i, c)| (*i, *c)).collect()
+    }
+
+    /// Get the sum, the count and the non-cumulative count of each classic
+    /// histogram bucket, keyed by upper bound in ascending order and ending
+    /// with the `+Inf` bucket.
+    ///
+    /// The upper bound of a negative bucket is exclusive, contrary to the
+    /// inclusive upper bounds of classic histograms.
+    pub(crate) fn get(&self) -> (f64, u64, Vec<(f64, u64)>) {
+        let inner = self.inner.lock().unwrap();
+        let schema = inner.schema;
+
+        let mut buckets: Vec<(f64, u64)> = inner
+            .negative
+            .iter()
+            .rev()
+            .map(|(i, c)| (-upper_bound(schema, i - 1), *c))
+            .collect();
+        buckets.push((inner.zero_threshold, inner.zero_count));
+        buckets[0].1 += inner.negative_infinity_count;
+        buckets.extend(
+            inner
+                .positive
+                .iter()
+                .map(|(i, c)| (upper_bound(schema, *i), *c)),
+        );
+
+        let bucketed: u64 = buckets.iter().map(|(_, c)| c).sum();
+        buckets.push((f64::INFINITY, inner.count - bucketed));
+
+        (inner.sum, inner.count, buckets)
+    }
+}
+
+impl Inner {
+    /// Decrease the schema by one, merging each pair of neighbouring buckets.
+    fn reduce_resolution(&mut self) {
+        self.schema -= 1;
+        for buckets in [&mut self.positive, &mut self.negative] {
+            let mut merged = BTreeMap::new();
+            for (index, count) in buckets.iter() {
+                // Bucket `i` covers `(2^((i - 1) * 2^-s), 2^(i * 2^-s)]`, thus
+                // falls into bucket `ceil(i / 2)` with schema `s - 1`.
+                *merged.entry((index + 1).div_euclid(2)).or_default() += count;
+            }
+            *buckets = merged;
+        }
+    }
+}
+
+impl TypedMetric for NativeHistogram {
+    const TYPE: MetricType = MetricType::Histogram;
+}
+
+impl Aggregate for NativeHistogram {
+    fn count(&self) -> u64 {
+        NativeHistogram::count(self)
+    }
+
+    fn sum(&self) -> f64 {
+        NativeHistogram::sum(self)
+    }
+}
+
+/// The upper bound of the bucket with the given index, i.e.
+/// `2^(index * 2^-schema)`.
+fn upper_bound(schema: i8, index: i32) -> f64 {
+    2f64.powf(index as f64 / 2f64.powi(schema.into()))
+}
+
+/// The index of the bucket the given positive, finite value falls into, i.e.
+/// the smallest index with an upper bound of at least the value.
+fn bucket_index(schema: i8, v: f64) -> i32 {
+    let mut index = (v.log2() * 2f64.powi(schema.into())).ceil() as i32;
+
+    // Correct floating point errors of the logarithm at bucket boundaries.
+    while upper_bound(schema, index) < v {
+        index += 1;
+    }
+    while upper_bound(schema, index - 1) >= v {
+        index -= 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_boundaries() {
+        // Schema 0 doubles the bucket width, schema 1 multiplies it by √2.
+        assert_eq!(0, bucket_index(0, 1.0));
+        assert_eq!(1, bucket_index(0, 1.5));
+        assert_eq!(1, bucket_index(0, 2.0));
+        assert_eq!(-1, bucket_index(0, 0.5));
+        assert_eq!(2, bucket_index(1, 2.0));
+        assert_eq!(3, bucket_index(1, 2.5));
+        // Schema -1 quadruples the bucket width.
+        assert_eq!(1, bucket_index(-1, 3.0));
+        assert_eq!(2, bucket_index(-1, 5.0));
+
+        for schema in MIN_SCHEMA..=MAX_SCHEMA {
+            for v in [1e-9, 0.1, 1.0, 3.0, 1234.5, 1e12] {
+                let index = bucket_index(schema, v);
+                assert!(upper_bound(schema, index - 1) < v);
+                assert!(v <= upper_bound(schema, index));
+            }
+        }
+    }
+
+    #[test]
+    fn zero_bucket_and_classic_buckets() {
+        let histogram = NativeHistogram::new(0).with_zero_threshold(0.1);
+        for v in [0.05, -0.05, 0.0, 1.5, 3.0, 4.0, -3.0, f64::INFINITY] {
+            histogram.observe(v);
+        }
+
+        assert_eq!(3, histogram.zero_count());
+        assert_eq!(vec![(1, 1), (2, 2)], histogram.positive_buckets());
+        assert_eq!(vec![(2, 1)], histogram.negative_buckets());
+
+        let (_sum, count, buckets) = histogram.get();
+        assert_eq!(8, count);
+        assert_eq!(
+            vec![(-2.0, 1), (0.1, 3), (2.0, 1), (4.0, 2), (f64::INFINITY, 1)],
+            buckets
+        );
+    }
+
+    #[test]
+    fn reduce_resolution_beyond_max_buckets() {
+        let histogram = NativeHistogram::new(2).with_max_buckets(3);
+        for v in [1.0, 1.1, 1.3, 1.5, 1.7, 2.0, -1.5] {
+            histogram.observe(v);
+        }
+
+        assert_eq!(0, histogram.schema());
+        assert_eq!(vec![(0, 1), (1, 5)], histogram.positive_buckets());
+        assert_eq!(vec![(1, 1)], histogram.negative_buckets());
+        assert_eq!(7, histogram.count());
+    }
+
+    #[test]
+    fn ignore_nan_and_count_negative_infinity_in_lowest_bucket() {
+        let histogram = NativeHistogram::new(0);
+        for v in [f64::NAN, f64::NEG_INFINITY, 3.0, f64::INFINITY] {
+            histogram.observe(v);
+        }
+
+        assert_eq!(3, histogram.count());
+        let (_sum, _count, buckets) = histogram.get();
+        assert_eq!(vec![(0.0, 1), (4.0, 1), (f64::INFINITY, 1)], buckets);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_schema() {
+        NativeHistogram::new(9);
+    }
+}
@@ -0,0 +1,125 @@
+//! Module implementing an Open Metrics state set.
+//!
+//! See [`StateSet`] for details.
+
+use crate::metrics::{MetricType, TypedMetric};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub use prometheus_client_derive_text_encode::EnumLike;
+
+/// An enumeration of the possible states of a [`StateSet`].
+///
+/// Usually derived via `#[derive(EnumLike)]` on an enum with unit variants
+/// only, using the variant names as the state names.
+pub trait EnumLike: Copy + PartialEq + 'static {
+    /// All possible states, in encoding order.
+    fn variants() -> &'static [Self];
+
+    /// The name of the state, exposed as the label value of its sample.
+    fn name(&self) -> &'static str;
+}
+
+/// Open Metrics [`StateSet`] to expose which of a fixed set of states is
+/// currently active.
+///
+/// On encoding a [`StateSet`] exposes one sample per possible state, with
+/// value `1` for the active state and `0` for all others, using the metric
+/// name as the label name. A new [`StateSet`] starts in the first state
+/// returned by [`EnumLike::variants`].
+///
+/// ```
+/// # use prometheus_client::metrics::state_set::{EnumLike, StateSet};
+/// #[derive(Clone, Copy, Debug, PartialEq, EnumLike)]
+/// enum Connection {
+///     Down,
+///     Connecting,
+///     Up,
+/// }
+///
+/// let state_set = StateSet::<Connection>::default();
+/// state_set.set(Connection::Up);
+/// assert_eq!(Connection::Up, state_set.get());
+/// ```
+pub struct StateSet<T> {
+    state: Arc<AtomicUsize>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Clone for StateSet<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: EnumLike> Default for StateSet<T> {
+    fn default() -> Self {
+        assert!(
+            !T::variants().is_empty(),
+            "StateSet requires at least one state."
+        );
+
+        Self {
+            state: Arc::new(AtomicUsize::new(0)),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: EnumLike> StateSet<T> {
+    /// Sets the active state of the [`StateSet`].
+    pub fn set(&self, state: T) {
+        let index = T::variants()
+            .iter()
+            .position(|s| *s == state)
+            .expect("State to be returned by `EnumLike::variants`.");
+        self.state.store(index, Ordering::Relaxed);
+    }
+
+    /// Get the currently active state of the [`StateSet`].
+    pub fn get(&self) -> T {
+        T::variants()[self.state.load(Ordering::Relaxed)]
+    }
+}
+
+impl<T> TypedMetric for StateSet<T> {
+    const TYPE: MetricType = MetricType::StateSet;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Connection {
+        Down,
+        Up,
+    }
+
+    impl EnumLike for Connection {
+        fn variants() -> &'static [Self] {
+            &[Connection::Down, Connection::Up]
+        }
+
+        fn name(&self) -> &'static str {
+            match self {
+                Connection::Down => "down",
+                Connection::Up => "up",
+            }
+        }
+    }
+
+    #[test]
+    fn set_and_get() {
+        let state_set = StateSet::<Connection>::default();
+        assert_eq!(Connection::Down, state_set.get());
+
+        state_set.set(Connection::Up);
+        assert_eq!(Connection::Up, state_set.get());
+        assert_eq!(Connection::Up, state_set.clone().get());
+    }
+}
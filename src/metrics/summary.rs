@@ -0,0 +1,336 @@
+//! Module implementing an Open Metrics summary.
+//!
+//! See [`Summary`] for details.
+
+use super::family::Aggregate;
+use super::{MetricType, TypedMetric};
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+
+/// Open Metrics [`Summary`] to measure distributions of discrete events,
+/// exposing client-side computed quantiles.
+///
+/// Quantiles are estimated via the targeted quantiles algorithm by Cormode,
+/// Korn, Muthukrishnan and Srivastava ("Effective Computation of Biased
+/// Quantiles over Data Streams"), guaranteeing an estimate of the φ-quantile
+/// to have a rank within `φ ± epsilon`. Quantiles are computed over all
+/// observations since the creation of the [`Summary`].
+///
+/// Prefer a [`Histogram`](crate::metrics::histogram::Histogram) where
+/// possible, as quantiles of a [`Summary`] can not be aggregated across
+/// instances.
+///
+/// ```
+/// # use prometheus_client::metrics::summary::Summary;
+/// let summary = Summary::new(vec![0.5, 0.9, 0.99], 0.01);
+/// summary.observe(4.2);
+/// assert_eq!(4.2, summary.quantile(0.5));
+/// ```
+pub struct Summary {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Clone for Summary {
+    fn clone(&self) -> Self {
+        Summary {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct Inner {
+    sum: f64,
+    count: u64,
+    quantiles: Vec<f64>,
+    stream: Stream,
+}
+
+impl Summary {
+    /// Create a new [`Summary`] exposing the given φ-quantiles, each estimated
+    /// with a rank error of at most `epsilon`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a quantile is not within `[0, 1]` or `epsilon` not within
+    /// `(0, 1)`.
+    pub fn new(quantiles: impl IntoIterator<Item = f64>, epsilon: f64) -> Self {
+        assert!(
+            epsilon > 0.0 && epsilon < 1.0,
+            "Summary epsilon {} not within (0, 1).",
+            epsilon
+        );
+
+        let mut quantiles: Vec<f64> = quantiles.into_iter().collect();
+        for q in quantiles.iter() {
+            assert!(
+                (0.0..=1.0).contains(q),
+                "Summary quantile {} not within [0, 1].",
+                q
+            );
+        }
+        quantiles.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        quantiles.dedup();
+
+        let targets = quantiles.iter().map(|q| (*q, epsilon)).collect();
+
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                sum: Default::default(),
+                count: Default::default(),
+                quantiles,
+                stream: Stream::new(targets),
+            })),
+        }
+    }
+
+    pub fn observe(&self, v: f64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.sum += v;
+        inner.count += 1;
+        inner.stream.insert(v);
+    }
+
+    /// Get the sum of all observed values.
+    pub fn sum(&self) -> f64 {
+        self.inner.lock().unwrap().sum
+    }
+
+    /// Get the number of observed values.
+    pub fn count(&self) -> u64 {
+        self.inner.lock().unwrap().count
+    }
+
+    /// Get the current estimate of the φ-quantile, or `NaN` if nothing has
+    /// been observed yet.
+    ///
+    /// Only the quantiles passed to [`Summary::new`] are subject to the
+    /// error guarantee.
+    pub fn quantile(&self, q: f64) -> f64 {
+        self.inner.lock().unwrap().stream.query(q)
+    }
+
+    pub(crate) fn get(&self) -> (f64, u64, Vec<(f64, f64)>) {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner {
+            sum,
+            count,
+            quantiles,
+            stream,
+        } = &mut *inner;
+        let quantiles = quantiles.iter().map(|q| (*q, stream.query(*q))).collect();
+        (*sum, *count, quantiles)
+    }
+}
+
+impl TypedMetric for Summary {
+    const TYPE: MetricType = MetricType::Summary;
+}
+
+impl Aggregate for Summary {
+    fn count(&self) -> u64 {
+        Summary::count(self)
+    }
+
+    fn sum(&self) -> f64 {
+        Summary::sum(self)
+    }
+}
+
+/// Number of observations buffered before merging them into the [`Stream`].
+const BUFFER_CAPACITY: usize = 500;
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    value: f64,
+    /// Difference between the lowest possible rank of this and the previous
+    /// sample.
+    width: f64,
+    /// Difference between the highest and lowest possible rank of this
+    /// sample.
+    delta: f64,
+}
+
+/// Targeted quantiles stream, buffering observations and merging them in
+/// batches into a compressed list of samples.
+struct Stream {
+    /// Targeted quantiles along with their allowed error.
+    targets: Vec<(f64, f64)>,
+    buffer: Vec<f64>,
+    samples: Vec<Sample>,
+    /// Number of observations merged into `samples`.
+    n: f64,
+}
+
+impl Stream {
+    fn new(targets: Vec<(f64, f64)>) -> Self {
+        Self {
+            targets,
+            buffer: Vec::with_capacity(BUFFER_CAPACITY),
+            samples: Vec::new(),
+            n: 0.0,
+        }
+    }
+
+    fn insert(&mut self, v: f64) {
+        self.buffer.push(v);
+        if self.buffer.len() == BUFFER_CAPACITY {
+            self.flush();
+        }
+    }
+
+    fn query(&mut self, q: f64) -> f64 {
+        if self.samples.is_empty() {
+            // Fast path as long as nothing has been merged, yielding exact
+            // results for small sets of observations.
+            if self.buffer.is_empty() {
+                return f64::NAN;
+            }
+            sort(&mut self.buffer);
+            let i = ((self.buffer.len() as f64 * q).ceil() as usize).max(1) - 1;
+            return self.buffer[i.min(self.buffer.len() - 1)];
+        }
+
+        self.flush();
+
+        let mut t = (q * self.n).ceil();
+        t += (self.invariant(t) / 2.0).ceil();
+        let mut previous = self.samples[0];
+        let mut r = 0.0;
+        for sample in self.samples[1..].iter() {
+            r += previous.width;
+            if r + sample.width + sample.delta > t {
+                return previous.value;
+            }
+            previous = *sample;
+        }
+        previous.value
+    }
+
+    /// The maximum allowed `width + delta` of a sample at rank `r`.
+    fn invariant(&self, r: f64) -> f64 {
+        self.targets
+            .iter()
+            .map(|(q, epsilon)| {
+                if q * self.n <= r {
+                    (2.0 * epsilon * r) / q
+                } else {
+                    (2.0 * epsilon * (self.n - r)) / (1.0 - q)
+                }
+            })
+            .fold(f64::MAX, f64::min)
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        sort(&mut self.buffer);
+        let buffer = std::mem::take(&mut self.buffer);
+        self.merge(&buffer);
+        self.buffer = buffer;
+        self.buffer.clear();
+        self.compress();
+    }
+
+    /// Merges the given sorted values into the samples, rebuilding the
+    /// samples in a single pass.
+    fn merge(&mut self, values: &[f64]) {
+        let samples = std::mem::take(&mut self.samples);
+        let mut merged = Vec::with_capacity(samples.len() + values.len());
+        let mut samples = samples.into_iter().peekable();
+        let mut r = 0.0;
+        for value in values {
+            while let Some(sample) = samples.next_if(|sample| sample.value <= *value) {
+                r += sample.width;
+                merged.push(sample);
+            }
+
+            let delta = if merged.is_empty() || samples.peek().is_none() {
+                0.0
+            } else {
+                (self.invariant(r).floor() - 1.0).max(0.0)
+            };
+            merged.push(Sample {
+                value: *value,
+                width: 1.0,
+                delta,
+            });
+            self.n += 1.0;
+            r += 1.0;
+        }
+        merged.extend(samples);
+
+        self.samples = merged;
+    }
+
+    /// Merges adjacent samples as long as the invariant allows, rebuilding
+    /// the samples in a single pass.
+    fn compress(&mut self) {
+        if self.samples.len() < 2 {
+            return;
+        }
+
+        let samples = std::mem::take(&mut self.samples);
+        let mut compressed = Vec::with_capacity(samples.len());
+        let mut x = samples[samples.len() - 1];
+        let mut r = self.n - 1.0 - x.width;
+
+        for c in samples[..samples.len() - 1].iter().rev() {
+            if c.width + x.width + x.delta <= self.invariant(r) {
+                x.width += c.width;
+            } else {
+                compressed.push(x);
+                x = *c;
+            }
+            r -= c.width;
+        }
+        compressed.push(x);
+        compressed.reverse();
+
+        self.samples = compressed;
+    }
+}
+
+fn sort(values: &mut [f64]) {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_summary() {
+        let summary = Summary::new(vec![0.5], 0.01);
+        assert!(summary.quantile(0.5).is_nan());
+        assert_eq!(0, summary.count());
+    }
+
+    #[test]
+    fn quantiles_within_error() {
+        let epsilon = 0.01;
+        let summary = Summary::new(vec![0.5, 0.9, 0.99], epsilon);
+
+        // Observe a permutation of 1..=10_000.
+        let n = 10_000u64;
+        for i in 0..n {
+            summary.observe(((i * 7_919) % n + 1) as f64);
+        }
+
+        assert_eq!(n, summary.count());
+        assert_eq!((n * (n + 1) / 2) as f64, summary.sum());
+
+        // As the observations are 1..=n, the rank of an estimate is its value.
+        let (_sum, _count, quantiles) = summary.get();
+        assert_eq!(3, quantiles.len());
+        for (q, estimate) in quantiles {
+            assert!(
+                (estimate - (q * n as f64).ceil()).abs() <= epsilon * n as f64,
+                "Estimate {} of quantile {} not within error.",
+                estimate,
+                q
+            );
+        }
+    }
+}
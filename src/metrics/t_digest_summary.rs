@@ -0,0 +1,341 @@
+//! Module implementing an Open Metrics summary backed by a t-digest.
+//!
+//! See [`TDigestSummary`] for details.
+
+use super::family::Aggregate;
+use super::{MetricType, TypedMetric};
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+
+/// Open Metrics summary exposing client-side computed quantiles, estimated via
+/// a t-digest by Dunning and Ertl ("Computing Extremely Accurate Quantiles
+/// Using t-Digests").
+///
+/// In contrast to the [`Summary`](crate::metrics::summary::Summary), which
+/// bounds the rank error of a fixed set of quantiles, the t-digest bounds the
+/// error relative to `q * (1 - q)`, i.e. estimates of tail quantiles like the
+/// 0.999-quantile are particularly accurate, and any quantile can be queried
+/// via [`TDigestSummary::quantile`]. The quantiles to expose are chosen on
+/// creation. Quantiles are computed over all observations since the
+/// creation of the [`TDigestSummary`].
+///
+/// The memory used is bounded by the compression factor, see
+/// [`TDigestSummary::new`].
+///
+/// ```
+/// # use prometheus_client::metrics::t_digest_summary::TDigestSummary;
+/// let summary = TDigestSummary::new(vec![0.5, 0.99, 0.999], 100.0);
+/// for v in 1..=1000 {
+///     summary.observe(v as f64);
+/// }
+/// assert_eq!(1000, summary.count());
+/// assert!((summary.quantile(0.5) - 500.0).abs() < 5.0);
+/// ```
+pub struct TDigestSummary {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Clone for TDigestSummary {
+    fn clone(&self) -> Self {
+        TDigestSummary {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct Inner {
+    sum: f64,
+    count: u64,
+    quantiles: Vec<f64>,
+    digest: Digest,
+}
+
+impl TDigestSummary {
+    /// Create a new [`TDigestSummary`] exposing the given φ-quantiles.
+    ///
+    /// The compression factor trades memory for accuracy. The digest keeps
+    /// at most about `compression` centroids, e.g. 100 is a common choice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a quantile is not within `[0, 1]` or the compression factor
+    /// is not at least 1.
+    pub fn new(quantiles: impl IntoIterator<Item = f64>, compression: f64) -> Self {
+        assert!(
+            compression >= 1.0 && compression.is_finite(),
+            "TDigestSummary compression {} not at least 1.",
+            compression
+        );
+
+        let mut quantiles: Vec<f64> = quantiles.into_iter().collect();
+        for q in quantiles.iter() {
+            assert!(
+                (0.0..=1.0).contains(q),
+                "TDigestSummary quantile {} not within [0, 1].",
+                q
+            );
+        }
+        sort(&mut quantiles);
+        quantiles.dedup();
+
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                sum: Default::default(),
+                count: Default::default(),
+                quantiles,
+                digest: Digest::new(compression),
+            })),
+        }
+    }
+
+    /// Observe the given value. `NaN` values are ignored.
+    pub fn observe(&self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.sum += v;
+        inner.count += 1;
+        inner.digest.insert(v);
+    }
+
+    /// Get the sum of all observed values.
+    pub fn sum(&self) -> f64 {
+        self.inner.lock().unwrap().sum
+    }
+
+    /// Get the number of observed values.
+    pub fn count(&self) -> u64 {
+        self.inner.lock().unwrap().count
+    }
+
+    /// Get the current estimate of the φ-quantile, or `NaN` if nothing has
+    /// been observed yet.
+    pub fn quantile(&self, q: f64) -> f64 {
+        self.inner.lock().unwrap().digest.query(q)
+    }
+
+    pub(crate) fn get(&self) -> (f64, u64, Vec<(f64, f64)>) {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner {
+            sum,
+            count,
+            quantiles,
+            digest,
+        } = &mut *inner;
+        let quantiles = quantiles.iter().map(|q| (*q, digest.query(*q))).collect();
+        (*sum, *count, quantiles)
+    }
+}
+
+impl TypedMetric for TDigestSummary {
+    const TYPE: MetricType = MetricType::Summary;
+}
+
+impl Aggregate for TDigestSummary {
+    fn count(&self) -> u64 {
+        TDigestSummary::count(self)
+    }
+
+    fn sum(&self) -> f64 {
+        TDigestSummary::sum(self)
+    }
+}
+
+/// Number of observations buffered before merging them into the [`Digest`].
+const BUFFER_CAPACITY: usize = 500;
+
+#[derive(Clone, Copy, Debug)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Merging t-digest, buffering observations and merging them in batches into
+/// a list of centroids sorted by their mean.
+struct Digest {
+    compression: f64,
+    buffer: Vec<f64>,
+    centroids: Vec<Centroid>,
+    /// Total weight of `centroids`.
+    weight: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Digest {
+    fn new(compression: f64) -> Self {
+        Self {
+            compression,
+            buffer: Vec::with_capacity(BUFFER_CAPACITY),
+            centroids: Vec::new(),
+            weight: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn insert(&mut self, v: f64) {
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        self.buffer.push(v);
+        if self.buffer.len() == BUFFER_CAPACITY {
+            self.flush();
+        }
+    }
+
+    fn query(&mut self, q: f64) -> f64 {
+        self.flush();
+
+        let centroids = &self.centroids;
+        if centroids.is_empty() {
+            return f64::NAN;
+        }
+        if centroids.len() == 1 {
+            return centroids[0].mean;
+        }
+
+        // Each centroid's weight is assumed to be spread evenly around its
+        // mean, interpolating linearly between neighbouring means as well as
+        // the minimum and maximum at either end.
+        let index = q * self.weight;
+        let first = centroids[0];
+        if index < first.weight / 2.0 {
+            return self.min + (first.mean - self.min) * index / (first.weight / 2.0);
+        }
+
+        let mut weight = first.weight / 2.0;
+        for pair in centroids.windows(2) {
+            let (left, right) = (pair[0], pair[1]);
+            let step = (left.weight + right.weight) / 2.0;
+            if weight + step > index {
+                return left.mean + (right.mean - left.mean) * (index - weight) / step;
+            }
+            weight += step;
+        }
+
+        let last = centroids[centroids.len() - 1];
+        let fraction = ((index - weight) / (last.weight / 2.0)).min(1.0);
+        last.mean + (self.max - last.mean) * fraction
+    }
+
+    /// Merges the buffered observations into the centroids, limiting the
+    /// weight of each centroid via the `k1` scale function, i.e. allowing
+    /// heavier centroids around the median than at the tails.
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        self.weight += self.buffer.len() as f64;
+        let mut all: Vec<Centroid> = self
+            .centroids
+            .iter()
+            .copied()
+            .chain(self.buffer.drain(..).map(|v| Centroid {
+                mean: v,
+                weight: 1.0,
+            }))
+            .collect();
+        all.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+
+        let total = self.weight;
+        let mut merged = Vec::with_capacity(self.centroids.len());
+        let mut weight = 0.0;
+        let mut limit = total * self.q_limit(0.0);
+        let mut current = all[0];
+        for centroid in all[1..].iter() {
+            let proposed = current.weight + centroid.weight;
+            if weight + proposed <= limit {
+                current.mean += (centroid.mean - current.mean) * centroid.weight / proposed;
+                current.weight = proposed;
+            } else {
+                weight += current.weight;
+                merged.push(current);
+                limit = total * self.q_limit(weight / total);
+                current = *centroid;
+            }
+        }
+        merged.push(current);
+
+        self.centroids = merged;
+    }
+
+    /// The largest quantile a centroid starting at quantile `q` may cover,
+    /// i.e. `k⁻¹(k(q) + 1)` with `k(q) = δ / (2π) * asin(2q - 1)`.
+    fn q_limit(&self, q: f64) -> f64 {
+        let k = self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin() + 1.0;
+        let k = k.min(self.compression / 4.0);
+        ((2.0 * PI * k / self.compression).sin() + 1.0) / 2.0
+    }
+}
+
+fn sort(values: &mut [f64]) {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_summary() {
+        let summary = TDigestSummary::new(vec![0.5], 100.0);
+        assert!(summary.quantile(0.5).is_nan());
+        assert_eq!(0, summary.count());
+    }
+
+    #[test]
+    fn ignore_nan() {
+        let summary = TDigestSummary::new(vec![0.5], 100.0);
+        summary.observe(f64::NAN);
+        summary.observe(4.2);
+        assert_eq!(1, summary.count());
+        assert_eq!(4.2, summary.quantile(0.5));
+    }
+
+    #[test]
+    fn quantiles_within_error() {
+        let summary = TDigestSummary::new(vec![0.5, 0.9, 0.99, 0.999], 100.0);
+
+        // Observe a permutation of 1..=100_000.
+        let n = 100_000u64;
+        for i in 0..n {
+            summary.observe(((i * 7_919) % n + 1) as f64);
+        }
+
+        assert_eq!(n, summary.count());
+        assert_eq!((n * (n + 1) / 2) as f64, summary.sum());
+        assert_eq!(1.0, summary.quantile(0.0));
+        assert_eq!(n as f64, summary.quantile(1.0));
+
+        // As the observations are 1..=n, the rank of an estimate is its value.
+        // The error is bound relative to `q * (1 - q)`.
+        let (_sum, _count, quantiles) = summary.get();
+        assert_eq!(4, quantiles.len());
+        for (q, estimate) in quantiles {
+            let error = (estimate - q * n as f64).abs() / n as f64;
+            assert!(
+                error <= 0.2 * q * (1.0 - q),
+                "Estimate {} of quantile {} not within error.",
+                estimate,
+                q
+            );
+        }
+
+        let centroids = summary.inner.lock().unwrap().digest.centroids.len();
+        assert!(
+            centroids <= 100,
+            "{} centroids exceed compression.",
+            centroids
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "TDigestSummary compression 0 not at least 1.")]
+    fn invalid_compression() {
+        TDigestSummary::new(vec![0.5], 0.0);
+    }
+}
@@ -0,0 +1,62 @@
+//! Module implementing an Open Metrics unknown metric.
+//!
+//! See [`Unknown`] for details.
+
+use super::{MetricType, TypedMetric};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Open Metrics [`Unknown`] metric exposing a value of unknown type.
+///
+/// Intended for bridging values from third-party systems whose type can not
+/// be determined, instead of wrongly exposing them e.g. as a
+/// [`Gauge`](crate::metrics::gauge::Gauge).
+///
+/// ```
+/// # use prometheus_client::metrics::unknown::Unknown;
+/// let unknown = Unknown::default();
+/// unknown.set(4.2);
+/// assert_eq!(4.2, unknown.get());
+/// ```
+#[derive(Default)]
+pub struct Unknown {
+    value: Arc<AtomicU64>,
+}
+
+impl Clone for Unknown {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl Unknown {
+    /// Sets the [`Unknown`] metric to `v`, returning the previous value.
+    pub fn set(&self, v: f64) -> f64 {
+        f64::from_bits(self.value.swap(v.to_bits(), Ordering::Relaxed))
+    }
+
+    /// Get the current value of the [`Unknown`] metric.
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.value.load(Ordering::Relaxed))
+    }
+}
+
+impl TypedMetric for Unknown {
+    const TYPE: MetricType = MetricType::Unknown;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get() {
+        let unknown = Unknown::default();
+        assert_eq!(0.0, unknown.get());
+
+        assert_eq!(0.0, unknown.set(-1.5));
+        assert_eq!(-1.5, unknown.clone().get());
+    }
+}
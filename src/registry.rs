@@ -2,8 +2,18 @@
 //!
 //! See [`Registry`] for details.
 
+use crate::clock::{Clock, SystemClock};
+use crate::collector::Collector;
+use crate::encoding::snapshot::Snapshot;
+use crate::encoding::text::{EncodeFamilies, EncodeMetric};
+use crate::metrics::counter::Counter;
+use crate::metrics::gauge::Gauge;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ops::Add;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
 
 /// A metric registry.
 ///
@@ -15,8 +25,7 @@ use std::ops::Add;
 /// [`Registry`] is the core building block, generic over the metric type being
 /// registered. Out of convenience, the generic type parameter is set to use
 /// dynamic dispatching by default to be able to register different types of
-/// metrics (e.g. [`Counter`](crate::metrics::counter::Counter) and
-/// [`Gauge`](crate::metrics::gauge::Gauge)) with the same registry. Advanced
+/// metrics (e.g. [`Counter`] and [`Gauge`]) with the same registry. Advanced
 /// users might want to use their custom types.
 ///
 /// ```
@@ -62,7 +71,16 @@ pub struct Registry<M = Box<dyn crate::encoding::text::SendEncodeMetric>> {
     prefix: Option<Prefix>,
     labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
     metrics: Vec<(Descriptor, M)>,
+    collectors: Vec<Box<dyn Collector>>,
     sub_registries: Vec<Registry<M>>,
+    /// Whether [`Registry::register_with_unit`] requires names to end with
+    /// the unit, see [`RegistryBuilder::strict_units`].
+    strict_units: bool,
+    /// See [`Registry::enable_self_metrics`].
+    self_metrics: Option<SelfMetrics>,
+    /// Help text of each registered metric by full name, shared with all
+    /// sub-registries to detect duplicate registrations.
+    names: Arc<Mutex<HashMap<String, String>>>,
 }
 
 impl<M> Default for Registry<M> {
@@ -71,22 +89,84 @@ impl<M> Default for Registry<M> {
             prefix: None,
             labels: Default::default(),
             metrics: Default::default(),
+            collectors: vec![],
             sub_registries: vec![],
+            strict_units: false,
+            self_metrics: None,
+            names: Default::default(),
         }
     }
 }
 
+impl Registry {
+    /// Create a [`RegistryBuilder`] to configure a prefix and constant labels
+    /// of a new [`Registry`].
+    pub fn builder() -> RegistryBuilder {
+        RegistryBuilder::default()
+    }
+}
+
 impl<M> Registry<M> {
+    /// Create a new [`Registry`] prefixing the names of all metrics
+    /// registered with it or any of its sub-registries, e.g. to namespace all
+    /// metrics of a service.
+    ///
+    /// ```
+    /// # use prometheus_client::encoding::text::encode;
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry = Registry::with_prefix("my_service");
+    /// registry.register("requests", "Number of requests", Counter::<u64>::default());
+    /// registry
+    ///     .sub_registry_with_prefix("cache")
+    ///     .register("hits", "Number of cache hits", Counter::<u64>::default());
+    ///
+    /// # let mut buffer = vec![];
+    /// # encode(&mut buffer, &registry).unwrap();
+    /// # let encoded = String::from_utf8(buffer).unwrap();
+    /// # assert!(encoded.contains("my_service_requests_total 0\n"));
+    /// # assert!(encoded.contains("my_service_cache_hits_total 0\n"));
+    /// ```
+    pub fn with_prefix<P: AsRef<str>>(prefix: P) -> Self {
+        validate_metric_name(prefix.as_ref());
+        Self {
+            prefix: Some(prefix.as_ref().to_owned().into()),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new [`SharedRegistry`], allowing metrics to be registered via
+    /// `&self`, e.g. by background tasks after startup.
+    pub fn new_shared() -> Arc<SharedRegistry<M>> {
+        Arc::new(SharedRegistry::new(Registry::default()))
+    }
+
     /// Register a metric with the [`Registry`].
     ///
     /// Note: In the Open Metrics text exposition format some metric types have
     /// a special suffix, e.g. the
     /// [`Counter`](crate::metrics::counter::Counter`) metric with `_total`.
     /// These suffixes are inferred through the metric type and must not be
-    /// appended to the metric name manually by the user.
+    /// appended to the metric name manually by the user. For compatibility, a
+    /// `_total` suffix of a counter name is stripped from the metric family
+    /// name, i.e. `my_counter_total` is exposed the same as `my_counter`.
     ///
     /// Note: A full stop punctuation mark (`.`) is automatically added to the
-    /// passed help text.
+    /// passed help text. The help text should describe the metric, as an empty
+    /// one leaves users of the exposed metric without any description. With
+    /// the `tracing` feature enabled, registering an empty help text emits a
+    /// warning.
+    ///
+    /// Note: In debug builds this method panics if the name does not match
+    /// `[a-zA-Z_:][a-zA-Z0-9_:]*`, as Prometheus would reject the metric at
+    /// scrape time.
+    ///
+    /// Note: This method panics if a metric with the same full name, i.e.
+    /// including the prefixes of sub-registries and the unit, is already
+    /// registered with the [`Registry`] or any of its sub-registries. A
+    /// `_total` or `_info` suffix is ignored, as e.g. `my_counter` and
+    /// `my_counter_total` would expose the same metric family.
     ///
     /// Use [`Registry::register_with_unit`] whenever a unit for the given
     /// metric is known.
@@ -101,7 +181,33 @@ impl<M> Registry<M> {
     /// registry.register("my_counter", "This is my counter", counter.clone());
     /// ```
     pub fn register<N: Into<String>, H: Into<String>>(&mut self, name: N, help: H, metric: M) {
-        self.priv_register(name, help, metric, None)
+        self.priv_register(name, help, metric, None, vec![])
+    }
+
+    /// Like [`Registry::register`], but returning a [`RegisterError`] instead
+    /// of panicking if a metric with the same name is already registered.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry: Registry<Counter> = Registry::default();
+    /// registry
+    ///     .try_register("my_counter", "This is my counter", Counter::default())
+    ///     .unwrap();
+    ///
+    /// let error = registry
+    ///     .try_register("my_counter", "This is my counter", Counter::default())
+    ///     .unwrap_err();
+    /// assert_eq!("my_counter", error.name());
+    /// ```
+    pub fn try_register<N: Into<String>, H: Into<String>>(
+        &mut self,
+        name: N,
+        help: H,
+        metric: M,
+    ) -> Result<(), RegisterError> {
+        self.priv_try_register(name, help, metric, None, vec![])
     }
 
     /// Register a metric with the [`Registry`] specifying the metric's unit.
@@ -109,8 +215,13 @@ impl<M> Registry<M> {
     /// See [`Registry::register`] for additional documentation.
     ///
     /// Note: In the Open Metrics text exposition format units are appended to
-    /// the metric name. This is done automatically. Users must not append the
-    /// unit to the name manually.
+    /// the metric name. This is done automatically. A name already ending with
+    /// the unit is not suffixed twice, i.e. `request_duration_seconds` with
+    /// [`Unit::Seconds`] is exposed the same as `request_duration`. The unit
+    /// has to follow an underscore, thus e.g. `seconds` is exposed as
+    /// `seconds_seconds`. Either way the metric is looked up by the name as
+    /// registered, see [`Registry::get`]. To instead require all names to end
+    /// with their unit, see [`RegistryBuilder::strict_units`].
     ///
     /// ```
     /// # use prometheus_client::metrics::counter::{Atomic as _, Counter};
@@ -133,7 +244,66 @@ impl<M> Registry<M> {
         unit: Unit,
         metric: M,
     ) {
-        self.priv_register(name, help, metric, Some(unit))
+        let name = name.into();
+        if self.strict_units {
+            validate_unit_suffix(&name, &unit);
+        }
+        self.priv_register(name, help, metric, Some(unit), vec![])
+    }
+
+    /// Like [`Registry::register_with_unit`], but returning a
+    /// [`RegisterError`] instead of panicking if a metric with the same name
+    /// is already registered.
+    pub fn try_register_with_unit<N: Into<String>, H: Into<String>>(
+        &mut self,
+        name: N,
+        help: H,
+        unit: Unit,
+        metric: M,
+    ) -> Result<(), RegisterError> {
+        let name = name.into();
+        if self.strict_units {
+            validate_unit_suffix(&name, &unit);
+        }
+        self.priv_try_register(name, help, metric, Some(unit), vec![])
+    }
+
+    /// Register a metric with the [`Registry`] attaching a set of constant
+    /// labels to each sample of the metric.
+    ///
+    /// See [`Registry::register`] for additional documentation.
+    ///
+    /// Note: Constant labels are emitted after the labels of any
+    /// sub-registries (see [`Registry::sub_registry_with_label`]) and before
+    /// the labels of a [`Family`](crate::metrics::family::Family) member.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::{Atomic as _, Counter};
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry: Registry<Counter> = Registry::default();
+    /// let counter = Counter::default();
+    ///
+    /// registry.register_with_labels(
+    ///   "my_counter",
+    ///   "This is my counter",
+    ///   [("version", "1.2.3")],
+    ///   counter.clone(),
+    /// );
+    /// ```
+    pub fn register_with_labels<N, H, L, K, V>(&mut self, name: N, help: H, labels: L, metric: M)
+    where
+        N: Into<String>,
+        H: Into<String>,
+        L: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+    {
+        let labels = labels
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self.priv_register(name, help, metric, None, labels)
     }
 
     fn priv_register<N: Into<String>, H: Into<String>>(
@@ -142,10 +312,169 @@ impl<M> Registry<M> {
         help: H,
         metric: M,
         unit: Option<Unit>,
+        const_labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
     ) {
-        let name = name.into();
-        let help = help.into() + ".";
-        let descriptor = Descriptor {
+        if let Err(e) = self.priv_try_register(name, help, metric, unit, const_labels) {
+            panic!("{}", e);
+        }
+    }
+
+    fn priv_try_register<N: Into<String>, H: Into<String>>(
+        &mut self,
+        name: N,
+        help: H,
+        metric: M,
+        unit: Option<Unit>,
+        const_labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    ) -> Result<(), RegisterError> {
+        let help = help.into();
+        #[cfg(feature = "tracing")]
+        let empty_help = help.trim().is_empty();
+        let descriptor = self.prefixed(Descriptor::new(name, help, unit, const_labels));
+        #[cfg(feature = "tracing")]
+        if empty_help {
+            tracing::warn!(
+                name = %descriptor.full_name(),
+                "Registered metric without a help text."
+            );
+        }
+
+        let mut names = self.names.lock().expect("Lock not to be poisoned.");
+        if let Some(existing_help) = names.get(&descriptor.family_name()) {
+            return Err(RegisterError {
+                name: descriptor.family_name(),
+                existing_help: existing_help.clone(),
+                help: descriptor.help,
+            });
+        }
+        names.insert(descriptor.family_name(), descriptor.help.clone());
+        drop(names);
+
+        self.metrics.push((descriptor, metric));
+
+        Ok(())
+    }
+
+    /// Register a [`Collector`] with the [`Registry`].
+    ///
+    /// The [`Collector`] is asked for its metrics each time the [`Registry`] is
+    /// encoded. See [`Collector`] for details.
+    pub fn register_collector(&mut self, collector: Box<dyn Collector>) {
+        self.collectors.push(collector);
+    }
+
+    /// Measure each [`encode`](crate::encoding::text::encode) of this
+    /// [`Registry`], exposing the number of successful scrapes as
+    /// `prometheus_client_scrapes_total` and the duration of the most recent
+    /// one as `prometheus_client_scrape_duration_seconds`.
+    ///
+    /// As the metrics are encoded alongside all other metrics, each scrape
+    /// exposes the values of the scrapes before it. The metrics are exposed
+    /// through a [`Collector`] and are thus subject to the prefix and labels
+    /// of this [`Registry`]. Only encoding this very [`Registry`] is measured,
+    /// not encoding a parent registry. Calling this method again has no
+    /// effect.
+    ///
+    /// The scrape duration is measured on the wall clock, i.e. with
+    /// [`SystemTime`]. A step of the clock backwards during a scrape thus
+    /// records a duration of 0.
+    ///
+    /// Panics if one of the two metric names is already registered, as
+    /// [`Registry::register`] does. Registering one of them afterwards panics
+    /// likewise.
+    ///
+    /// ```
+    /// # use prometheus_client::encoding::text::encode;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry = <Registry>::default();
+    /// registry.enable_self_metrics();
+    ///
+    /// encode(&mut Vec::new(), &registry).unwrap();
+    ///
+    /// let mut buffer = vec![];
+    /// encode(&mut buffer, &registry).unwrap();
+    /// assert!(String::from_utf8(buffer)
+    ///     .unwrap()
+    ///     .contains("prometheus_client_scrapes_total 1\n"));
+    /// ```
+    pub fn enable_self_metrics(&mut self) {
+        self.enable_self_metrics_with_clock(SystemClock)
+    }
+
+    /// Like [`Registry::enable_self_metrics`], measuring the scrape duration
+    /// with the given [`Clock`], e.g. to control the time in tests.
+    pub fn enable_self_metrics_with_clock<C: Clock + 'static>(&mut self, clock: C) {
+        if self.self_metrics.is_some() {
+            return;
+        }
+
+        let descriptors: Vec<Descriptor> = SelfMetrics::descriptors()
+            .into_iter()
+            .map(|desc| self.prefixed(desc))
+            .collect();
+        let mut names = self.names.lock().expect("Lock not to be poisoned.");
+        for desc in descriptors.iter() {
+            if let Some(existing_help) = names.get(&desc.family_name()) {
+                let error = RegisterError {
+                    name: desc.family_name(),
+                    existing_help: existing_help.clone(),
+                    help: desc.help.clone(),
+                };
+                drop(names);
+                panic!("{}", error);
+            }
+        }
+        for desc in descriptors {
+            names.insert(desc.family_name(), desc.help);
+        }
+        drop(names);
+
+        let self_metrics = SelfMetrics {
+            clock: Arc::new(clock),
+            scrapes: Counter::default(),
+            duration: Gauge::default(),
+        };
+        self.collectors.push(Box::new(self_metrics.clone()));
+        self.self_metrics = Some(self_metrics);
+    }
+
+    /// The metrics enabled via [`Registry::enable_self_metrics`], if any.
+    pub(crate) fn self_metrics(&self) -> Option<&SelfMetrics> {
+        self.self_metrics.as_ref()
+    }
+
+    /// The prefixed descriptors of the metrics enabled via
+    /// [`Registry::enable_self_metrics`] on this [`Registry`] and its
+    /// sub-registries, whose names are reserved.
+    fn self_metric_descriptors(&self) -> Vec<Descriptor> {
+        let mut descriptors: Vec<Descriptor> = match self.self_metrics {
+            Some(_) => SelfMetrics::descriptors()
+                .into_iter()
+                .map(|desc| self.prefixed(desc))
+                .collect(),
+            None => Vec::new(),
+        };
+        for sub_registry in self.sub_registries.iter() {
+            descriptors.extend(sub_registry.self_metric_descriptors());
+        }
+        descriptors
+    }
+
+    /// Apply the prefix and labels of the [`Registry`] to the given
+    /// [`Descriptor`].
+    fn prefixed(&self, descriptor: Descriptor) -> Descriptor {
+        let Descriptor {
+            name,
+            help,
+            unit,
+            labels: const_labels,
+        } = descriptor;
+
+        let mut labels = self.labels.clone();
+        labels.extend(const_labels);
+
+        Descriptor {
             name: self
                 .prefix
                 .as_ref()
@@ -153,13 +482,123 @@ impl<M> Registry<M> {
                 .unwrap_or(name),
             help,
             unit,
-            labels: self.labels.clone(),
-        };
+            labels,
+        }
+    }
 
-        self.metrics.push((descriptor, metric));
+    /// Remove the metric with the given name from the [`Registry`] or any of its
+    /// sub-registries, returning whether a metric was removed.
+    ///
+    /// Prometheus marks the series of a removed metric stale with the next
+    /// scrape, see [`Family::remove`](crate::metrics::family::Family::remove).
+    ///
+    /// The name is the name of the metric as registered, prefixed with the
+    /// prefixes of any sub-registries, see [`Descriptor::name`]. A unit or type
+    /// specific suffix like `_total` is not appended.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry: Registry<Counter> = Registry::default();
+    /// let sub_registry = registry.sub_registry_with_prefix("my_prefix");
+    /// sub_registry.register("my_counter", "This is my counter", Counter::default());
+    ///
+    /// assert!(registry.unregister("my_prefix_my_counter"));
+    /// assert_eq!(0, registry.iter().count());
+    /// ```
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let mut names = self.names.lock().expect("Lock not to be poisoned.");
+        let len = self.metrics.len();
+        self.metrics.retain(|(desc, _)| {
+            if desc.name != name {
+                return true;
+            }
+            names.remove(&desc.family_name());
+            false
+        });
+        drop(names);
+        let mut removed = self.metrics.len() != len;
+
+        for sub_registry in self.sub_registries.iter_mut() {
+            removed |= sub_registry.unregister(name);
+        }
+
+        removed
+    }
+
+    /// Get the metric with the given name registered with the [`Registry`] or
+    /// any of its sub-registries, e.g. to assert on it in tests.
+    ///
+    /// As with [`Registry::unregister`], the name is the name of the metric as
+    /// registered, prefixed with the prefixes of any sub-registries. A unit or
+    /// type specific suffix like `_total` is not appended. Metrics of
+    /// [`Collector`]s are not included.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry: Registry<Counter> = Registry::default();
+    /// let sub_registry = registry.sub_registry_with_prefix("my_prefix");
+    /// sub_registry.register("my_counter", "This is my counter", Counter::default());
+    ///
+    /// registry.get("my_prefix_my_counter").unwrap().inc();
+    /// assert_eq!(1, registry.get("my_prefix_my_counter").unwrap().get());
+    /// assert!(registry.get("my_counter").is_none());
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&M> {
+        self.iter()
+            .find(|(desc, _)| desc.name == name)
+            .map(|(_, metric)| metric)
+    }
+
+    /// Like [`Registry::get`], but returning a mutable reference.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut M> {
+        if let Some((_, metric)) = self.metrics.iter_mut().find(|(desc, _)| desc.name == name) {
+            return Some(metric);
+        }
+
+        self.sub_registries
+            .iter_mut()
+            .find_map(|sub_registry| sub_registry.get_mut(name))
+    }
+
+    /// Remove all metrics, collectors and sub-registries from the [`Registry`],
+    /// returning it to its default state.
+    ///
+    /// This includes the prefix and labels of a sub-registry, i.e. after
+    /// clearing a sub-registry, metrics registered with it are no longer
+    /// prefixed nor labeled.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry: Registry<Counter> = Registry::default();
+    /// registry.register("my_counter", "This is my counter", Counter::default());
+    ///
+    /// registry.clear();
+    /// assert_eq!(0, registry.iter().count());
+    /// ```
+    pub fn clear(&mut self) {
+        // The names might be shared with a parent registry. Keep sharing them
+        // to continue detecting duplicates, but allow registering the cleared
+        // names again.
+        let mut names = self.names.lock().expect("Lock not to be poisoned.");
+        for (desc, _) in self.iter() {
+            names.remove(&desc.family_name());
+        }
+        for desc in self.self_metric_descriptors() {
+            names.remove(&desc.family_name());
+        }
+        drop(names);
+
+        let names = self.names.clone();
+        *self = Self::default();
+        self.names = names;
     }
 
-    // TODO: Update doc.
     /// Create a sub-registry to register metrics with a common prefix.
     ///
     /// Say you would like to prefix one set of metrics with `subsystem_a` and
@@ -171,7 +610,12 @@ impl<M> Registry<M> {
     /// your architecture automatically adding a prefix to each metric the
     /// subsystem registers.
     ///
+    /// Prefixes of nested sub-registries are concatenated, e.g. a metric
+    /// `my_metric` registered with sub-registry `inner` of sub-registry `outer`
+    /// is named `outer_inner_my_metric`.
+    ///
     /// ```
+    /// # use prometheus_client::encoding::text::encode;
     /// # use prometheus_client::metrics::counter::{Atomic as _, Counter};
     /// # use prometheus_client::registry::{Registry, Unit};
     /// #
@@ -181,20 +625,27 @@ impl<M> Registry<M> {
     /// let subsystem_a_counter_2 = Counter::default();
     ///
     /// let subsystem_a_registry = registry.sub_registry_with_prefix("subsystem_a");
-    /// registry.register("counter_1", "", subsystem_a_counter_1.clone());
-    /// registry.register("counter_2", "", subsystem_a_counter_2.clone());
+    /// subsystem_a_registry.register("counter_1", "", subsystem_a_counter_1.clone());
+    /// subsystem_a_registry.register("counter_2", "", subsystem_a_counter_2.clone());
     ///
     /// let subsystem_b_counter_1 = Counter::default();
     /// let subsystem_b_counter_2 = Counter::default();
     ///
-    /// let subsystem_a_registry = registry.sub_registry_with_prefix("subsystem_b");
-    /// registry.register("counter_1", "", subsystem_b_counter_1.clone());
-    /// registry.register("counter_2", "", subsystem_b_counter_2.clone());
+    /// let subsystem_b_registry = registry.sub_registry_with_prefix("subsystem_b");
+    /// subsystem_b_registry.register("counter_1", "", subsystem_b_counter_1.clone());
+    /// subsystem_b_registry.register("counter_2", "", subsystem_b_counter_2.clone());
+    ///
+    /// # let mut buffer = vec![];
+    /// # encode(&mut buffer, &registry).unwrap();
+    /// # let encoded = String::from_utf8(buffer).unwrap();
+    /// # assert!(encoded.contains("subsystem_a_counter_1_total 0\n"));
+    /// # assert!(encoded.contains("subsystem_b_counter_2_total 0\n"));
     /// ```
     ///
     /// See [`Registry::sub_registry_with_label`] for the same functionality,
     /// but namespacing with a label instead of a metric name prefix.
     pub fn sub_registry_with_prefix<P: AsRef<str>>(&mut self, prefix: P) -> &mut Self {
+        validate_metric_name(prefix.as_ref());
         let sub_registry = Registry {
             prefix: Some(
                 self.prefix
@@ -204,6 +655,8 @@ impl<M> Registry<M> {
                     + prefix.as_ref(),
             ),
             labels: self.labels.clone(),
+            strict_units: self.strict_units,
+            names: self.names.clone(),
             ..Default::default()
         };
 
@@ -211,15 +664,40 @@ impl<M> Registry<M> {
     }
 
     /// Like [`Registry::sub_registry_with_prefix`] but with a label instead.
+    ///
+    /// Labels of nested sub-registries accumulate, ordered from the outermost
+    /// to the innermost sub-registry, and precede the constant labels of a
+    /// metric as well as the label sets of a
+    /// [`Family`](crate::metrics::family::Family).
+    ///
+    /// ```
+    /// # use prometheus_client::encoding::text::encode;
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry = Registry::default();
+    /// registry
+    ///     .sub_registry_with_label(("region".into(), "eu".into()))
+    ///     .sub_registry_with_label(("zone".into(), "a".into()))
+    ///     .register("requests", "Number of requests", Counter::<u64>::default());
+    ///
+    /// # let mut buffer = vec![];
+    /// # encode(&mut buffer, &registry).unwrap();
+    /// # let encoded = String::from_utf8(buffer).unwrap();
+    /// # assert!(encoded.contains("requests_total{region=\"eu\",zone=\"a\"} 0\n"));
+    /// ```
     pub fn sub_registry_with_label(
         &mut self,
         label: (Cow<'static, str>, Cow<'static, str>),
     ) -> &mut Self {
+        validate_label_name(&label.0);
         let mut labels = self.labels.clone();
         labels.push(label);
         let sub_registry = Registry {
             prefix: self.prefix.clone(),
             labels,
+            strict_units: self.strict_units,
+            names: self.names.clone(),
             ..Default::default()
         };
 
@@ -234,78 +712,799 @@ impl<M> Registry<M> {
             .expect("sub_registries not to be empty.")
     }
 
-    pub fn iter(&self) -> RegistryIterator<M> {
-        let metrics = self.metrics.iter();
-        let sub_registries = self.sub_registries.iter();
-        RegistryIterator {
-            metrics,
-            sub_registries,
-            sub_registry: None,
+    /// Merge the given [`Registry`] into this [`Registry`], e.g. to expose the
+    /// metrics of multiple libraries, each with their own [`Registry`], via a
+    /// single scrape endpoint.
+    ///
+    /// The metrics, collectors and sub-registries of the given [`Registry`]
+    /// keep its prefix and labels. Neither the prefix nor the labels of this
+    /// [`Registry`] are applied to them.
+    ///
+    /// Returns a [`MergeError`] listing the full names of all metrics
+    /// registered with both registries, leaving this [`Registry`] unchanged.
+    /// Collisions of the metrics of [`Collector`]s are only detected on
+    /// [`encode`](crate::encoding::text::encode).
+    ///
+    /// ```
+    /// # use prometheus_client::encoding::text::encode;
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry: Registry<Counter> = Registry::with_prefix("app");
+    /// registry.register("requests", "Number of requests", Counter::default());
+    ///
+    /// let mut library_registry = Registry::with_prefix("library");
+    /// library_registry.register("requests", "Number of requests", Counter::default());
+    ///
+    /// registry.merge(library_registry).unwrap();
+    ///
+    /// # let mut buffer = vec![];
+    /// # encode(&mut buffer, &registry).unwrap();
+    /// # let encoded = String::from_utf8(buffer).unwrap();
+    /// # assert!(encoded.contains("app_requests_total 0\n"));
+    /// # assert!(encoded.contains("library_requests_total 0\n"));
+    /// ```
+    pub fn merge(&mut self, mut other: Registry<M>) -> Result<(), MergeError> {
+        let self_metric_descriptors = other.self_metric_descriptors();
+        let mut names = self.names.lock().expect("Lock not to be poisoned.");
+        let mut collisions: Vec<String> = other
+            .iter()
+            .map(|(desc, _)| desc)
+            .chain(self_metric_descriptors.iter())
+            .map(|desc| desc.family_name())
+            .filter(|name| names.contains_key(name))
+            .collect();
+        if !collisions.is_empty() {
+            collisions.sort();
+            return Err(MergeError { names: collisions });
         }
-    }
-}
 
-/// Iterator iterating both the metrics registered directly with the registry as
-/// well as all metrics registered with sub-registries.
-pub struct RegistryIterator<'a, M> {
-    metrics: std::slice::Iter<'a, (Descriptor, M)>,
-    sub_registries: std::slice::Iter<'a, Registry<M>>,
-    sub_registry: Option<Box<RegistryIterator<'a, M>>>,
-}
+        for desc in other
+            .iter()
+            .map(|(desc, _)| desc)
+            .chain(self_metric_descriptors.iter())
+        {
+            names.insert(desc.family_name(), desc.help.clone());
+        }
+        drop(names);
 
-impl<'a, M> Iterator for RegistryIterator<'a, M> {
-    type Item = &'a (Descriptor, M);
+        other.share_names(&self.names);
+        self.sub_registries.push(other);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(metric) = self.metrics.next() {
-            return Some(metric);
-        }
+        Ok(())
+    }
 
-        loop {
-            if let Some(metric) = self.sub_registry.as_mut().and_then(|i| i.next()) {
-                return Some(metric);
-            }
+    /// Share the given names with this [`Registry`] and all its
+    /// sub-registries, to detect duplicate registrations after a merge.
+    fn share_names(&mut self, names: &Arc<Mutex<HashMap<String, String>>>) {
+        self.names = names.clone();
+        for sub_registry in self.sub_registries.iter_mut() {
+            sub_registry.share_names(names);
+        }
+    }
 
-            self.sub_registry = self.sub_registries.next().map(|r| Box::new(r.iter()));
+    /// Collect the metrics of all [`Collector`]s registered with the
+    /// [`Registry`] or any of its sub-registries.
+    pub(crate) fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+        let mut metrics: Vec<_> = self
+            .collectors
+            .iter()
+            .flat_map(|c| c.collect())
+            .map(|(desc, metric)| (self.prefixed(desc), metric))
+            .collect();
 
-            if self.sub_registry.is_none() {
-                break;
-            }
+        for sub_registry in self.sub_registries.iter() {
+            metrics.extend(sub_registry.collect());
         }
 
-        None
+        metrics
     }
-}
 
-#[derive(Clone)]
-struct Prefix(String);
+    /// Iterate the [`Collector`]s registered directly with the [`Registry`].
+    ///
+    /// [`Collector`]s registered with sub-registries are not included, as
+    /// their metrics are subject to the prefix and labels of the respective
+    /// sub-registry. Use [`encode`](crate::encoding::text::encode) to encode
+    /// the metrics of all [`Collector`]s.
+    pub fn iter_collectors(&self) -> impl Iterator<Item = &dyn Collector> {
+        self.collectors.iter().map(|c| c.as_ref())
+    }
 
-impl From<String> for Prefix {
-    fn from(s: String) -> Self {
-        Prefix(s)
+    pub fn iter(&self) -> RegistryIterator<'_, M> {
+        let metrics = self.metrics.iter();
+        let sub_registries = self.sub_registries.iter();
+        RegistryIterator {
+            metrics,
+            sub_registries,
+            sub_registry: None,
+        }
     }
 }
 
-impl From<Prefix> for String {
-    fn from(p: Prefix) -> Self {
-        p.0
+impl<M: EncodeMetric> Registry<M> {
+    /// Take an owned [`Snapshot`] of the current values of all metrics,
+    /// including those of sub-registries and [`Collector`]s.
+    ///
+    /// The [`Snapshot`] is recorded while traversing the metrics the way
+    /// [`encode`](crate::encoding::text::encode) does, thus matching what a
+    /// scrape would return. Each metric is read once, while the [`Registry`]
+    /// itself can not change during the snapshot.
+    ///
+    /// ```
+    /// # use prometheus_client::encoding::snapshot::Value;
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::metrics::MetricType;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry = Registry::default();
+    /// let counter: Counter = Counter::default();
+    /// registry.register("my_counter", "This is my counter", counter.clone());
+    /// counter.inc();
+    ///
+    /// let snapshot = registry.snapshot().unwrap();
+    /// let family = snapshot.family("my_counter").unwrap();
+    /// assert_eq!(MetricType::Counter, family.metric_type());
+    /// assert_eq!(Some(Value::Unsigned(1)), family.value("my_counter_total", &[]));
+    /// ```
+    pub fn snapshot(&self) -> Result<Snapshot, std::io::Error> {
+        crate::encoding::snapshot::snapshot(self)
     }
-}
 
-impl Add<&str> for Prefix {
-    type Output = Self;
-    fn add(self, rhs: &str) -> Self::Output {
-        Prefix(self.0 + rhs)
+    /// Encode the [`Registry`] one metric family at a time in the OpenMetrics
+    /// text format, e.g. to write a chunked HTTP response without buffering
+    /// the whole exposition.
+    ///
+    /// Concatenating the yielded chunks, the last of which being the `# EOF`
+    /// marker, yields the same output as
+    /// [`encode`](crate::encoding::text::encode). See [`EncodeFamilies`].
+    ///
+    /// ```
+    /// # use prometheus_client::encoding::text::encode;
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::metrics::gauge::Gauge;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry = <Registry>::default();
+    /// registry.register("my_counter", "My counter", Box::new(Counter::<u64>::default()));
+    /// registry.register("my_gauge", "My gauge", Box::new(Gauge::<u64>::default()));
+    ///
+    /// let mut body = Vec::new();
+    /// for chunk in registry.encode_families() {
+    ///     // E.g. write each chunk as an HTTP body frame instead.
+    ///     body.extend(chunk.unwrap());
+    /// }
+    ///
+    /// let mut encoded = Vec::new();
+    /// encode(&mut encoded, &registry).unwrap();
+    /// assert_eq!(encoded, body);
+    /// ```
+    pub fn encode_families(&self) -> EncodeFamilies<'_, M> {
+        EncodeFamilies::new(self)
     }
-}
 
-impl Add<&Prefix> for String {
-    type Output = Self;
-    fn add(self, rhs: &Prefix) -> Self::Output {
-        self + rhs.0.as_str()
+    /// The number of metric families [`encode`](crate::encoding::text::encode)
+    /// exposes, including those of sub-registries and [`Collector`]s, e.g. to
+    /// monitor the registry itself.
+    ///
+    /// Like on encoding, empty metrics, i.e. a
+    /// [`Family`](crate::metrics::family::Family) without label sets, are not
+    /// counted. The metrics of [`Collector`]s are collected to be counted.
+    ///
+    /// ```
+    /// # use prometheus_client::metrics::counter::Counter;
+    /// # use prometheus_client::metrics::family::Family;
+    /// # use prometheus_client::registry::Registry;
+    /// #
+    /// let mut registry = <Registry>::default();
+    /// let requests = Family::<Vec<(String, String)>, Counter>::default();
+    /// registry.register("requests", "Number of requests", Box::new(requests.clone()));
+    /// registry.register("errors", "Number of errors", Box::new(Counter::<u64>::default()));
+    /// assert_eq!(1, registry.families_len());
+    ///
+    /// requests.get_or_create(&vec![("method".to_owned(), "GET".to_owned())]);
+    /// requests.get_or_create(&vec![("method".to_owned(), "PUT".to_owned())]);
+    /// assert_eq!(2, registry.families_len());
+    /// assert_eq!(3, registry.series_len());
+    /// ```
+    pub fn families_len(&self) -> usize {
+        self.fold_metrics(0, |len, _| len + 1)
     }
-}
 
+    /// The number of series, i.e. label sets, across all metric families
+    /// [`encode`](crate::encoding::text::encode) exposes, see
+    /// [`Registry::families_len`]. A non-empty metric other than a
+    /// [`Family`](crate::metrics::family::Family) counts as one series.
+    pub fn series_len(&self) -> usize {
+        self.fold_metrics(0, |len, metric| len + metric.series_len())
+    }
+
+    /// Fold all non-empty metrics, registered and collected.
+    fn fold_metrics<B>(&self, init: B, mut f: impl FnMut(B, &dyn EncodeMetric) -> B) -> B {
+        let registered = self
+            .iter()
+            .map(|(_, metric)| metric as &dyn EncodeMetric)
+            .filter(|metric| !metric.is_empty())
+            .fold(init, &mut f);
+        self.collect()
+            .iter()
+            .map(|(_, metric)| metric as &dyn EncodeMetric)
+            .filter(|metric| !metric.is_empty())
+            .fold(registered, f)
+    }
+}
+
+/// Builder of a [`Registry`] with a prefix and constant labels applying to all
+/// metrics registered with it or any of its sub-registries.
+///
+/// ```
+/// # use prometheus_client::encoding::text::encode;
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = Registry::builder()
+///     .prefix("my_service")
+///     .const_label("region", "eu")
+///     .const_label("zone", "a")
+///     .build();
+/// registry.register("requests", "Number of requests", Counter::<u64>::default());
+///
+/// # let mut buffer = vec![];
+/// # encode(&mut buffer, &registry).unwrap();
+/// # let encoded = String::from_utf8(buffer).unwrap();
+/// # assert!(encoded.contains("my_service_requests_total{region=\"eu\",zone=\"a\"} 0\n"));
+/// ```
+///
+/// There is no registry-wide limit on the number of series. The metrics are
+/// constructed by the caller, not the [`Registry`], which thus has no way to
+/// refuse their new series. Limit the number of series of each
+/// [`Family`](crate::metrics::family::Family) via
+/// [`Family::with_max_series`](crate::metrics::family::Family::with_max_series)
+/// instead, and see [`Registry::series_len`] for the current total.
+#[derive(Debug, Default)]
+pub struct RegistryBuilder {
+    prefix: Option<String>,
+    labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    strict_units: bool,
+}
+
+impl RegistryBuilder {
+    /// Prefix the names of all metrics, see [`Registry::with_prefix`].
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        validate_metric_name(&prefix);
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Add a constant label to all metrics, see
+    /// [`Registry::sub_registry_with_label`]. Labels are encoded in the order
+    /// they are added.
+    pub fn const_label(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        let name = name.into();
+        validate_label_name(&name);
+        self.labels.push((name, value.into()));
+        self
+    }
+
+    /// Require the names passed to [`Registry::register_with_unit`] to end
+    /// with their unit, e.g. `request_duration_seconds` with
+    /// [`Unit::Seconds`], instead of appending the unit when missing. Applies
+    /// to all sub-registries.
+    ///
+    /// Registering a name without its unit suffix panics, in release builds
+    /// as well, as the unit is part of the name users query for.
+    ///
+    /// ```should_panic
+    /// # use prometheus_client::metrics::gauge::Gauge;
+    /// # use prometheus_client::registry::{Registry, Unit};
+    /// #
+    /// let mut registry = Registry::builder().strict_units().build();
+    /// registry.register_with_unit(
+    ///     "request_duration_seconds",
+    ///     "Request duration",
+    ///     Unit::Seconds,
+    ///     Gauge::<u64>::default(),
+    /// );
+    ///
+    /// // Panics, as the name is missing the `_seconds` suffix.
+    /// registry.register_with_unit(
+    ///     "response_duration",
+    ///     "Response duration",
+    ///     Unit::Seconds,
+    ///     Gauge::<u64>::default(),
+    /// );
+    /// ```
+    pub fn strict_units(mut self) -> Self {
+        self.strict_units = true;
+        self
+    }
+
+    pub fn build<M>(self) -> Registry<M> {
+        Registry {
+            prefix: self.prefix.map(Into::into),
+            labels: self.labels,
+            strict_units: self.strict_units,
+            ..Default::default()
+        }
+    }
+}
+
+/// Error returned by [`Registry::try_register`] if a metric with the same
+/// name is already registered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisterError {
+    name: String,
+    existing_help: String,
+    help: String,
+}
+
+impl RegisterError {
+    /// The full name of the metric registered twice.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Metric {:?} registered twice. Help of the existing metric: {:?}. Help of the new metric: {:?}.",
+            self.name, self.existing_help, self.help,
+        )
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+/// Error returned by [`Registry::merge`], listing the full names of the
+/// metrics registered with both registries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeError {
+    names: Vec<String>,
+}
+
+impl MergeError {
+    /// The full names of the colliding metrics, sorted alphabetically.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Metrics {:?} registered in both registries.", self.names)
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Metrics measuring the scrapes of a [`Registry`], see
+/// [`Registry::enable_self_metrics`].
+#[derive(Clone)]
+pub(crate) struct SelfMetrics {
+    clock: Arc<dyn Clock>,
+    scrapes: Counter,
+    duration: Gauge<f64, AtomicU64>,
+}
+
+impl SelfMetrics {
+    /// The descriptors of the scrape count and duration metrics, in this
+    /// order.
+    fn descriptors() -> Vec<Descriptor> {
+        vec![
+            Descriptor::new(
+                "prometheus_client_scrapes",
+                "Number of successful scrapes of the registry",
+                None,
+                vec![],
+            ),
+            Descriptor::new(
+                "prometheus_client_scrape_duration",
+                "Duration of the most recent successful scrape of the registry",
+                Some(Unit::Seconds),
+                vec![],
+            ),
+        ]
+    }
+
+    /// The start time of a scrape, to be passed to [`SelfMetrics::record`].
+    pub(crate) fn start(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    /// Record a successful scrape started at the given time.
+    pub(crate) fn record(&self, start: SystemTime) {
+        let duration = self.clock.now().duration_since(start).unwrap_or_default();
+        self.scrapes.inc();
+        self.duration.set(duration.as_secs_f64());
+    }
+}
+
+impl Collector for SelfMetrics {
+    fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+        let metrics: Vec<Box<dyn EncodeMetric>> = vec![
+            Box::new(self.scrapes.clone()),
+            Box::new(self.duration.clone()),
+        ];
+        Self::descriptors().into_iter().zip(metrics).collect()
+    }
+}
+
+/// Iterator iterating both the metrics registered directly with the registry as
+/// well as all metrics registered with sub-registries.
+pub struct RegistryIterator<'a, M> {
+    metrics: std::slice::Iter<'a, (Descriptor, M)>,
+    sub_registries: std::slice::Iter<'a, Registry<M>>,
+    sub_registry: Option<Box<RegistryIterator<'a, M>>>,
+}
+
+impl<'a, M> Iterator for RegistryIterator<'a, M> {
+    type Item = &'a (Descriptor, M);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(metric) = self.metrics.next() {
+            return Some(metric);
+        }
+
+        loop {
+            if let Some(metric) = self.sub_registry.as_mut().and_then(|i| i.next()) {
+                return Some(metric);
+            }
+
+            self.sub_registry = self.sub_registries.next().map(|r| Box::new(r.iter()));
+
+            if self.sub_registry.is_none() {
+                break;
+            }
+        }
+
+        None
+    }
+}
+
+/// Register multiple metrics with a [`Registry`], expanding to one
+/// [`Registry::register`] call per `(name, help, metric)` triple.
+///
+/// Names must be string literals and are checked for uniqueness at compile
+/// time. The registry expression is evaluated once per metric, thus pass a
+/// variable.
+///
+/// ```
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::metrics::gauge::Gauge;
+/// # use prometheus_client::register_metrics;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry = <Registry>::default();
+/// let requests: Counter = Counter::default();
+/// let connections: Gauge = Gauge::default();
+///
+/// register_metrics!(
+///     registry,
+///     ("requests", "Number of requests", Box::new(requests.clone())),
+///     ("connections", "Number of open connections", Box::new(connections.clone())),
+/// );
+/// # assert_eq!(2, registry.iter().count());
+/// ```
+///
+/// Registering the same name twice fails to compile:
+///
+/// ```compile_fail
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::register_metrics;
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let mut registry: Registry<Counter> = Registry::default();
+/// register_metrics!(
+///     registry,
+///     ("requests", "Number of requests", Counter::default()),
+///     ("requests", "Number of requests", Counter::default()),
+/// );
+/// ```
+#[macro_export]
+macro_rules! register_metrics {
+    ($registry:expr, $(($name:literal, $help:expr, $metric:expr)),* $(,)?) => {{
+        const _: () = $crate::registry::assert_unique_names(&[$($name),*]);
+        $($registry.register($name, $help, $metric);)*
+    }};
+}
+
+/// Panics, i.e. fails compilation in a const context, if the given names
+/// contain a duplicate. Used by [`register_metrics!`].
+#[doc(hidden)]
+pub const fn assert_unique_names(names: &[&str]) {
+    let mut i = 0;
+    while i < names.len() {
+        let mut j = i + 1;
+        while j < names.len() {
+            if str_eq(names[i], names[j]) {
+                panic!("register_metrics! called with duplicate metric name.");
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// A [`Registry`] behind a lock, allowing metrics to be registered via `&self`
+/// and thus through an [`Arc`] shared across threads.
+///
+/// Encoding holds the lock for its entire duration via
+/// [`SharedRegistry::lock`], thus concurrent registrations are either fully
+/// included in or fully excluded from a single encoding. A [`Mutex`] instead
+/// of a read-write lock is used, as [`Collector`]s are only required to be
+/// [`Send`].
+///
+/// Configure prefixes and sub-registries on the [`Registry`] before wrapping it
+/// via [`SharedRegistry::new`].
+///
+/// ```
+/// # use prometheus_client::encoding::text::encode;
+/// # use prometheus_client::metrics::counter::{Atomic as _, Counter};
+/// # use prometheus_client::registry::Registry;
+/// #
+/// let registry = <Registry>::new_shared();
+///
+/// let handle = {
+///     let registry = registry.clone();
+///     std::thread::spawn(move || {
+///         let counter: Counter = Counter::default();
+///         registry.register("my_counter", "This is my counter", Box::new(counter.clone()));
+///         counter.inc();
+///     })
+/// };
+/// handle.join().unwrap();
+///
+/// let mut buffer = vec![];
+/// encode(&mut buffer, &registry.lock()).unwrap();
+/// ```
+pub struct SharedRegistry<M = Box<dyn crate::encoding::text::SendEncodeMetric>> {
+    registry: Mutex<Registry<M>>,
+}
+
+impl<M> SharedRegistry<M> {
+    /// Wrap the given [`Registry`].
+    pub fn new(registry: Registry<M>) -> Self {
+        Self {
+            registry: Mutex::new(registry),
+        }
+    }
+
+    /// See [`Registry::register`].
+    ///
+    /// The [`Registry`] is unlocked before panicking on a duplicate
+    /// registration, thus remains usable afterwards.
+    pub fn register<N: Into<String>, H: Into<String>>(&self, name: N, help: H, metric: M) {
+        let result = self.try_register(name, help, metric);
+        if let Err(e) = result {
+            panic!("{}", e);
+        }
+    }
+
+    /// See [`Registry::try_register`].
+    pub fn try_register<N: Into<String>, H: Into<String>>(
+        &self,
+        name: N,
+        help: H,
+        metric: M,
+    ) -> Result<(), RegisterError> {
+        self.lock().try_register(name, help, metric)
+    }
+
+    /// See [`Registry::register_with_unit`].
+    pub fn register_with_unit<N: Into<String>, H: Into<String>>(
+        &self,
+        name: N,
+        help: H,
+        unit: Unit,
+        metric: M,
+    ) {
+        let result = self.lock().try_register_with_unit(name, help, unit, metric);
+        if let Err(e) = result {
+            panic!("{}", e);
+        }
+    }
+
+    /// See [`Registry::register_collector`].
+    pub fn register_collector(&self, collector: Box<dyn Collector>) {
+        self.lock().register_collector(collector)
+    }
+
+    /// See [`Registry::unregister`].
+    pub fn unregister(&self, name: &str) -> bool {
+        self.lock().unregister(name)
+    }
+
+    /// Lock the [`Registry`], e.g. to encode it. Registrations block for as
+    /// long as the returned guard is alive.
+    ///
+    /// The lock is recovered if a thread panicked while holding it, e.g. on
+    /// a duplicate registration via the returned guard, as the methods of
+    /// [`Registry`] check for duplicates before modifying it.
+    pub fn lock(&self) -> MutexGuard<'_, Registry<M>> {
+        self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<M> Default for SharedRegistry<M> {
+    fn default() -> Self {
+        Self::new(Registry::default())
+    }
+}
+
+/// Panics in debug builds if the given metric name does not match
+/// `[a-zA-Z_:][a-zA-Z0-9_:]*`.
+fn validate_metric_name(name: &str) {
+    validate_name("metric", name, true)
+}
+
+/// Label names reserved for the labels added by the encoder, e.g. `le` for
+/// the buckets of a histogram, or by Prometheus itself. Label names of
+/// [`Family`](crate::metrics::family::Family) label sets and constant labels
+/// must not be one of them, as they would collide.
+pub const RESERVED_LABEL_NAMES: [&str; 3] = ["__name__", "le", "quantile"];
+
+/// Panics in debug builds if the given label name does not match
+/// `[a-zA-Z_][a-zA-Z0-9_]*` or is one of the [`RESERVED_LABEL_NAMES`].
+fn validate_label_name(name: &str) {
+    validate_name("label", name, false);
+
+    if cfg!(debug_assertions) && RESERVED_LABEL_NAMES.contains(&name) {
+        panic!("Invalid label name {:?}: name is reserved.", name);
+    }
+}
+
+fn validate_name(kind: &str, name: &str, allow_colon: bool) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    if name.is_empty() {
+        panic!("Invalid {} name: name must not be empty.", kind);
+    }
+
+    let invalid = name.chars().enumerate().find(|(i, c)| {
+        let valid = c.is_ascii_alphabetic()
+            || *c == '_'
+            || (allow_colon && *c == ':')
+            || (*i > 0 && c.is_ascii_digit());
+        !valid
+    });
+
+    if let Some((i, c)) = invalid {
+        panic!(
+            "Invalid {} name {:?}: invalid character {:?} at position {}.",
+            kind, name, c, i
+        );
+    }
+}
+
+/// Panics if the given metric name does not end with the unit, see
+/// [`RegistryBuilder::strict_units`].
+fn validate_unit_suffix(name: &str, unit: &Unit) {
+    if strip_unit_suffix(name, unit).is_none() {
+        panic!(
+            "Invalid metric name {:?}: name must end with its unit {:?}.",
+            name,
+            unit.as_str()
+        );
+    }
+}
+
+/// The given metric name suffixed with `_<unit>`, unless it already ends with
+/// it, e.g. `request_duration_seconds` for both `request_duration` and
+/// `request_duration_seconds` with [`Unit::Seconds`].
+pub(crate) fn with_unit<'a>(name: &'a str, unit: Option<&Unit>) -> Cow<'a, str> {
+    match unit {
+        Some(unit) if strip_unit_suffix(name, unit).is_none() => {
+            Cow::Owned(format!("{}_{}", name, unit.as_str()))
+        }
+        _ => Cow::Borrowed(name),
+    }
+}
+
+/// The given metric name without the `_<unit>` suffix, if present.
+fn strip_unit_suffix<'a>(name: &'a str, unit: &Unit) -> Option<&'a str> {
+    name.strip_suffix(unit.as_str())
+        .and_then(|name| name.strip_suffix('_'))
+        .filter(|name| !name.is_empty())
+}
+
+#[derive(Clone)]
+struct Prefix(String);
+
+impl From<String> for Prefix {
+    fn from(s: String) -> Self {
+        Prefix(s)
+    }
+}
+
+impl From<Prefix> for String {
+    fn from(p: Prefix) -> Self {
+        p.0
+    }
+}
+
+impl Add<&str> for Prefix {
+    type Output = Self;
+    fn add(self, rhs: &str) -> Self::Output {
+        Prefix(self.0 + rhs)
+    }
+}
+
+impl Add<&Prefix> for String {
+    type Output = Self;
+    fn add(self, rhs: &Prefix) -> Self::Output {
+        self + rhs.0.as_str()
+    }
+}
+
+/// Metadata of a registered metric, i.e. its name, help text, unit and
+/// constant labels.
+///
+/// [`Registry::iter`] yields each [`Descriptor`] along with its metric, whose
+/// [`EncodeMetric::metric_type`] provides the metric type. Together they
+/// allow building custom encoders.
+///
+/// ```
+/// # use prometheus_client::metrics::counter::Counter;
+/// # use prometheus_client::metrics::gauge::Gauge;
+/// # use prometheus_client::registry::{Registry, Unit};
+/// #
+/// let mut registry = <Registry>::default();
+/// registry.register("requests", "Number of requests", Box::new(Counter::<u64>::default()));
+/// registry.register_with_unit(
+///     "uptime",
+///     "Time since start",
+///     Unit::Seconds,
+///     Box::new(Gauge::<u64>::default()),
+/// );
+///
+/// // A minimal encoder listing each metric along with its type.
+/// let listing: Vec<String> = registry
+///     .iter()
+///     .map(|(desc, metric)| {
+///         format!(
+///             "{} ({}): {}",
+///             desc.full_name(),
+///             metric.metric_type().as_str(),
+///             desc.help(),
+///         )
+///     })
+///     .collect();
+///
+/// assert_eq!(
+///     vec![
+///         "requests (counter): Number of requests.",
+///         "uptime_seconds (gauge): Time since start.",
+///     ],
+///     listing,
+/// );
+/// ```
+///
+/// Note: A [`Descriptor`] does not carry the metric type, as it is created
+/// independently of the metric, e.g. by a [`Collector`].
 pub struct Descriptor {
     name: String,
     help: String,
@@ -314,10 +1513,38 @@ pub struct Descriptor {
 }
 
 impl Descriptor {
+    /// Create a new [`Descriptor`], e.g. to be returned by a [`Collector`].
+    ///
+    /// Note: A full stop punctuation mark (`.`) is automatically added to the
+    /// passed help text, as with [`Registry::register`].
+    pub fn new<N: Into<String>, H: Into<String>>(
+        name: N,
+        help: H,
+        unit: Option<Unit>,
+        labels: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    ) -> Self {
+        let name = name.into();
+        validate_metric_name(&name);
+        for (key, _) in labels.iter() {
+            validate_label_name(key);
+        }
+
+        Self {
+            name,
+            help: help.into() + ".",
+            unit,
+            labels,
+        }
+    }
+
+    /// The name of the metric as registered, including the prefixes of any
+    /// sub-registries. See [`Descriptor::full_name`] for the name including
+    /// the unit.
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// The help text of the metric, including the trailing full stop.
     pub fn help(&self) -> &str {
         &self.help
     }
@@ -326,14 +1553,46 @@ impl Descriptor {
         &self.unit
     }
 
+    /// The constant labels of the metric, starting with the labels of any
+    /// sub-registries.
     pub fn labels(&self) -> &[(Cow<'static, str>, Cow<'static, str>)] {
         &self.labels
     }
+
+    /// The name of the metric family as emitted, i.e. including the unit,
+    /// unless the name already ends with it.
+    pub fn full_name(&self) -> String {
+        with_unit(&self.name, self.unit.as_ref()).into_owned()
+    }
+
+    /// The [`Descriptor::full_name`] without a `_total` or `_info` suffix,
+    /// identifying metrics exposing the same metric family, e.g. a counter
+    /// `my_counter` and a counter `my_counter_total`.
+    ///
+    /// The suffixes are stripped regardless of the metric type, which a
+    /// [`Descriptor`] does not carry, as e.g. a gauge `my_metric_total`
+    /// collides with the `my_metric_total` sample of a counter `my_metric`.
+    pub(crate) fn family_name(&self) -> String {
+        let name = self
+            .name
+            .strip_suffix("_total")
+            .or_else(|| self.name.strip_suffix("_info"))
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.name);
+        with_unit(name, self.unit.as_ref()).into_owned()
+    }
 }
 
 /// Metric units recommended by Open Metrics.
 ///
-/// See [`Unit::Other`] to specify alternative units.
+/// The set is limited to the base units recommended by the OpenMetrics
+/// specification on purpose, e.g. [`Unit::Ratios`] instead of percent and
+/// [`Unit::Seconds`] instead of milliseconds, so that metrics of different
+/// sources are comparable. Units like requests are better expressed through
+/// the metric name. See [`Unit::Other`] to specify alternative units.
+///
+/// Encoding a unit does not allocate, as [`Unit::as_str`] borrows the name
+/// of both the known units and [`Unit::Other`].
 pub enum Unit {
     Amperes,
     Bytes,
@@ -347,10 +1606,80 @@ pub enum Unit {
     Other(String),
 }
 
+impl Unit {
+    /// The name of the unit as appended to metric names.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Unit::Amperes => "amperes",
+            Unit::Bytes => "bytes",
+            Unit::Celsius => "celsius",
+            Unit::Grams => "grams",
+            Unit::Joules => "joules",
+            Unit::Meters => "meters",
+            Unit::Ratios => "ratios",
+            Unit::Seconds => "seconds",
+            Unit::Volts => "volts",
+            Unit::Other(other) => other.as_str(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::metrics::counter::Counter;
+    use crate::metrics::family::Family;
+
+    #[test]
+    fn builder() {
+        let mut registry: Registry<Counter> = Registry::builder()
+            .prefix("my_service")
+            .const_label("region", "eu")
+            .build();
+        registry
+            .sub_registry_with_label(("zone".into(), "a".into()))
+            .register("requests", "Requests", Counter::default());
+
+        let (desc, _) = registry.iter().next().unwrap();
+        assert_eq!("my_service_requests", desc.name());
+        assert_eq!(
+            &[("region".into(), "eu".into()), ("zone".into(), "a".into())],
+            desc.labels()
+        );
+    }
+
+    #[test]
+    fn with_prefix() {
+        let mut registry: Registry<Counter> = Registry::with_prefix("my_service");
+        registry.register("root", "Root counter", Counter::default());
+        registry.sub_registry_with_prefix("subsystem").register(
+            "sub",
+            "Sub counter",
+            Counter::default(),
+        );
+
+        let names: Vec<&str> = registry.iter().map(|(desc, _)| desc.name()).collect();
+        assert_eq!(vec!["my_service_root", "my_service_subsystem_sub"], names);
+    }
+
+    #[test]
+    fn get_and_get_mut() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("root", "Root counter", Counter::default());
+        registry
+            .sub_registry_with_prefix("outer")
+            .sub_registry_with_prefix("inner")
+            .register("leaf", "Leaf counter", Counter::default());
+
+        registry.get("root").unwrap().inc();
+        assert_eq!(1, registry.get("root").unwrap().get());
+
+        *registry.get_mut("outer_inner_leaf").unwrap() = Counter::default();
+        registry.get("outer_inner_leaf").unwrap().inc_by(2);
+        assert_eq!(2, registry.get("outer_inner_leaf").unwrap().get());
+
+        assert!(registry.get("leaf").is_none());
+        assert!(registry.get_mut("root_total").is_none());
+    }
 
     #[test]
     fn register_and_iterate() {
@@ -361,6 +1690,497 @@ mod tests {
         assert_eq!(1, registry.iter().count())
     }
 
+    #[test]
+    fn families_len_and_series_len() {
+        struct MyCollector;
+
+        impl Collector for MyCollector {
+            fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+                let family = Family::<Vec<(String, String)>, Counter>::default();
+                family.get_or_create(&vec![("shard".to_string(), "0".to_string())]);
+                family.get_or_create(&vec![("shard".to_string(), "1".to_string())]);
+                vec![(
+                    Descriptor::new("my_family", "My family", None, vec![]),
+                    Box::new(family),
+                )]
+            }
+        }
+
+        let mut registry = <Registry>::default();
+        assert_eq!(0, registry.families_len());
+        assert_eq!(0, registry.series_len());
+
+        registry.register(
+            "my_counter",
+            "My counter",
+            Box::new(Counter::<u64>::default()),
+        );
+        registry.sub_registry_with_prefix("my_prefix").register(
+            "my_empty_family",
+            "My empty family",
+            Box::new(Family::<Vec<(String, String)>, Counter>::default()),
+        );
+        registry.register_collector(Box::new(MyCollector));
+
+        assert_eq!(2, registry.families_len());
+        assert_eq!(3, registry.series_len());
+        assert_eq!(
+            registry.families_len(),
+            registry.snapshot().unwrap().families().len()
+        );
+    }
+
+    #[test]
+    fn clear() {
+        struct MyCollector;
+
+        impl Collector for MyCollector {
+            fn collect(&self) -> Vec<(Descriptor, Box<dyn EncodeMetric>)> {
+                vec![(
+                    Descriptor::new("my_gauge", "My gauge", None, vec![]),
+                    Box::new(crate::metrics::gauge::Gauge::<u64>::default()),
+                )]
+            }
+        }
+
+        let mut registry = <Registry>::default();
+        registry.register(
+            "my_counter",
+            "My counter",
+            Box::new(Counter::<u64>::default()),
+        );
+        registry.register_collector(Box::new(MyCollector));
+        registry.sub_registry_with_prefix("my_prefix").register(
+            "my_counter",
+            "My counter",
+            Box::new(Counter::<u64>::default()),
+        );
+
+        registry.clear();
+        assert_eq!(0, registry.iter().count());
+        assert_eq!(0, registry.iter_collectors().count());
+
+        let mut encoded = Vec::new();
+        crate::encoding::text::encode(&mut encoded, &registry).unwrap();
+        assert_eq!("# EOF\n", String::from_utf8(encoded).unwrap());
+
+        // Names can be registered again.
+        registry.register(
+            "my_counter",
+            "My counter",
+            Box::new(Counter::<u64>::default()),
+        );
+    }
+
+    #[test]
+    fn clear_sub_registry() {
+        let mut registry = Registry::<Counter>::default();
+        let sub_registry = registry
+            .sub_registry_with_prefix("my_prefix")
+            .sub_registry_with_label((Cow::Borrowed("my_key"), Cow::Borrowed("my_value")));
+        sub_registry.register("my_counter", "My counter", Default::default());
+
+        sub_registry.clear();
+        sub_registry.register("my_counter", "My counter", Default::default());
+        let (desc, _) = registry.iter().next().unwrap();
+        assert_eq!("my_counter", desc.name());
+        assert!(desc.labels().is_empty());
+
+        registry.register("my_prefix_my_counter", "My counter", Default::default());
+        assert_eq!(2, registry.iter().count());
+    }
+
+    #[test]
+    fn register_metrics_macro() {
+        let mut registry = Registry::<Counter>::default();
+        crate::register_metrics!(
+            registry,
+            ("my_counter", "My counter", Counter::default()),
+            ("my_other_counter", "My other counter", Counter::default())
+        );
+        assert_eq!(2, registry.iter().count());
+
+        assert!(str_eq("my_counter", "my_counter"));
+        assert!(!str_eq("my_counter", "my_counters"));
+    }
+
+    #[test]
+    fn shared_registry_register_concurrently() {
+        let registry = Registry::<Counter>::new_shared();
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    registry.register(
+                        format!("my_counter_{}", i),
+                        "My counter",
+                        Counter::default(),
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(4, registry.lock().iter().count());
+        assert!(registry.unregister("my_counter_0"));
+        assert_eq!(3, registry.lock().iter().count());
+    }
+
+    #[test]
+    fn shared_registry_usable_after_duplicate_registration() {
+        let registry = Registry::<Counter>::new_shared();
+        registry.register("my_counter", "My counter", Counter::default());
+
+        let r = registry.clone();
+        let result = std::thread::spawn(move || {
+            r.register("my_counter", "My other counter", Counter::default())
+        })
+        .join();
+        assert!(result.is_err());
+
+        let error = registry
+            .try_register("my_counter", "My other counter", Counter::default())
+            .unwrap_err();
+        assert_eq!("my_counter", error.name());
+        registry.register("my_other_counter", "My other counter", Counter::default());
+        assert_eq!(2, registry.lock().iter().count());
+
+        // Recover even if a thread panics while holding the guard.
+        let r = registry.clone();
+        let result = std::thread::spawn(move || {
+            let _guard = r.lock();
+            panic!("Panic while holding the lock.");
+        })
+        .join();
+        assert!(result.is_err());
+        assert_eq!(2, registry.lock().iter().count());
+    }
+
+    #[test]
+    fn valid_names() {
+        validate_metric_name("my_metric:total");
+        validate_metric_name(":my_metric2");
+        validate_label_name("_my_label2");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Invalid metric name \"my-metric\": invalid character '-' at position 2."
+    )]
+    fn register_invalid_metric_name() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("my-metric", "My metric", Default::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid label name \"le\": name is reserved.")]
+    fn register_reserved_label_name() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry
+            .sub_registry_with_label((Cow::Borrowed("le"), Cow::Borrowed("1.0")))
+            .register("my_metric", "My metric", Default::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric \"prometheus_client_scrapes\" registered twice.")]
+    fn register_self_metric_name() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.enable_self_metrics();
+        registry.register("prometheus_client_scrapes", "My metric", Default::default());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Metric \"my_prefix_prometheus_client_scrape_duration_seconds\" registered twice."
+    )]
+    fn enable_self_metrics_with_registered_name() {
+        let mut registry: Registry<Counter> = Registry::default();
+        let sub_registry = registry.sub_registry_with_prefix("my_prefix");
+        sub_registry.register_with_unit(
+            "prometheus_client_scrape_duration",
+            "My metric",
+            Unit::Seconds,
+            Default::default(),
+        );
+        sub_registry.enable_self_metrics();
+    }
+
+    #[test]
+    fn clear_releases_self_metric_names() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.enable_self_metrics();
+        registry.clear();
+        assert!(registry
+            .try_register("prometheus_client_scrapes", "My metric", Default::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn register_name_with_unit_suffix() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register_with_unit(
+            "my_metric_seconds",
+            "My metric",
+            Unit::Seconds,
+            Default::default(),
+        );
+        registry.register_with_unit("my_bytes", "My bytes", Unit::Bytes, Default::default());
+
+        let names: Vec<_> = registry
+            .iter()
+            .map(|(desc, _)| (desc.name().to_owned(), desc.full_name()))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "my_metric_seconds".to_owned(),
+                    "my_metric_seconds".to_owned()
+                ),
+                ("my_bytes".to_owned(), "my_bytes".to_owned()),
+            ],
+            names
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric \"my_metric_seconds\" registered twice.")]
+    fn register_name_with_and_without_unit_suffix() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register_with_unit("my_metric", "My metric", Unit::Seconds, Default::default());
+        registry.register_with_unit(
+            "my_metric_seconds",
+            "My metric",
+            Unit::Seconds,
+            Default::default(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric \"my_counter\" registered twice.")]
+    fn register_name_with_and_without_total_suffix() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("my_counter", "My counter", Default::default());
+        registry.register("my_counter_total", "My counter", Default::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric \"my_metric\" registered twice.")]
+    fn register_gauge_colliding_with_counter_total() {
+        let mut registry = <Registry>::default();
+        registry.register("my_metric", "My gauge", Box::new(Gauge::<u64>::default()));
+        registry.register(
+            "my_metric_total",
+            "My counter",
+            Box::new(Counter::<u64>::default()),
+        );
+    }
+
+    #[test]
+    fn register_with_strict_units() {
+        let mut registry: Registry<Counter> = Registry::builder().strict_units().build();
+        registry
+            .sub_registry_with_prefix("my_prefix")
+            .register_with_unit(
+                "my_metric_seconds",
+                "My metric",
+                Unit::Seconds,
+                Default::default(),
+            );
+
+        let names: Vec<_> = registry.iter().map(|(desc, _)| desc.full_name()).collect();
+        assert_eq!(vec!["my_prefix_my_metric_seconds".to_owned()], names);
+
+        assert!(registry.get("my_prefix_my_metric_seconds").is_some());
+        assert!(registry.get_mut("my_prefix_my_metric_seconds").is_some());
+        assert!(registry.get("my_prefix_my_metric").is_none());
+        assert!(registry.unregister("my_prefix_my_metric_seconds"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Invalid metric name \"my_metric\": name must end with its unit \"seconds\"."
+    )]
+    fn register_with_strict_units_missing_suffix() {
+        let mut registry: Registry<Counter> = Registry::builder().strict_units().build();
+        registry
+            .sub_registry_with_label((Cow::Borrowed("my_key"), Cow::Borrowed("my_value")))
+            .register_with_unit("my_metric", "My metric", Unit::Seconds, Default::default());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn register_empty_help_warns() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts the warnings emitted.
+        #[derive(Default)]
+        struct Warnings(AtomicUsize);
+
+        impl tracing::Subscriber for Warnings {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+                tracing::span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+            fn event(&self, event: &tracing::Event<'_>) {
+                if *event.metadata().level() == tracing::Level::WARN {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            fn enter(&self, _: &tracing::span::Id) {}
+
+            fn exit(&self, _: &tracing::span::Id) {}
+        }
+
+        let warnings = Arc::new(Warnings::default());
+        tracing::subscriber::with_default(warnings.clone(), || {
+            let mut registry: Registry<Counter> = Registry::default();
+            registry.register("my_metric", "My metric", Default::default());
+            assert_eq!(0, warnings.0.load(Ordering::Relaxed));
+            registry.register("my_other_metric", " ", Default::default());
+            assert_eq!(1, warnings.0.load(Ordering::Relaxed));
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid metric name \"2xx\": invalid character '2' at position 0.")]
+    fn register_metric_name_starting_with_digit() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("2xx", "My metric", Default::default());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Invalid label name \"my:label\": invalid character ':' at position 2."
+    )]
+    fn register_invalid_label_name() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register_with_labels(
+            "my_metric",
+            "My metric",
+            [("my:label", "value")],
+            Default::default(),
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Metric \"my_prefix_my_counter\" registered twice. Help of the existing metric: \"First counter.\". Help of the new metric: \"Second counter.\"."
+    )]
+    fn register_duplicate_name_via_sub_registries() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.sub_registry_with_prefix("my_prefix").register(
+            "my_counter",
+            "First counter",
+            Default::default(),
+        );
+        registry
+            .sub_registry_with_label((Cow::Borrowed("my_key"), Cow::Borrowed("my_value")))
+            .sub_registry_with_prefix("my_prefix")
+            .register("my_counter", "Second counter", Default::default());
+    }
+
+    #[test]
+    fn register_same_name_different_unit() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("my_counter", "My counter", Default::default());
+        registry.register_with_unit(
+            "my_counter",
+            "My counter",
+            Unit::Seconds,
+            Default::default(),
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("my_counter", "My counter", Default::default());
+
+        let mut other = Registry::builder()
+            .prefix("other")
+            .const_label("my_key", "my_value")
+            .build();
+        other.register("my_counter", "My counter", Default::default());
+        other.sub_registry_with_prefix("sub").register(
+            "my_counter",
+            "My counter",
+            Default::default(),
+        );
+        registry.merge(other).unwrap();
+
+        let metrics: Vec<_> = registry
+            .iter()
+            .map(|(desc, _)| (desc.name().to_owned(), desc.labels().to_vec()))
+            .collect();
+        let labels = vec![(Cow::Borrowed("my_key"), Cow::Borrowed("my_value"))];
+        assert_eq!(
+            vec![
+                ("my_counter".to_owned(), vec![]),
+                ("other_my_counter".to_owned(), labels.clone()),
+                ("other_sub_my_counter".to_owned(), labels),
+            ],
+            metrics
+        );
+
+        // Names of the merged registry are shared to detect duplicates.
+        let mut other: Registry<Counter> = Registry::default();
+        other.register("other_sub_my_counter", "My counter", Default::default());
+        other.register("my_other_counter", "My other counter", Default::default());
+        other.register("my_counter", "My counter", Default::default());
+        assert_eq!(
+            vec!["my_counter".to_owned(), "other_sub_my_counter".to_owned()],
+            registry.merge(other).unwrap_err().names()
+        );
+        assert_eq!(3, registry.iter().count());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metric \"other_my_counter\" registered twice.")]
+    fn register_after_merge() {
+        let mut registry: Registry<Counter> = Registry::default();
+        let mut other = Registry::with_prefix("other");
+        other.register("my_counter", "My counter", Default::default());
+        registry.merge(other).unwrap();
+
+        registry.register("other_my_counter", "My counter", Default::default());
+    }
+
+    #[test]
+    fn register_after_unregister() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("my_counter", "My counter", Default::default());
+        assert!(registry.unregister("my_counter"));
+        registry.register("my_counter", "My counter", Default::default());
+    }
+
+    #[test]
+    fn unregister() {
+        let mut registry: Registry<Counter> = Registry::default();
+        registry.register("my_counter", "My counter", Default::default());
+        registry.register("my_other_counter", "My other counter", Default::default());
+        let sub_registry = registry.sub_registry_with_prefix("my_prefix");
+        sub_registry.register("my_counter", "My counter", Default::default());
+
+        assert!(registry.unregister("my_counter"));
+        assert!(!registry.unregister("my_counter"));
+        assert!(registry.unregister("my_prefix_my_counter"));
+
+        let names: Vec<_> = registry.iter().map(|(desc, _)| desc.name.clone()).collect();
+        assert_eq!(vec!["my_other_counter".to_string()], names);
+    }
+
     #[test]
     fn sub_registry_with_prefix_and_label() {
         let top_level_metric_name = "my_top_level_metric";
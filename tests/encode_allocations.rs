@@ -0,0 +1,59 @@
+//! Counts the allocations of encoding a [`Family`] with many series.
+//!
+//! Lives in its own test binary, as it replaces the global allocator.
+
+use prometheus_client::encoding::text::{encode, EncodeMetric};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::registry::Registry;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Allocator counting the number of allocations.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Encoding a family of 10k series into a reused buffer does not allocate per
+/// series, as the label sets are encoded into a single shared buffer.
+#[test]
+fn encode_family_does_not_allocate_per_series() {
+    const SERIES: usize = 10_000;
+
+    let mut registry = Registry::<Box<dyn EncodeMetric>>::default();
+    let family = Family::<Vec<(&'static str, String)>, Counter>::default();
+    registry.register("my_counter", "My counter", Box::new(family.clone()));
+    for i in 0..SERIES {
+        family.get_or_create(&vec![("id", i.to_string())]).inc();
+    }
+
+    // Encode once upfront, growing the buffer to its final size.
+    let mut buffer = vec![];
+    encode(&mut buffer, &registry).unwrap();
+
+    buffer.clear();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    encode(&mut buffer, &registry).unwrap();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    assert!(
+        allocations < SERIES / 10,
+        "{} allocations encoding {} series",
+        allocations,
+        SERIES
+    );
+}
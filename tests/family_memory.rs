@@ -0,0 +1,78 @@
+//! Measures the memory footprint of a [`Family`] with and without an
+//! [`Interner`].
+//!
+//! Lives in its own test binary, as it replaces the global allocator.
+
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::{Family, Interner};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Allocator tracking the number of bytes currently allocated.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A family of 50k series sharing their label names and most of their label
+/// values allocates less with an [`Interner`] than with owned label sets.
+#[test]
+fn interned_family_allocates_less() {
+    const SERIES: usize = 50_000;
+
+    fn label_values(i: usize) -> [String; 3] {
+        [
+            ["GET", "PUT", "POST"][i % 3].to_owned(),
+            ["200", "404", "500"][i % 7 % 3].to_owned(),
+            format!("instance-{}", i),
+        ]
+    }
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let owned = Family::<Vec<(String, String)>, Counter>::default();
+    for i in 0..SERIES {
+        let [method, status, instance] = label_values(i);
+        owned.get_or_create(&vec![
+            ("method".to_owned(), method),
+            ("status".to_owned(), status),
+            ("instance".to_owned(), instance),
+        ]);
+    }
+    let owned_bytes = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let interner = Interner::default();
+    let interned = Family::<Vec<(Arc<str>, Arc<str>)>, Counter>::default();
+    for i in 0..SERIES {
+        let [method, status, instance] = label_values(i);
+        interned.get_or_create(&vec![
+            (interner.intern("method"), interner.intern(&method)),
+            (interner.intern("status"), interner.intern(&status)),
+            // Unique values don't benefit from interning.
+            (interner.intern("instance"), Arc::from(instance)),
+        ]);
+    }
+    let interned_bytes = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    assert!(
+        interned_bytes < owned_bytes,
+        "{} bytes interned, {} bytes owned",
+        interned_bytes,
+        owned_bytes
+    );
+}